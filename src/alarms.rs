@@ -0,0 +1,125 @@
+use crate::aws::Clients;
+use crate::config::AlarmsConfig;
+use rusoto_cloudwatch::{CloudWatch, DescribeAlarmsInput};
+
+use std::error::Error;
+
+// what one of the service's alarms is saying right now
+#[derive(Clone, Debug)]
+pub struct Alarm {
+    pub name: String,
+    // OK, ALARM or INSUFFICIENT_DATA
+    pub state: String,
+    pub reason: Option<String>,
+}
+
+// which alarms belong to a pipeline's service, and which stage to show them next to
+#[derive(Clone, Debug, Default)]
+pub struct AlarmSource {
+    pub names: Vec<String>,
+    pub prefixes: Vec<String>,
+    pub stage: Option<String>,
+}
+
+impl AlarmSource {
+    // the config's entry for the pipeline, if it has one, plus whatever its alarms tag lists: alarm names
+    // separated by spaces or commas, with a trailing * for every alarm starting with that
+    pub fn for_pipeline(
+        config: &AlarmsConfig,
+        pipeline: &str,
+        tags: &[(String, String)],
+    ) -> Option<AlarmSource> {
+        let mut source = AlarmSource::default();
+        for entry in config
+            .pipelines
+            .iter()
+            .filter(|entry| entry.pipeline == pipeline)
+        {
+            source.names.extend(entry.names.iter().cloned());
+            source.prefixes.extend(entry.prefix.iter().cloned());
+            source.stage = source.stage.or_else(|| entry.stage.clone());
+        }
+        let tagged = tags
+            .iter()
+            .filter(|(key, _)| !config.tag.is_empty() && *key == config.tag)
+            .flat_map(|(_, value)| value.split([' ', ',']))
+            .filter(|name| !name.is_empty());
+        for name in tagged {
+            match name.strip_suffix('*') {
+                Some(prefix) => source.prefixes.push(prefix.to_owned()),
+                None => source.names.push(name.to_owned()),
+            }
+        }
+        if source.names.is_empty() && source.prefixes.is_empty() {
+            None
+        } else {
+            Some(source)
+        }
+    }
+}
+
+// the ones in alarm first, then the ones without enough data to say, then everything that's fine
+pub async fn fetch_alarms(
+    clients: &Clients,
+    source: &AlarmSource,
+) -> Result<Vec<Alarm>, Box<dyn Error + Send + Sync>> {
+    let mut alarms = vec![];
+    // DescribeAlarms takes either a list of names or a prefix, not both
+    let mut queries = source
+        .names
+        .chunks(100)
+        .map(|names| (Some(names.to_vec()), None))
+        .collect::<Vec<_>>();
+    queries.extend(
+        source
+            .prefixes
+            .iter()
+            .map(|prefix| (None, Some(prefix.clone()))),
+    );
+    for (alarm_names, alarm_name_prefix) in queries {
+        let mut next_token = None;
+        loop {
+            let page = clients
+                .cloudwatch
+                .describe_alarms(DescribeAlarmsInput {
+                    alarm_names: alarm_names.clone(),
+                    alarm_name_prefix: alarm_name_prefix.clone(),
+                    alarm_types: Some(vec!["MetricAlarm".to_owned(), "CompositeAlarm".to_owned()]),
+                    next_token,
+                    ..DescribeAlarmsInput::default()
+                })
+                .await?;
+            for alarm in page.metric_alarms.unwrap_or_default() {
+                if let Some(name) = alarm.alarm_name {
+                    alarms.push(Alarm {
+                        name,
+                        state: alarm.state_value.unwrap_or_default(),
+                        reason: alarm.state_reason,
+                    });
+                }
+            }
+            for alarm in page.composite_alarms.unwrap_or_default() {
+                if let Some(name) = alarm.alarm_name {
+                    alarms.push(Alarm {
+                        name,
+                        state: alarm.state_value.unwrap_or_default(),
+                        reason: alarm.state_reason,
+                    });
+                }
+            }
+            next_token = page.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+    }
+    // a name and a prefix can both pick out the same alarm
+    alarms.sort_by(|a, b| a.name.cmp(&b.name));
+    alarms.dedup_by(|a, b| a.name == b.name);
+    alarms.sort_by_key(|alarm| match alarm.state.as_str() {
+        "ALARM" => 0,
+        "INSUFFICIENT_DATA" => 1,
+        _ => 2,
+    });
+    Ok(alarms)
+}
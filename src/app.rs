@@ -0,0 +1,2005 @@
+use crate::alarms::Alarm;
+use crate::aws::{
+    short_id, ActionExecutionDetailV2, ActionRuns, ExecutionMode, Pipeline, PipelineDeclarationV2,
+    PipelineListing, ResolvedVariable, StageConditions, StageStateV2, VariableDeclaration,
+};
+use crate::calls::CallLog;
+use crate::config::{
+    ConfirmConfig, LayoutConfig, LogConfig, Pane, PaneLayout, SlaConfig, StagesConfig,
+};
+use crate::console;
+use crate::credentials;
+use crate::deploy::{DeploymentProgress, DeploymentSource};
+use crate::git::{CommitInfo, SourceChange};
+use crate::history::Bookmark;
+use crate::logview::LogView;
+use crate::macros::{Macro, Step};
+use crate::poller::Executions;
+use crate::provenance::ActionNode;
+use crate::stats::{StageComparison, StageStats, ANOMALY_FACTOR};
+use crate::theme::Theme;
+use crate::watchlist::Watchlist;
+use chrono::{DateTime, Local};
+use crossterm::event::KeyCode;
+use rusoto_cloudformation::StackEvent;
+use rusoto_codebuild::Build;
+use rusoto_codepipeline::{
+    ActionDeclaration, ActionState, PipelineDeclaration, PipelineExecutionSummary, SourceRevision,
+};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum View {
+    // picking which pipeline to look at
+    Pipelines,
+    // every pipeline as a colored cell, for a dashboard on the wall
+    Fleet,
+    Stages,
+    History,
+    // success rates and durations per stage over recent executions
+    Stats,
+    // how the source revisions differ between two executions picked in the history view
+    Compare,
+    // which action produced and consumed each artifact of the selected execution
+    Provenance,
+    // when each action of the selected execution ran, as bars along a shared time axis
+    Timeline,
+    // details for the selected action
+    Action,
+    // the output of the selected action's CodeBuild run
+    BuildLog,
+    // what we've logged ourselves this session
+    AppLog,
+    // every notification so far this session, for catching up after stepping away
+    Notifications,
+    // the requests and responses of the latest AWS calls, for working out why something isn't showing
+    Inspector,
+    // executions of any pipeline bookmarked for finding again later
+    Bookmarks,
+}
+
+// things a keypress wants done that mean going back to AWS, which the main loop takes care of
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    // startup, a step at a time so each one's drawn as soon as it's in: the account and pipeline names, then
+    // the selector's tags and statuses
+    LoadPipelines,
+    LoadListings,
+    // list the fleet's pipelines again and poll them all now, without holding up the keys
+    RefreshFleet,
+    OpenPipeline(String),
+    // open the pipeline following one execution, for --execution-id
+    Follow {
+        pipeline: String,
+        execution_id: String,
+    },
+    // start a new execution with these variable values
+    StartExecution(Vec<(String, String)>),
+    // find an earlier execution the stage could go back to, so it can be confirmed
+    PlanRollback(String),
+    Rollback {
+        stage: String,
+        target: String,
+    },
+    EnableTransition(String),
+    DisableTransition {
+        stage: String,
+        reason: String,
+    },
+    // answer the manual approval waiting in the stage, the comment shown with it on the execution
+    Approve {
+        stage: String,
+        action: String,
+        comment: String,
+    },
+    Reject {
+        stage: String,
+        action: String,
+        comment: String,
+    },
+    // run the stage's failed actions again
+    RetryStage(String),
+    // stop the running execution, letting what's already going finish
+    StopExecution {
+        execution_id: String,
+        reason: Option<String>,
+    },
+    LoadStats,
+    // show the stages as one of a parallel pipeline's running executions sees them, or with none the latest
+    // state again
+    Focus(Option<String>),
+    // skip the cache and refresh straight away
+    Refresh,
+    // run the sign-in command in the terminal, the dashboard put away until it's done
+    Reauthenticate,
+    LoadProvenance(String),
+    LoadTimeline(String),
+    LoadStageActions(String),
+    LoadBuildLog(String),
+    Copy(String),
+    OpenUrl(String),
+    LoadBookmarks,
+    // kept in the history database, and out of its pruning
+    Bookmark {
+        pipeline: String,
+        execution_id: String,
+        bookmarked: bool,
+    },
+    // a note on the execution kept in the history database, an empty one taking the note off
+    Annotate {
+        execution_id: String,
+        note: String,
+    },
+    // base is the older of the two executions, head the newer
+    Compare {
+        base: String,
+        head: String,
+    },
+    Load(Detail),
+}
+
+impl Command {
+    // the ones that change the pipeline rather than just looking at it, which read-only turns off
+    pub fn mutates(&self) -> bool {
+        matches!(
+            self,
+            Command::StartExecution(_)
+                | Command::PlanRollback(_)
+                | Command::Rollback { .. }
+                | Command::EnableTransition(_)
+                | Command::DisableTransition { .. }
+                | Command::Approve { .. }
+                | Command::Reject { .. }
+                | Command::RetryStage(_)
+                | Command::StopExecution { .. }
+        )
+    }
+}
+
+// the extra information the action detail view can show, depending on the kind of action
+#[derive(Clone, Debug, PartialEq)]
+pub enum Detail {
+    Build(String),
+    StackEvents(String),
+    Deployment(DeploymentSource),
+    // earlier runs of a manual approval action, so we can say who approved them
+    Approvals { stage: String, action: String },
+}
+
+// everything the UI needs to draw a frame
+pub struct App {
+    // the account the credentials are for, which starts the breadcrumbs
+    pub account: String,
+    // the region the pipelines live in, which cross-region actions differ from
+    pub region: String,
+    // everything the selector can offer, already filtered by --tag
+    pub pipelines: Vec<PipelineListing>,
+    // every --tag, which a pipeline has to have all of to be listed
+    pub tag_filter: Vec<(String, String)>,
+    // from --watchlist, the only pipelines listed
+    pub watchlist: Option<Watchlist>,
+    // the stages the config or --hide-stage/--only-stage leave off the dashboard
+    pub stage_filter: StagesConfig,
+    // what signs in again once the credentials have expired, and whether they have
+    pub reauth: String,
+    pub credentials_expired: bool,
+    // still finding out what the selector should show, the first frame goes up before any of it has arrived
+    pub loading: bool,
+    // when the listings last finished loading, the fleet view keeps them fresh
+    pub listed_at: Option<Instant>,
+    pub selected_pipeline: usize,
+    // how many cells fit across the fleet grid as it was last drawn, for moving up and down it
+    pub fleet_columns: usize,
+    // the pipeline we're looking at, empty until one has been opened
+    pub pipeline: Pipeline,
+    pub definition: PipelineDeclaration,
+    // whether executions replace, queue behind or run alongside each other, which changes what the stages can show
+    pub execution_mode: ExecutionMode,
+    // the rules each stage checks on the way in and out, which the definition has and rusoto doesn't
+    pub stage_conditions: Vec<StageConditions>,
+    // with --execution-id, the execution the stages show instead of whatever ran through them last
+    pub pinned_execution: Option<String>,
+    // the pipeline-level variables the definition declares, which every start has to fill in
+    pub variable_declarations: Vec<VariableDeclaration>,
+    // the latest execution's ID along with the values its variables resolved to
+    pub execution_variables: Option<(String, Vec<ResolvedVariable>)>,
+    // a popup over the current view, while it's open it gets every keypress
+    pub modal: Option<Modal>,
+    // everything we've told the user about this session, oldest first, the recent ones also show in the corner
+    pub toasts: Vec<Toast>,
+    pub toast_duration: Duration,
+    // the panes of the stages view, from the config
+    pub layout: Vec<PaneLayout>,
+    // which of those panes tab has got to, and whether it's blown up to fill the screen
+    pub focused_pane: usize,
+    pub zoomed: bool,
+    // the built-in themes and any from the config, 'c' moves on to the next
+    pub themes: Vec<Theme>,
+    pub selected_theme: usize,
+    pub view: View,
+    // the execution picked out in the history view, and the one marked for comparing against it
+    pub selected_execution: usize,
+    pub marked_execution: Option<usize>,
+    // which stage and which action inside it the arrow keys have picked out
+    pub selected_stage: usize,
+    // with the problem filter on anything that's green is hidden, leaving only what needs looking at
+    pub problems_only: bool,
+    pub selected_action: usize,
+    // how the action table in the detail view is sorted
+    pub action_sort: ActionColumn,
+    pub action_sort_descending: bool,
+    // every action run in the selected stage's latest execution, keyed by that execution's ID, for the action table
+    pub stage_actions: Option<(String, Vec<ActionExecutionDetailV2>)>,
+    // the CodeBuild run behind the selected action, if it's a build action
+    pub build: Option<Build>,
+    // as much of that build's log as we've fetched so far
+    pub build_log: Option<BuildLog>,
+    pub app_log: Vec<String>,
+    // scrolling and searching in whichever of those two logs is open
+    pub log_view: LogView,
+    // recent events for the stack behind the selected action, if it's a CloudFormation action
+    pub stack_events: Option<Vec<StackEvent>>,
+    // rollout progress for the selected action, if it's a CodeDeploy or ECS action
+    pub deployment: Option<DeploymentProgress>,
+    // previous runs of the selected action, if it's a manual approval
+    pub approvals: Option<ActionRuns>,
+    // per-stage statistics, loaded when the pipeline is opened and kept up to date by the stats view
+    pub stats: Option<Vec<StageStats>>,
+    // how many days of history they cover, when we're keeping history rather than going by the last few runs
+    pub stats_days: Option<u32>,
+    // the two executions being compared, older first, what changed in their sources and how long each of
+    // their stages took, or why the sources can't be compared
+    pub compared: Option<(String, String)>,
+    pub comparison: Option<Result<Vec<SourceChange>, String>>,
+    // what's been noted down about the pipeline's executions, by execution ID
+    pub notes: HashMap<String, String>,
+    // every pipeline's, the latest first
+    pub bookmarks: Vec<Bookmark>,
+    pub selected_bookmark: usize,
+    pub stage_comparison: Option<Result<Vec<StageComparison>, String>>,
+    // the artifact chain of the selected execution, starting from the actions with no inputs
+    pub provenance: Option<Vec<ActionNode>>,
+    // every action execution of the selected execution, for the timeline
+    pub timeline: Option<Vec<ActionExecutionDetailV2>>,
+    // when each running stage's current run started, keyed by execution ID and stage name
+    pub stage_starts: HashMap<(String, String), f64>,
+    // commit details for every revision we've looked up so far, keyed by revision ID
+    pub commits: HashMap<String, CommitInfo>,
+    // the state of the service's alarms, when the pipeline has any
+    pub alarms: Option<Vec<Alarm>>,
+    // the stage the config says to show them next to
+    pub alarm_stage: Option<String>,
+    // when they were last looked at, they're kept fresh alongside the pipeline
+    pub alarms_at: Option<Instant>,
+    // --read-only or the config's read_only, which turns off everything that changes the pipeline
+    pub read_only: bool,
+    // which pipelines are production, and what has to be typed out before changing them
+    pub confirm: ConfirmConfig,
+    // how long each stage is allowed to run, and the stage runs (execution ID and stage name) that have already
+    // been alerted on for going over
+    pub sla: SlaConfig,
+    pub sla_alerted: HashSet<(String, String)>,
+    // the config's macros, and the steps left of the one that's running along with its name
+    pub macros: Vec<Macro>,
+    pub macro_steps: VecDeque<Step>,
+    pub running_macro: Option<String>,
+    // F12's overlay of the AWS calls we've been making, and the log it's drawn from
+    pub debug: bool,
+    pub calls: CallLog,
+    // the most recent refresh error, cleared by the next successful refresh
+    pub last_error: Option<String>,
+    pub should_quit: bool,
+}
+
+impl App {
+    pub fn new(account: String, region: String, pipelines: Vec<PipelineListing>) -> App {
+        App {
+            account,
+            region,
+            pipelines,
+            tag_filter: vec![],
+            watchlist: None,
+            stage_filter: StagesConfig::default(),
+            reauth: String::new(),
+            credentials_expired: false,
+            loading: false,
+            listed_at: None,
+            selected_pipeline: 0,
+            fleet_columns: 1,
+            pipeline: Pipeline::default(),
+            definition: PipelineDeclaration::default(),
+            pinned_execution: None,
+            execution_mode: ExecutionMode::default(),
+            stage_conditions: vec![],
+            variable_declarations: vec![],
+            execution_variables: None,
+            modal: None,
+            toasts: vec![],
+            toast_duration: Duration::from_secs(5),
+            layout: LayoutConfig::default().panes,
+            focused_pane: 0,
+            zoomed: false,
+            themes: vec![Theme::dark(), Theme::light(), Theme::high_contrast()],
+            selected_theme: 0,
+            view: View::Pipelines,
+            selected_execution: 0,
+            marked_execution: None,
+            selected_stage: 0,
+            problems_only: false,
+            selected_action: 0,
+            // pipeline order, which is what the stage view shows
+            action_sort: ActionColumn::None,
+            action_sort_descending: false,
+            stage_actions: None,
+            build: None,
+            build_log: None,
+            app_log: vec![],
+            log_view: LogView::default(),
+            stack_events: None,
+            deployment: None,
+            approvals: None,
+            stats: None,
+            stats_days: None,
+            compared: None,
+            comparison: None,
+            notes: HashMap::new(),
+            bookmarks: vec![],
+            selected_bookmark: 0,
+            stage_comparison: None,
+            provenance: None,
+            timeline: None,
+            stage_starts: HashMap::new(),
+            commits: HashMap::new(),
+            alarms: None,
+            alarm_stage: None,
+            alarms_at: None,
+            last_error: None,
+            read_only: false,
+            confirm: ConfirmConfig::default(),
+            sla: SlaConfig::default(),
+            sla_alerted: HashSet::new(),
+            macros: vec![],
+            macro_steps: VecDeque::new(),
+            running_macro: None,
+            debug: false,
+            calls: CallLog::default(),
+            should_quit: false,
+        }
+    }
+
+    // swap in a different pipeline, forgetting everything we knew about the old one
+    pub fn open_pipeline(&mut self, pipeline: Pipeline, definition: PipelineDeclarationV2) {
+        self.pipeline = pipeline;
+        self.definition = definition.declaration;
+        self.variable_declarations = definition.variables;
+        self.execution_mode = definition.execution_mode;
+        self.stage_conditions = definition.conditions;
+        // an execution ID only means anything for the pipeline it came from
+        self.pinned_execution = None;
+        self.execution_variables = None;
+        self.modal = None;
+        self.view = View::Stages;
+        // the first stage may well be the hidden Source
+        self.selected_stage = self.visible_stages().first().copied().unwrap_or(0);
+        self.selected_action = 0;
+        self.stage_actions = None;
+        self.build = None;
+        self.stack_events = None;
+        self.deployment = None;
+        self.approvals = None;
+        self.stats = None;
+        self.selected_execution = 0;
+        self.marked_execution = None;
+        self.compared = None;
+        self.comparison = None;
+        self.stage_comparison = None;
+        self.notes.clear();
+        self.provenance = None;
+        self.timeline = None;
+        self.stage_starts.clear();
+        self.sla_alerted.clear();
+        self.commits.clear();
+        self.alarms = None;
+        self.alarm_stage = None;
+        self.alarms_at = None;
+    }
+
+    // only for a stage that has any
+    pub fn conditions(&self, stage: &str) -> Option<&StageConditions> {
+        self.stage_conditions
+            .iter()
+            .find(|conditions| conditions.name == stage)
+            .filter(|conditions| !conditions.is_empty())
+    }
+
+    // where the alarms go: the configured stage, or the last one that deploys something
+    pub fn alarm_stage_name(&self) -> Option<&str> {
+        if let Some(stage) = &self.alarm_stage {
+            return Some(stage);
+        }
+        self.definition
+            .stages
+            .iter()
+            .rev()
+            .find(|stage| {
+                stage
+                    .actions
+                    .iter()
+                    .any(|action| action.action_type_id.category == "Deploy")
+            })
+            .map(|stage| stage.name.as_str())
+    }
+
+    pub fn toast(&mut self, level: ToastLevel, message: String) {
+        self.toasts.push(Toast {
+            message,
+            level,
+            at: Local::now(),
+            popup: true,
+        });
+    }
+
+    // for the notifications view only, some things happen too often to pop up every time
+    pub fn note(&mut self, level: ToastLevel, message: String) {
+        self.toasts.push(Toast {
+            message,
+            level,
+            at: Local::now(),
+            popup: false,
+        });
+    }
+
+    // the toasts still young enough to be in the corner, oldest first
+    pub fn visible_toasts(&self) -> Vec<&Toast> {
+        let cutoff = Local::now()
+            - chrono::Duration::from_std(self.toast_duration)
+                .unwrap_or_else(|_| chrono::Duration::zero());
+        let mut toasts = self
+            .toasts
+            .iter()
+            .rev()
+            .take_while(|toast| toast.at > cutoff)
+            .filter(|toast| toast.popup)
+            .take(MAX_VISIBLE_TOASTS)
+            .collect::<Vec<_>>();
+        toasts.reverse();
+        toasts
+    }
+
+    // every notification this session as a line of the notifications view, oldest first like any other log
+    pub fn notification_lines(&self) -> Vec<String> {
+        self.toasts
+            .iter()
+            .map(|toast| format!("{}  {}", toast.at.format("%H:%M:%S"), toast.message))
+            .collect()
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.themes
+            .get(self.selected_theme)
+            .cloned()
+            .unwrap_or_else(Theme::dark)
+    }
+
+    // every --tag has to be present on the pipeline with exactly that value
+    pub fn matches_tags(&self, tags: &[(String, String)]) -> bool {
+        self.tag_filter.iter().all(|wanted| tags.contains(wanted))
+    }
+
+    // expired credentials fail every call the same way until someone signs in again, so rather than an error
+    // each time round it's the one popup offering to, and a footer saying how to get it back. True when
+    // they've only just expired
+    pub fn check_credentials(&mut self) -> bool {
+        if !self.last_error.as_deref().is_some_and(credentials::expired) {
+            return false;
+        }
+        self.last_error = Some(EXPIRED_CREDENTIALS.to_owned());
+        if self.credentials_expired {
+            return false;
+        }
+        self.credentials_expired = true;
+        if self.modal.is_none() {
+            self.modal = Some(Modal::Reauth);
+        }
+        true
+    }
+
+    // everything's watched without a --watchlist
+    pub fn watching(&self, name: &str) -> bool {
+        self.watchlist
+            .as_ref()
+            .is_none_or(|watchlist| watchlist.matches(name))
+    }
+
+    pub fn has_pipeline(&self) -> bool {
+        !self.pipeline.name.is_empty()
+    }
+
+    // up one level of the breadcrumbs, and out altogether from the top
+    fn back(&mut self) {
+        self.view = match self.view {
+            View::Pipelines | View::Fleet => {
+                self.should_quit = true;
+                return;
+            }
+            View::Bookmarks if !self.has_pipeline() => View::Pipelines,
+            View::BuildLog => View::Action,
+            View::Compare | View::Provenance | View::Timeline => View::History,
+            _ if !self.has_pipeline() => View::Pipelines,
+            View::Stages => View::Pipelines,
+            View::History
+            | View::Stats
+            | View::Action
+            | View::AppLog
+            | View::Notifications
+            | View::Inspector
+            | View::Bookmarks => View::Stages,
+        };
+    }
+
+    // where we are: account, pipeline, execution, stage and action, as far down as the view goes
+    pub fn breadcrumbs(&self) -> Vec<String> {
+        let account = if self.account.is_empty() {
+            "…"
+        } else {
+            self.account.as_str()
+        };
+        let mut crumbs = vec![format!("Account {} ({})", account, self.region)];
+        // they're from every pipeline, not the open one
+        if self.view == View::Bookmarks {
+            crumbs.push(self.view.title().to_owned());
+            return crumbs;
+        }
+        if matches!(self.view, View::Pipelines | View::Fleet) || !self.has_pipeline() {
+            return crumbs;
+        }
+        crumbs.push(self.pipeline.name.clone());
+        match self.view {
+            View::History | View::Stats | View::AppLog | View::Notifications | View::Inspector => {
+                crumbs.push(self.view.title().to_owned())
+            }
+            View::Compare | View::Provenance | View::Timeline => {
+                if let Some(id) = self
+                    .pipeline
+                    .executions
+                    .get(self.selected_execution)
+                    .and_then(|execution| execution.pipeline_execution_id.as_deref())
+                {
+                    crumbs.push(format!("Execution {}", short_id(id)));
+                }
+                crumbs.push(self.view.title().to_owned());
+            }
+            View::Action | View::BuildLog => {
+                let stage = match self.selected_stage() {
+                    Some(stage) => stage,
+                    None => return crumbs,
+                };
+                if let Some(execution) = &stage.state.latest_execution {
+                    crumbs.push(format!(
+                        "Execution {}",
+                        short_id(&execution.pipeline_execution_id)
+                    ));
+                }
+                crumbs.extend(stage.state.stage_name.clone());
+                crumbs.extend(
+                    self.selected_action()
+                        .and_then(|action| action.action_name.clone()),
+                );
+                if self.view == View::BuildLog {
+                    crumbs.push(self.view.title().to_owned());
+                }
+            }
+            View::Pipelines | View::Fleet | View::Stages | View::Bookmarks => {}
+        }
+        crumbs
+    }
+
+    // a macro's key takes over from whatever the key did before, except in a popup or while typing a search
+    pub fn on_key(&mut self, key: KeyCode) -> Option<Command> {
+        if self.modal.is_none() && self.log_view.typing.is_none() {
+            if let Some(found) = self.macros.iter().find(|found| found.key == key) {
+                self.running_macro = Some(found.name.clone());
+                self.macro_steps = found.steps.iter().cloned().collect();
+                return None;
+            }
+        }
+        self.press(key)
+    }
+
+    // the running macro's next step, the main loop runs whatever command it comes to before the one after
+    pub fn next_macro_step(&mut self) -> Option<Command> {
+        let step = self.macro_steps.pop_front()?;
+        let command = match step {
+            Step::Key(key) => self.press(key),
+            Step::Pipeline(name) => Some(Command::OpenPipeline(name)),
+            Step::Failed => {
+                if !self.select_failed_action() {
+                    self.stop_macro("nothing's failed");
+                }
+                None
+            }
+        };
+        if self.macro_steps.is_empty() {
+            self.running_macro = None;
+        }
+        command
+    }
+
+    pub fn stop_macro(&mut self, why: &str) {
+        self.macro_steps.clear();
+        if let Some(name) = self.running_macro.take() {
+            self.toast(
+                ToastLevel::Error,
+                format!("Stopped macro {}: {}", name, why),
+            );
+        }
+    }
+
+    // the next or previous of a parallel pipeline's running executions, going round through the latest state in
+    // between the last and the first
+    fn focus_execution(&mut self, offset: isize) -> Option<Command> {
+        let mut choices = vec![None];
+        choices.extend(
+            self.pipeline
+                .concurrent
+                .iter()
+                .map(|concurrent| Some(concurrent.execution_id.clone())),
+        );
+        if choices.len() == 1 {
+            self.toast(ToastLevel::Info, "Nothing's running to focus on".to_owned());
+            return None;
+        }
+        // pinned to something that's finished since, or with --execution-id to something else altogether
+        let current = choices
+            .iter()
+            .position(|choice| *choice == self.pinned_execution)
+            .unwrap_or(0) as isize;
+        let len = choices.len() as isize;
+        let next = (current + offset).rem_euclid(len) as usize;
+        Some(Command::Focus(choices.swap_remove(next)))
+    }
+
+    // on the stages view, the failed action of the first stage down the pipeline that has one
+    fn select_failed_action(&mut self) -> bool {
+        let failed = |status: Option<&str>| status == Some("Failed");
+        for index in self.visible_stages() {
+            let actions = self.pipeline.stage_states[index].state.action_states.iter();
+            let position = actions.flatten().position(|action| {
+                failed(
+                    action
+                        .latest_execution
+                        .as_ref()
+                        .and_then(|execution| execution.status.as_deref()),
+                )
+            });
+            if let Some(position) = position {
+                self.view = View::Stages;
+                self.selected_stage = index;
+                self.selected_action = position;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn press(&mut self, key: KeyCode) -> Option<Command> {
+        if let Some(modal) = &mut self.modal {
+            return match modal.on_key(key) {
+                ModalKey::Keep => None,
+                ModalKey::Dismiss => {
+                    self.modal = None;
+                    None
+                }
+                ModalKey::Confirm => match self.modal.take() {
+                    // only the exact name will do, anything else leaves it open to try again
+                    Some(Modal::Typed(typed)) if typed.typed != typed.expected => {
+                        self.modal = Some(Modal::Typed(typed));
+                        None
+                    }
+                    Some(Modal::Typed(typed)) => Some(typed.command),
+                    modal => {
+                        let command = modal.and_then(Modal::confirm)?;
+                        self.confirmed(command)
+                    }
+                },
+            };
+        }
+
+        // the log views have their own scrolling and search keys, which win over everything but quitting
+        let consumed = match self.view {
+            View::BuildLog => {
+                let lines = self
+                    .build_log
+                    .as_ref()
+                    .map_or(&[][..], |log| &log.lines[..]);
+                self.log_view.on_key(key, lines)
+            }
+            View::AppLog => self.log_view.on_key(key, &self.app_log),
+            View::Notifications => {
+                let lines = self.notification_lines();
+                self.log_view.on_key(key, &lines)
+            }
+            View::Inspector => self.log_view.on_key(key, &self.calls.lines().0),
+            _ => false,
+        };
+        if consumed {
+            return None;
+        }
+
+        match (self.view, key) {
+            (_, KeyCode::Char('q')) => self.should_quit = true,
+            (_, KeyCode::Char('n')) => {
+                self.view = View::Notifications;
+                self.log_view = LogView::default();
+            }
+            // rather than the whole of an error that's only saying the credentials have expired
+            (_, KeyCode::Char('e')) if self.credentials_expired => self.modal = Some(Modal::Reauth),
+            // the footer only has room for the start of an error
+            (_, KeyCode::Char('e')) if self.last_error.is_some() => {
+                self.modal = Some(Modal::Message {
+                    title: "Error".to_owned(),
+                    text: self.last_error.clone().unwrap_or_default(),
+                })
+            }
+            // a stray backspace shouldn't quit, only esc goes past the top
+            (View::Pipelines, KeyCode::Backspace) | (View::Fleet, KeyCode::Backspace) => {}
+            (_, KeyCode::Esc) | (_, KeyCode::Backspace) => self.back(),
+            (View::Fleet, KeyCode::Char('r')) => return Some(Command::RefreshFleet),
+            (_, KeyCode::Char('r')) if self.has_pipeline() => return Some(Command::Refresh),
+            (_, KeyCode::Char('c')) => {
+                self.selected_theme = (self.selected_theme + 1) % self.themes.len().max(1);
+                let name = self.theme().name;
+                self.toast(ToastLevel::Info, format!("Switched to the {} theme", name));
+            }
+            (_, KeyCode::F(12)) => self.debug = !self.debug,
+            (_, KeyCode::Char('L')) => {
+                self.view = View::AppLog;
+                self.log_view = LogView::default();
+            }
+            (_, KeyCode::Char('I')) => {
+                self.view = View::Inspector;
+                self.log_view = LogView::default();
+            }
+            (_, KeyCode::Char('B')) => {
+                self.view = View::Bookmarks;
+                return Some(Command::LoadBookmarks);
+            }
+            (View::Bookmarks, KeyCode::Up) => {
+                self.selected_bookmark = step(self.selected_bookmark, -1, self.bookmarks.len())
+            }
+            (View::Bookmarks, KeyCode::Down) => {
+                self.selected_bookmark = step(self.selected_bookmark, 1, self.bookmarks.len())
+            }
+            (View::Bookmarks, KeyCode::Enter) => {
+                let bookmark = self.bookmarks.get(self.selected_bookmark)?;
+                return Some(Command::Follow {
+                    pipeline: bookmark.pipeline.clone(),
+                    execution_id: bookmark.execution_id.clone(),
+                });
+            }
+            (View::Bookmarks, KeyCode::Char('*')) => {
+                let bookmark = self.bookmarks.get(self.selected_bookmark)?;
+                return Some(Command::Bookmark {
+                    pipeline: bookmark.pipeline.clone(),
+                    execution_id: bookmark.execution_id.clone(),
+                    bookmarked: false,
+                });
+            }
+            (View::Action, KeyCode::Char('l')) => {
+                let command = self.build_log_command()?;
+                self.view = View::BuildLog;
+                self.log_view = LogView::default();
+                return Some(command);
+            }
+            (_, KeyCode::Char('p')) => self.view = View::Pipelines,
+            (_, KeyCode::Char('F')) => self.view = View::Fleet,
+            // the grid reads left to right, so up and down jump a whole row
+            (View::Fleet, KeyCode::Left) | (View::Fleet, KeyCode::Right) => {
+                let offset = if key == KeyCode::Left { -1 } else { 1 };
+                self.selected_pipeline =
+                    step_within(&self.visible_pipelines(), self.selected_pipeline, offset)
+            }
+            (View::Fleet, KeyCode::Up) | (View::Fleet, KeyCode::Down) => {
+                let columns = self.fleet_columns.max(1) as isize;
+                let offset = if key == KeyCode::Up {
+                    -columns
+                } else {
+                    columns
+                };
+                let visible = self.visible_pipelines();
+                // a short last row means there may be nothing straight below, so stop rather than wrapping
+                let position = visible
+                    .iter()
+                    .position(|index| *index == self.selected_pipeline)
+                    .unwrap_or(0) as isize;
+                let target = position + offset;
+                if target >= 0 {
+                    if let Some(index) = visible.get(target as usize) {
+                        self.selected_pipeline = *index;
+                    }
+                }
+            }
+            (View::Pipelines, KeyCode::Up) => {
+                self.selected_pipeline =
+                    step_within(&self.visible_pipelines(), self.selected_pipeline, -1)
+            }
+            (View::Pipelines, KeyCode::Down) => {
+                self.selected_pipeline =
+                    step_within(&self.visible_pipelines(), self.selected_pipeline, 1)
+            }
+            (View::Pipelines, KeyCode::Char('!'))
+            | (View::Fleet, KeyCode::Char('!'))
+            | (View::Stages, KeyCode::Char('!')) => self.toggle_problems_only(),
+            (View::Pipelines, KeyCode::Enter) | (View::Fleet, KeyCode::Enter) => {
+                return self
+                    .pipelines
+                    .get(self.selected_pipeline)
+                    .map(|listing| Command::OpenPipeline(listing.name.clone()))
+            }
+            // there's nothing to show in these until a pipeline has been picked
+            (_, KeyCode::Char('s')) if self.has_pipeline() => self.view = View::Stages,
+            (_, KeyCode::Char('h')) if self.has_pipeline() => self.view = View::History,
+            (_, KeyCode::Char('S'))
+            | (View::Stages, KeyCode::Char('b'))
+            | (View::Stages, KeyCode::Char('T'))
+            | (View::Stages, KeyCode::Char('R'))
+            | (View::Stages, KeyCode::Char('X'))
+            | (View::Stages, KeyCode::Char('A'))
+            | (View::Stages, KeyCode::Char('x'))
+            | (View::Action, KeyCode::Char('A'))
+            | (View::Action, KeyCode::Char('x'))
+                if self.read_only =>
+            {
+                self.toast(
+                    ToastLevel::Info,
+                    "Read-only: changing the pipeline is turned off".to_owned(),
+                )
+            }
+            (_, KeyCode::Char('S')) if self.has_pipeline() => {
+                self.modal = Some(Modal::Form(Form::start_execution(
+                    &self.variable_declarations,
+                )))
+            }
+            (_, KeyCode::Char('t')) if self.has_pipeline() => {
+                self.view = View::Stats;
+                return Some(Command::LoadStats);
+            }
+            (View::Stages, KeyCode::Left) => self.select_stage(-1),
+            (View::Stages, KeyCode::Right) => self.select_stage(1),
+            (View::Stages, KeyCode::Up) => self.select_action(-1),
+            (View::Stages, KeyCode::Down) => self.select_action(1),
+            (View::Stages, KeyCode::Char('b')) => return self.rollback_command(),
+            (View::Stages, KeyCode::Char('T')) => {
+                let command = self.toggle_transition()?;
+                return self.confirmed(command);
+            }
+            (View::Stages, KeyCode::Char('R')) => {
+                let command = self.retry_command()?;
+                return self.confirmed(command);
+            }
+            (View::Stages, KeyCode::Char('X')) => {
+                let (execution_id, _) = self
+                    .pipeline
+                    .latest_run()
+                    .filter(|(_, status)| status == "InProgress")?;
+                self.modal = Some(Modal::Form(Form::stop_execution(execution_id)))
+            }
+            (View::Stages, KeyCode::Char('A'))
+            | (View::Stages, KeyCode::Char('x'))
+            | (View::Action, KeyCode::Char('A'))
+            | (View::Action, KeyCode::Char('x')) => {
+                let (stage, action) = self.waiting_approval()?;
+                self.modal = Some(Modal::Form(if key == KeyCode::Char('A') {
+                    Form::approve(stage, action)
+                } else {
+                    Form::reject(stage, action)
+                }))
+            }
+            (View::Stages, KeyCode::Tab) => {
+                self.focused_pane = (self.focused_pane + 1) % self.layout.len().max(1)
+            }
+            // like tmux's zoom, the same key puts the layout back
+            (View::Stages, KeyCode::Char('z')) => self.zoomed = !self.zoomed,
+            (View::Stages, KeyCode::Char(']')) | (View::Stages, KeyCode::Char('['))
+                if self.execution_mode == ExecutionMode::Parallel =>
+            {
+                return self.focus_execution(if key == KeyCode::Char(']') { 1 } else { -1 });
+            }
+            (View::History, KeyCode::Up) => {
+                self.selected_execution =
+                    step(self.selected_execution, -1, self.pipeline.executions.len())
+            }
+            (View::History, KeyCode::Down) => {
+                self.selected_execution =
+                    step(self.selected_execution, 1, self.pipeline.executions.len())
+            }
+            (View::History, KeyCode::Char(' ')) => {
+                self.marked_execution = match self.marked_execution {
+                    Some(marked) if marked == self.selected_execution => None,
+                    _ => Some(self.selected_execution),
+                }
+            }
+            (View::History, KeyCode::Enter) => return self.compare_command(),
+            (View::History, KeyCode::Char('a')) => {
+                let execution = self.pipeline.executions.get(self.selected_execution)?;
+                let id = execution.pipeline_execution_id.clone()?;
+                self.view = View::Provenance;
+                self.provenance = None;
+                return Some(Command::LoadProvenance(id));
+            }
+            (View::History, KeyCode::Char('*')) => {
+                let execution = self.pipeline.executions.get(self.selected_execution)?;
+                let id = execution.pipeline_execution_id.clone()?;
+                return Some(Command::Bookmark {
+                    pipeline: self.pipeline.name.clone(),
+                    bookmarked: !self.is_bookmarked(&id),
+                    execution_id: id,
+                });
+            }
+            (View::History, KeyCode::Char('N')) => {
+                let execution = self.pipeline.executions.get(self.selected_execution)?;
+                let id = execution.pipeline_execution_id.clone()?;
+                let note = self.notes.get(&id).cloned().unwrap_or_default();
+                self.modal = Some(Modal::Form(Form::annotate(id, note)));
+            }
+            (View::History, KeyCode::Char('g')) => {
+                let execution = self.pipeline.executions.get(self.selected_execution)?;
+                let id = execution.pipeline_execution_id.clone()?;
+                self.view = View::Timeline;
+                self.timeline = None;
+                return Some(Command::LoadTimeline(id));
+            }
+            (View::Stages, KeyCode::Enter) => {
+                self.view = View::Action;
+                self.clear_details();
+                return self.detail_command();
+            }
+            // up and down follow the table's order, whatever it's sorted by
+            (View::Action, KeyCode::Up) | (View::Action, KeyCode::Down) => {
+                let order = self.sorted_actions(Local::now().timestamp() as f64);
+                let offset = if key == KeyCode::Up { -1 } else { 1 };
+                let index = step_within(&order, self.selected_action, offset);
+                if index != self.selected_action {
+                    self.selected_action = index;
+                    self.clear_details();
+                    return self.detail_command();
+                }
+            }
+            // the number keys pick the column to sort by, picking the same one again flips the order
+            (View::Action, KeyCode::Char(c @ '1'..='5')) => {
+                let column = ACTION_COLUMNS[c as usize - '1' as usize];
+                if column == self.action_sort {
+                    self.action_sort_descending = !self.action_sort_descending;
+                } else {
+                    self.action_sort = column;
+                    self.action_sort_descending = false;
+                }
+            }
+            (_, KeyCode::Char('y')) | (_, KeyCode::Char('Y')) | (_, KeyCode::Char('u')) => {
+                return self.yank(key)
+            }
+            (_, KeyCode::Char('o')) => return self.console_url().map(Command::OpenUrl),
+            _ => {}
+        }
+        None
+    }
+
+    // y copies whatever's selected's ID, Y the source revision it ran with, u its link
+    // in the action view "whatever's selected" is the action, everywhere else it's an execution
+    fn yank(&self, key: KeyCode) -> Option<Command> {
+        let execution_id = match self.view {
+            View::Pipelines | View::Fleet => return None,
+            View::Stages | View::Action | View::BuildLog => self
+                .selected_stage()?
+                .state
+                .latest_execution
+                .as_ref()?
+                .pipeline_execution_id
+                .clone(),
+            _ => self
+                .pipeline
+                .executions
+                .get(self.selected_execution)?
+                .pipeline_execution_id
+                .clone()?,
+        };
+        let action = match self.view {
+            View::Action | View::BuildLog => self.selected_action()?.latest_execution.as_ref(),
+            _ => None,
+        };
+        let text = match key {
+            KeyCode::Char('y') => match action {
+                Some(action) => action.external_execution_id.clone()?,
+                None => execution_id,
+            },
+            KeyCode::Char('Y') => self
+                .pipeline
+                .executions
+                .iter()
+                .find(|execution| execution.pipeline_execution_id.as_ref() == Some(&execution_id))?
+                .source_revisions
+                .as_ref()?
+                .first()?
+                .revision_id
+                .clone()?,
+            _ => self.console_url()?,
+        };
+        Some(Command::Copy(text))
+    }
+
+    // the console page for whatever's selected: the pipeline, an execution, or an action's own page
+    pub fn console_url(&self) -> Option<String> {
+        let region = self.region.as_str();
+        let pipeline = self.pipeline.name.as_str();
+        let execution = |index: usize| {
+            let id = self
+                .pipeline
+                .executions
+                .get(index)?
+                .pipeline_execution_id
+                .as_deref()?;
+            Some(console::execution_url(region, pipeline, id))
+        };
+        match self.view {
+            View::Pipelines | View::Fleet => {
+                let listing = self.pipelines.get(self.selected_pipeline)?;
+                Some(console::pipeline_url(region, &listing.name))
+            }
+            View::Stages => Some(match &self.pinned_execution {
+                Some(id) => console::execution_url(region, pipeline, id),
+                None => console::pipeline_url(region, pipeline),
+            }),
+            View::Action | View::BuildLog => {
+                let execution = self.selected_action()?.latest_execution.as_ref()?;
+                // cross-region actions run somewhere other than the pipeline
+                let action_region = self.selected_region().unwrap_or(region);
+                match (self.selected_provider(), &execution.external_execution_id) {
+                    (Some("CodeBuild"), Some(build_id)) => {
+                        console::build_url(action_region, build_id)
+                    }
+                    _ => execution.external_execution_url.clone(),
+                }
+            }
+            View::Stats => Some(console::pipeline_url(region, pipeline)),
+            _ => execution(self.selected_execution),
+        }
+    }
+
+    fn clear_details(&mut self) {
+        self.build = None;
+        self.stack_events = None;
+        self.deployment = None;
+        self.approvals = None;
+    }
+
+    // let the open log view know how many lines its log has now
+    pub fn update_log_view(&mut self) {
+        let (len, dropped) = match self.view {
+            View::BuildLog => self
+                .build_log
+                .as_ref()
+                .map_or((0, 0), |log| (log.lines.len(), log.dropped)),
+            View::AppLog => (self.app_log.len(), 0),
+            View::Notifications => (self.toasts.len(), 0),
+            View::Inspector => {
+                let (lines, dropped) = self.calls.lines();
+                (lines.len(), dropped)
+            }
+            _ => return,
+        };
+        self.log_view.update(len, dropped);
+    }
+
+    // only CodeBuild actions have a log we know how to find
+    pub fn build_log_command(&self) -> Option<Command> {
+        if self.selected_provider()? != "CodeBuild" {
+            return None;
+        }
+        let build_id = self
+            .selected_action()?
+            .latest_execution
+            .as_ref()?
+            .external_execution_id
+            .clone()?;
+        Some(Command::LoadBuildLog(build_id))
+    }
+
+    // the action table needs the selected stage's action runs, unless we already have them for its latest execution
+    pub fn stage_actions_command(&self) -> Option<Command> {
+        if self.view != View::Action {
+            return None;
+        }
+        let id = self
+            .selected_stage()?
+            .state
+            .latest_execution
+            .as_ref()?
+            .pipeline_execution_id
+            .clone();
+        match &self.stage_actions {
+            Some((loaded, _)) if *loaded == id => None,
+            _ => Some(Command::LoadStageActions(id)),
+        }
+    }
+
+    // how long the selected stage's action took (or has taken so far) in the stage's latest execution
+    pub fn action_duration(&self, action_name: &str, now: f64) -> Option<u64> {
+        let (_, details) = self.stage_actions.as_ref()?;
+        let stage_name = self.selected_stage()?.state.stage_name.as_deref()?;
+        // details are newest first, so a retried action's latest attempt wins
+        let detail = details.iter().find(|detail| {
+            detail.detail.stage_name.as_deref() == Some(stage_name)
+                && detail.detail.action_name.as_deref() == Some(action_name)
+        })?;
+        let start = detail.detail.start_time?;
+        let end = match detail.detail.status.as_deref() {
+            Some("InProgress") => now,
+            _ => detail.detail.last_update_time?,
+        };
+        Some((end - start).max(0.0) as u64)
+    }
+
+    // indexes into the selected stage's actions, in the order the action table shows them
+    pub fn sorted_actions(&self, now: f64) -> Vec<usize> {
+        let actions = match self
+            .selected_stage()
+            .and_then(|stage| stage.state.action_states.as_ref())
+        {
+            Some(actions) => actions,
+            None => return vec![],
+        };
+        let stage_name = self
+            .selected_stage()
+            .and_then(|stage| stage.state.stage_name.clone())
+            .unwrap_or_default();
+        let mut order = (0..actions.len()).collect::<Vec<_>>();
+        let name = |index: usize| actions[index].action_name.clone().unwrap_or_default();
+        let execution = |index: usize| actions[index].latest_execution.as_ref();
+        match self.action_sort {
+            ActionColumn::None => {}
+            ActionColumn::Action => order.sort_by_key(|index| name(*index)),
+            ActionColumn::Provider => order.sort_by_key(|index| {
+                self.action_declaration(&stage_name, &name(*index))
+                    .map(|action| action.action_type_id.provider.clone())
+            }),
+            ActionColumn::Status => order.sort_by_key(|index| {
+                execution(*index).and_then(|execution| execution.status.clone())
+            }),
+            ActionColumn::Duration => {
+                order.sort_by_key(|index| self.action_duration(&name(*index), now))
+            }
+            ActionColumn::LastChange => order.sort_by(|a, b| {
+                let changed = |index: usize| {
+                    execution(index)
+                        .and_then(|execution| execution.last_status_change)
+                        .unwrap_or(0.0)
+                };
+                changed(*a)
+                    .partial_cmp(&changed(*b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            }),
+        }
+        if self.action_sort_descending {
+            order.reverse();
+        }
+        order
+    }
+
+    // move the selection along by `offset`, staying within the stages we have
+    fn select_stage(&mut self, offset: isize) {
+        self.selected_stage = step_within(&self.visible_stages(), self.selected_stage, offset);
+        self.selected_action = 0;
+    }
+
+    fn toggle_problems_only(&mut self) {
+        self.problems_only = !self.problems_only;
+        // keep the selections on something that's still showing
+        let pipelines = self.visible_pipelines();
+        if !pipelines.contains(&self.selected_pipeline) {
+            self.selected_pipeline = pipelines.first().copied().unwrap_or(0);
+        }
+        let stages = self.visible_stages();
+        if !stages.contains(&self.selected_stage) {
+            self.selected_stage = stages.first().copied().unwrap_or(0);
+            self.selected_action = 0;
+        }
+    }
+
+    // indexes into the selector's pipelines, leaving out the green ones when the problem filter's on
+    pub fn visible_pipelines(&self) -> Vec<usize> {
+        (0..self.pipelines.len())
+            .filter(|index| {
+                !self.problems_only
+                    || self.pipelines[*index].latest_status.as_deref() != Some("Succeeded")
+            })
+            .collect()
+    }
+
+    // the same for the pipeline's stages, which can also be left out by the stage filter
+    pub fn visible_stages(&self) -> Vec<usize> {
+        self.filtered_stages()
+            .into_iter()
+            .filter(|index| {
+                !self.problems_only
+                    || self.pipeline.stage_states[*index]
+                        .state
+                        .latest_execution
+                        .as_ref()
+                        .is_none_or(|execution| execution.status != "Succeeded")
+            })
+            .collect()
+    }
+
+    // the stages the stage filter lets through, whatever state they're in
+    pub fn filtered_stages(&self) -> Vec<usize> {
+        (0..self.pipeline.stage_states.len())
+            .filter(|index| {
+                self.pipeline.stage_states[*index]
+                    .state
+                    .stage_name
+                    .as_deref()
+                    .is_none_or(|name| self.stage_filter.shows(name))
+            })
+            .collect()
+    }
+
+    fn select_action(&mut self, offset: isize) {
+        let actions = self
+            .selected_stage()
+            .and_then(|stage| stage.state.action_states.as_ref())
+            .map_or(0, |actions| actions.len());
+        self.selected_action = step(self.selected_action, offset, actions);
+    }
+
+    pub fn selected_stage(&self) -> Option<&StageStateV2> {
+        self.pipeline.stage_states.get(self.selected_stage)
+    }
+
+    pub fn selected_action(&self) -> Option<&ActionState> {
+        self.selected_stage()
+            .and_then(|stage| stage.state.action_states.as_ref())
+            .and_then(|actions| actions.get(self.selected_action))
+    }
+
+    // the provider ("CodeBuild", "CloudFormation", "Manual", ...) and configuration come from the pipeline definition,
+    // the state only knows names
+    pub fn action_declaration(
+        &self,
+        stage_name: &str,
+        action_name: &str,
+    ) -> Option<&ActionDeclaration> {
+        self.definition
+            .stages
+            .iter()
+            .find(|stage| stage.name == stage_name)
+            .and_then(|stage| {
+                stage
+                    .actions
+                    .iter()
+                    .find(|action| action.name == action_name)
+            })
+    }
+
+    pub fn selected_declaration(&self) -> Option<&ActionDeclaration> {
+        let stage_name = self.selected_stage()?.state.stage_name.as_ref()?;
+        let action_name = self.selected_action()?.action_name.as_ref()?;
+        self.action_declaration(stage_name, action_name)
+    }
+
+    // the region an action runs in, only when that's somewhere other than the pipeline's own region
+    pub fn action_region(&self, stage_name: &str, action_name: &str) -> Option<&str> {
+        self.action_declaration(stage_name, action_name)?
+            .region
+            .as_deref()
+            .filter(|region| *region != self.region)
+    }
+
+    pub fn selected_region(&self) -> Option<&str> {
+        let stage_name = self.selected_stage()?.state.stage_name.as_ref()?;
+        let action_name = self.selected_action()?.action_name.as_ref()?;
+        self.action_region(stage_name, action_name)
+    }
+
+    pub fn selected_provider(&self) -> Option<&str> {
+        self.selected_declaration()
+            .map(|action| action.action_type_id.provider.as_str())
+    }
+
+    // what to fetch for the action detail view, depending on what kind of action is selected
+    pub fn detail_command(&self) -> Option<Command> {
+        let action = self.selected_action()?;
+        let execution = action.latest_execution.as_ref()?;
+        match self.selected_provider()? {
+            // for CodeBuild actions the external execution ID is the build ID
+            "CodeBuild" => execution
+                .external_execution_id
+                .clone()
+                .map(|id| Command::Load(Detail::Build(id))),
+            // the stack's events say why a deploy is stuck or failed, which the action itself rarely does
+            "CloudFormation" => match execution.status.as_deref() {
+                Some("InProgress") | Some("Failed") => self
+                    .selected_declaration()?
+                    .configuration
+                    .as_ref()?
+                    .get("StackName")
+                    .cloned()
+                    .map(|name| Command::Load(Detail::StackEvents(name))),
+                _ => None,
+            },
+            // for both of these the action's external execution ID is the CodeDeploy deployment ID
+            "CodeDeploy" | "CodeDeployToECS" => execution
+                .external_execution_id
+                .clone()
+                .map(|id| Command::Load(Detail::Deployment(DeploymentSource::CodeDeploy(id)))),
+            "ECS" => {
+                let configuration = self.selected_declaration()?.configuration.as_ref()?;
+                Some(Command::Load(Detail::Deployment(DeploymentSource::Ecs {
+                    cluster: configuration.get("ClusterName")?.clone(),
+                    service: configuration.get("ServiceName")?.clone(),
+                })))
+            }
+            "Manual" => Some(Command::Load(Detail::Approvals {
+                stage: self.selected_stage()?.state.stage_name.clone()?,
+                action: action.action_name.clone()?,
+            })),
+            _ => None,
+        }
+    }
+
+    // re-enabling a transition needs nothing more, disabling one needs a reason so ask for it first
+    // on a production pipeline, the actions the config names need the stage's or pipeline's name typed out on top
+    // of whatever confirmation they've already had
+    fn confirmed(&mut self, command: Command) -> Option<Command> {
+        let (action, expected) = match &command {
+            Command::StartExecution(_) => ("start", self.pipeline.name.clone()),
+            Command::Rollback { stage, .. } => ("rollback", stage.clone()),
+            Command::EnableTransition(stage) => ("enable-transition", stage.clone()),
+            Command::DisableTransition { stage, .. } => ("disable-transition", stage.clone()),
+            Command::Approve { stage, .. } => ("approve", stage.clone()),
+            Command::Reject { stage, .. } => ("reject", stage.clone()),
+            Command::RetryStage(stage) => ("retry", stage.clone()),
+            Command::StopExecution { .. } => ("stop", self.pipeline.name.clone()),
+            _ => return Some(command),
+        };
+        if !self.confirm.needs_typing(&self.pipeline.name, action) {
+            return Some(command);
+        }
+        self.modal = Some(Modal::Typed(TypedConfirmation {
+            action,
+            command,
+            expected,
+            typed: String::new(),
+        }));
+        None
+    }
+
+    fn toggle_transition(&mut self) -> Option<Command> {
+        // the first stage has nothing coming into it
+        if self.selected_stage == 0 {
+            return None;
+        }
+        let stage = self.selected_stage()?;
+        let name = stage.state.stage_name.clone()?;
+        let enabled = stage
+            .state
+            .inbound_transition_state
+            .as_ref()
+            .and_then(|transition| transition.enabled)
+            .unwrap_or(true);
+        if enabled {
+            self.modal = Some(Modal::Form(Form::disable_transition(name)));
+            None
+        } else {
+            Some(Command::EnableTransition(name))
+        }
+    }
+
+    // the stage after this one, whose inbound transition is this one's outbound
+    pub fn next_stage(&self, stage: &StageStateV2) -> Option<&StageStateV2> {
+        let stages = &self.pipeline.stage_states;
+        let index = stages
+            .iter()
+            .position(|other| other.state.stage_name == stage.state.stage_name)?;
+        stages.get(index + 1)
+    }
+
+    // compare the marked execution with the selected one, whichever way round they were picked
+    fn compare_command(&mut self) -> Option<Command> {
+        let marked = self.marked_execution?;
+        if marked == self.selected_execution {
+            return None;
+        }
+        // executions are newest first, so the higher index is the older one
+        let (older, newer) = if marked > self.selected_execution {
+            (marked, self.selected_execution)
+        } else {
+            (self.selected_execution, marked)
+        };
+        let executions = &self.pipeline.executions;
+        let base = executions.get(older)?.pipeline_execution_id.clone()?;
+        let head = executions.get(newer)?.pipeline_execution_id.clone()?;
+        self.view = View::Compare;
+        self.compared = Some((base.clone(), head.clone()));
+        self.comparison = None;
+        self.stage_comparison = None;
+        Some(Command::Compare { base, head })
+    }
+
+    // only a failed stage has anything to retry
+    fn retry_command(&self) -> Option<Command> {
+        let stage = self.selected_stage()?;
+        match stage.state.latest_execution.as_ref()?.status.as_str() {
+            "Failed" => Some(Command::RetryStage(stage.state.stage_name.clone()?)),
+            _ => None,
+        }
+    }
+
+    // the selected action, if it's a manual approval still waiting on someone, which is all that has a token
+    fn waiting_approval(&self) -> Option<(String, String)> {
+        let action = self.selected_action()?;
+        let execution = action.latest_execution.as_ref()?;
+        if execution.status.as_deref() != Some("InProgress") || execution.token.is_none() {
+            return None;
+        }
+        Some((
+            self.selected_stage()?.state.stage_name.clone()?,
+            action.action_name.clone()?,
+        ))
+    }
+
+    // CodePipeline only rolls back stages that have finished, and never the source stage
+    fn rollback_command(&self) -> Option<Command> {
+        if self.selected_stage == 0 {
+            return None;
+        }
+        let stage = self.selected_stage()?;
+        match stage.state.latest_execution.as_ref()?.status.as_str() {
+            "Failed" | "Succeeded" => Some(Command::PlanRollback(stage.state.stage_name.clone()?)),
+            _ => None,
+        }
+    }
+
+    // how long the stage has been running and how long it usually takes, if it's well past its usual time
+    pub fn overrunning(&self, stage: &StageStateV2, now: f64) -> Option<(u64, u64)> {
+        let execution = stage.state.latest_execution.as_ref()?;
+        if execution.status != "InProgress" {
+            return None;
+        }
+        let name = stage.state.stage_name.as_ref()?;
+        let start = self
+            .stage_starts
+            .get(&(execution.pipeline_execution_id.clone(), name.clone()))?;
+        let median = self
+            .stats
+            .as_ref()?
+            .iter()
+            .find(|stats| &stats.stage == name)?
+            .median()?;
+        let elapsed = (now - start).max(0.0) as u64;
+        if elapsed as f64 > median as f64 * ANOMALY_FACTOR {
+            Some((elapsed, median))
+        } else {
+            None
+        }
+    }
+
+    // how long the stage has been running and how long the [sla] allows it, if it's gone over
+    pub fn over_sla(&self, stage: &StageStateV2, now: f64) -> Option<(u64, u64)> {
+        let execution = stage.state.latest_execution.as_ref()?;
+        if execution.status != "InProgress" {
+            return None;
+        }
+        let name = stage.state.stage_name.as_ref()?;
+        let limit = self.sla.limit(&self.pipeline.name, name)?;
+        let start = self
+            .stage_starts
+            .get(&(execution.pipeline_execution_id.clone(), name.clone()))?;
+        let elapsed = (now - start).max(0.0) as u64;
+        if elapsed > limit {
+            Some((elapsed, limit))
+        } else {
+            None
+        }
+    }
+
+    // the stages that have gone over their [sla] since we last looked, each stage run only turning up once: its
+    // name, execution ID, how long it's been running and how long it was allowed
+    pub fn newly_over_sla(&mut self, now: f64) -> Vec<(String, String, u64, u64)> {
+        let mut over = vec![];
+        for stage in &self.pipeline.stage_states {
+            if let Some((elapsed, limit)) = self.over_sla(stage, now) {
+                let name = stage.state.stage_name.clone().unwrap_or_default();
+                let execution_id = stage
+                    .state
+                    .latest_execution
+                    .as_ref()
+                    .map(|execution| execution.pipeline_execution_id.clone())
+                    .unwrap_or_default();
+                if !self
+                    .sla_alerted
+                    .contains(&(execution_id.clone(), name.clone()))
+                {
+                    over.push((name, execution_id, elapsed, limit));
+                }
+            }
+        }
+        for (name, execution_id, _, _) in &over {
+            self.sla_alerted
+                .insert((execution_id.clone(), name.clone()));
+        }
+        over
+    }
+
+    pub fn is_bookmarked(&self, execution_id: &str) -> bool {
+        self.bookmarks.iter().any(|bookmark| {
+            bookmark.pipeline == self.pipeline.name && bookmark.execution_id == execution_id
+        })
+    }
+
+    // the views that are all about executions, which keep the execution list fresh while they're open
+    pub fn shows_executions(&self) -> bool {
+        matches!(
+            self.view,
+            View::History | View::Compare | View::Provenance | View::Timeline
+        )
+    }
+
+    // whether the stages view is showing the pane, zooming in on one hides the rest
+    pub fn shows_pane(&self, pane: Pane) -> bool {
+        self.view == View::Stages
+            && self.layout.iter().enumerate().any(|(index, layout)| {
+                layout.pane == pane && (!self.zoomed || index == self.focused_pane)
+            })
+    }
+
+    // how much of the execution list the open pipeline's polls fetch, for whatever's on screen
+    pub fn executions_needed(&self) -> Executions {
+        if self.shows_executions() {
+            Executions::Always
+        } else if self.view == View::Fleet || self.execution_mode == ExecutionMode::Parallel {
+            Executions::Changes
+        } else if self.shows_pane(Pane::Commits) {
+            Executions::New
+        } else {
+            Executions::Never
+        }
+    }
+
+    // running timers, toasts counting down and the debug overlay's calls per minute change with the clock rather than with anything we fetch, so while
+    // there are any the screen needs redrawing now and then even if nothing else has happened
+    pub fn animating(&self) -> bool {
+        self.debug || !self.visible_toasts().is_empty() || !self.running_stages().is_empty()
+    }
+
+    // the pipeline executions our running stages are on, each paired with those stages' names
+    pub fn running_stages(&self) -> HashMap<String, Vec<String>> {
+        let mut running: HashMap<String, Vec<String>> = HashMap::new();
+        for stage in &self.pipeline.stage_states {
+            if let (Some(name), Some(execution)) =
+                (&stage.state.stage_name, &stage.state.latest_execution)
+            {
+                if execution.status == "InProgress" {
+                    running
+                        .entry(execution.pipeline_execution_id.clone())
+                        .or_default()
+                        .push(name.clone());
+                }
+            }
+        }
+        running
+    }
+
+    // the source revisions that some stage is currently showing but we haven't looked up yet
+    pub fn unresolved_revisions(&self) -> Vec<SourceRevision> {
+        let mut revisions = self
+            .pipeline
+            .stage_states
+            .iter()
+            .filter_map(|stage| stage.state.latest_execution.as_ref())
+            .filter_map(|latest| {
+                self.pipeline.executions.iter().find(|execution| {
+                    execution.pipeline_execution_id.as_ref() == Some(&latest.pipeline_execution_id)
+                })
+            })
+            .flat_map(|execution| execution.source_revisions.clone().unwrap_or_default())
+            .filter(|revision| match &revision.revision_id {
+                Some(id) => !self.commits.contains_key(id),
+                None => false,
+            })
+            .collect::<Vec<_>>();
+        // several stages are usually running the same revision
+        revisions.sort_by(|a, b| a.revision_id.cmp(&b.revision_id));
+        revisions.dedup_by(|a, b| a.revision_id == b.revision_id);
+        revisions
+    }
+}
+
+// the columns of the action table in the detail view, which the number keys sort by
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActionColumn {
+    // not sorted, the actions stay in pipeline order
+    None,
+    Action,
+    Provider,
+    Status,
+    Duration,
+    LastChange,
+}
+
+// in the order they're shown, so '1' sorts by the first one
+pub const ACTION_COLUMNS: [ActionColumn; 5] = [
+    ActionColumn::Action,
+    ActionColumn::Provider,
+    ActionColumn::Status,
+    ActionColumn::Duration,
+    ActionColumn::LastChange,
+];
+
+impl View {
+    pub fn title(self) -> &'static str {
+        match self {
+            View::Pipelines => "Pipelines",
+            View::Fleet => "Fleet",
+            View::Stages => "Stages",
+            View::History => "History",
+            View::Stats => "Stats",
+            View::Compare => "Compare",
+            View::Provenance => "Artifacts",
+            View::Timeline => "Timeline",
+            View::Action => "Action",
+            View::BuildLog => "Build log",
+            View::AppLog => "App log",
+            View::Notifications => "Notifications",
+            View::Inspector => "API calls",
+            View::Bookmarks => "Bookmarks",
+        }
+    }
+}
+
+impl ActionColumn {
+    pub fn title(self) -> &'static str {
+        match self {
+            ActionColumn::None => "",
+            ActionColumn::Action => "Action",
+            ActionColumn::Provider => "Provider",
+            ActionColumn::Status => "Status",
+            ActionColumn::Duration => "Duration",
+            ActionColumn::LastChange => "Last change",
+        }
+    }
+}
+
+// a CodeBuild run's log, fetched a bit more at a time as the build goes
+pub struct BuildLog {
+    pub build_id: String,
+    // only the newest of them, the oldest go once there are more than the limits allow
+    pub lines: Vec<String>,
+    // how many lines have gone from the front, and how much the ones left add up to
+    pub dropped: usize,
+    bytes: usize,
+    limits: LogConfig,
+    // where CloudWatch Logs carries on from next time
+    pub next_token: Option<String>,
+}
+
+impl BuildLog {
+    pub fn new(build_id: String, limits: LogConfig) -> BuildLog {
+        BuildLog {
+            build_id,
+            lines: vec![],
+            dropped: 0,
+            bytes: 0,
+            limits,
+            next_token: None,
+        }
+    }
+
+    // an hours-long chatty build would otherwise keep growing until we ran out of memory
+    pub fn extend(&mut self, lines: Vec<String>) {
+        self.bytes += lines.iter().map(String::len).sum::<usize>();
+        self.lines.extend(lines);
+        let mut drop = self.lines.len().saturating_sub(self.limits.max_lines);
+        let mut bytes = self.bytes;
+        for line in &self.lines[..drop] {
+            bytes -= line.len();
+        }
+        while bytes > self.limits.max_bytes && drop < self.lines.len() {
+            bytes -= self.lines[drop].len();
+            drop += 1;
+        }
+        if drop > 0 {
+            self.lines.drain(..drop);
+            self.dropped += drop;
+            self.bytes = bytes;
+        }
+    }
+}
+
+// any more than this and they'd start covering the view
+const MAX_VISIBLE_TOASTS: usize = 3;
+// in place of whatever call it was that found out
+const EXPIRED_CREDENTIALS: &str = "The AWS credentials have expired, e signs in again";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToastLevel {
+    Info,
+    Success,
+    Error,
+}
+
+// a notification about something that happened in the background
+pub struct Toast {
+    pub message: String,
+    pub level: ToastLevel,
+    pub at: DateTime<Local>,
+    // whether it pops up in the corner as well as going in the notifications view
+    pub popup: bool,
+}
+
+// a popup over the current view. whatever kind it is, esc backs out of it and enter goes ahead
+pub enum Modal {
+    // text fields to fill in, with nothing to fill in it's just a confirmation
+    Form(Form),
+    // a rollback waiting on confirmation
+    Rollback(RollbackPlan),
+    // something too long for the footer, like the whole of an error
+    Message { title: String, text: String },
+    // a production pipeline's name or stage to type out before going ahead
+    Typed(TypedConfirmation),
+    // the credentials have expired, sign in again?
+    Reauth,
+}
+
+pub struct TypedConfirmation {
+    // as it's named in the config and the audit log
+    pub action: &'static str,
+    pub command: Command,
+    pub expected: String,
+    pub typed: String,
+}
+
+// what a keypress does to the open modal
+pub enum ModalKey {
+    Keep,
+    Dismiss,
+    Confirm,
+}
+
+impl Modal {
+    fn on_key(&mut self, key: KeyCode) -> ModalKey {
+        match (self, key) {
+            (_, KeyCode::Esc) => ModalKey::Dismiss,
+            (_, KeyCode::Enter) => ModalKey::Confirm,
+            // a form's fields take any typing, so only the yes/no popups get the y and n shortcuts
+            (Modal::Form(form), key) => {
+                form.on_key(key);
+                ModalKey::Keep
+            }
+            (Modal::Typed(typed), KeyCode::Backspace) => {
+                typed.typed.pop();
+                ModalKey::Keep
+            }
+            (Modal::Typed(typed), KeyCode::Char(c)) => {
+                typed.typed.push(c);
+                ModalKey::Keep
+            }
+            (_, KeyCode::Char('y')) => ModalKey::Confirm,
+            (_, KeyCode::Char('n')) => ModalKey::Dismiss,
+            _ => ModalKey::Keep,
+        }
+    }
+
+    // what to run once the user has said yes, if anything
+    fn confirm(self) -> Option<Command> {
+        match self {
+            Modal::Form(form) => Some(form.submit()),
+            Modal::Rollback(plan) => Some(Command::Rollback {
+                stage: plan.stage,
+                target: plan.target.pipeline_execution_id?,
+            }),
+            Modal::Message { .. } => None,
+            Modal::Typed(typed) => Some(typed.command),
+            Modal::Reauth => Some(Command::Reauthenticate),
+        }
+    }
+}
+
+// which earlier execution a stage would be rolled back to
+pub struct RollbackPlan {
+    pub stage: String,
+    pub target: PipelineExecutionSummary,
+}
+
+// a few text fields to fill in before doing something, with nothing to fill in it's just a confirmation
+pub struct Form {
+    pub purpose: FormPurpose,
+    pub fields: Vec<FormField>,
+    pub focused: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormPurpose {
+    // asks for a value for each of the pipeline's variables
+    StartExecution,
+    // asks why the transition into this stage is being disabled
+    DisableTransition(String),
+    // asks for a note on this execution
+    Annotate(String),
+    // asks for the comment to answer the approval with
+    Approve { stage: String, action: String },
+    Reject { stage: String, action: String },
+    // asks why this execution is being stopped
+    StopExecution(String),
+}
+
+pub struct FormField {
+    pub name: String,
+    pub description: Option<String>,
+    pub value: String,
+}
+
+impl Form {
+    pub fn start_execution(declarations: &[VariableDeclaration]) -> Form {
+        Form {
+            purpose: FormPurpose::StartExecution,
+            fields: declarations
+                .iter()
+                .map(|declaration| FormField {
+                    name: declaration.name.clone(),
+                    description: declaration.description.clone(),
+                    value: declaration.default_value.clone().unwrap_or_default(),
+                })
+                .collect(),
+            focused: 0,
+        }
+    }
+
+    pub fn disable_transition(stage: String) -> Form {
+        Form {
+            purpose: FormPurpose::DisableTransition(stage),
+            fields: vec![FormField {
+                name: "Reason".to_owned(),
+                description: Some("shown to anyone looking at the pipeline".to_owned()),
+                value: String::new(),
+            }],
+            focused: 0,
+        }
+    }
+
+    pub fn annotate(execution_id: String, note: String) -> Form {
+        Form {
+            purpose: FormPurpose::Annotate(execution_id),
+            fields: vec![FormField {
+                name: "Note".to_owned(),
+                description: Some(
+                    "only kept on this machine, leave it empty to take it off".to_owned(),
+                ),
+                value: note,
+            }],
+            focused: 0,
+        }
+    }
+
+    pub fn approve(stage: String, action: String) -> Form {
+        Form::comment(FormPurpose::Approve { stage, action })
+    }
+
+    pub fn reject(stage: String, action: String) -> Form {
+        Form::comment(FormPurpose::Reject { stage, action })
+    }
+
+    fn comment(purpose: FormPurpose) -> Form {
+        Form {
+            purpose,
+            fields: vec![FormField {
+                name: "Comment".to_owned(),
+                description: Some("shown with the approval on the execution".to_owned()),
+                value: String::new(),
+            }],
+            focused: 0,
+        }
+    }
+
+    pub fn stop_execution(execution_id: String) -> Form {
+        Form {
+            purpose: FormPurpose::StopExecution(execution_id),
+            fields: vec![FormField {
+                name: "Reason".to_owned(),
+                description: Some("kept with the stopped execution".to_owned()),
+                value: String::new(),
+            }],
+            focused: 0,
+        }
+    }
+
+    fn submit(self) -> Command {
+        let value = self.first_value();
+        match self.purpose {
+            FormPurpose::Annotate(execution_id) => Command::Annotate {
+                execution_id,
+                note: self
+                    .fields
+                    .into_iter()
+                    .next()
+                    .map(|field| field.value)
+                    .unwrap_or_default(),
+            },
+            FormPurpose::StartExecution => Command::StartExecution(self.values()),
+            // CodePipeline insists on some reason, and on a comment for an approval
+            FormPurpose::DisableTransition(stage) => Command::DisableTransition {
+                stage,
+                reason: value.unwrap_or_else(|| "Disabled from codepipeline-status".to_owned()),
+            },
+            FormPurpose::Approve { stage, action } => Command::Approve {
+                stage,
+                action,
+                comment: value.unwrap_or_else(|| "Approved from codepipeline-status".to_owned()),
+            },
+            FormPurpose::Reject { stage, action } => Command::Reject {
+                stage,
+                action,
+                comment: value.unwrap_or_else(|| "Rejected from codepipeline-status".to_owned()),
+            },
+            FormPurpose::StopExecution(execution_id) => Command::StopExecution {
+                execution_id,
+                reason: value,
+            },
+        }
+    }
+
+    // what was typed into the only field, if anything was
+    fn first_value(&self) -> Option<String> {
+        self.fields
+            .first()
+            .map(|field| field.value.clone())
+            .filter(|value| !value.trim().is_empty())
+    }
+
+    fn on_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Tab | KeyCode::Down => self.focused = step(self.focused, 1, self.fields.len()),
+            KeyCode::BackTab | KeyCode::Up => {
+                self.focused = step(self.focused, -1, self.fields.len())
+            }
+            KeyCode::Backspace => {
+                if let Some(field) = self.fields.get_mut(self.focused) {
+                    field.value.pop();
+                }
+            }
+            KeyCode::Char(c) => {
+                if let Some(field) = self.fields.get_mut(self.focused) {
+                    field.value.push(c);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn values(&self) -> Vec<(String, String)> {
+        self.fields
+            .iter()
+            .map(|field| (field.name.clone(), field.value.clone()))
+            .collect()
+    }
+}
+
+// move `current` along by `offset` within `order`, for lists that don't show everything or not in index order
+fn step_within(order: &[usize], current: usize, offset: isize) -> usize {
+    let position = order
+        .iter()
+        .position(|index| *index == current)
+        .unwrap_or(0);
+    order
+        .get(step(position, offset, order.len()))
+        .copied()
+        .unwrap_or(current)
+}
+
+fn step(current: usize, offset: isize, len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    (current as isize + offset).max(0).min(len as isize - 1) as usize
+}
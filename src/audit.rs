@@ -0,0 +1,172 @@
+use crate::aws::{self, Clients};
+use crate::config::{self, AuditConfig};
+use chrono::Local;
+use rusoto_core::RusotoError;
+use rusoto_logs::{
+    CloudWatchLogs, CreateLogStreamError, CreateLogStreamRequest, InputLogEvent,
+    PutLogEventsRequest,
+};
+use serde::Serialize;
+
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+// something somebody did to a pipeline through us, filled in by whoever did it
+#[derive(Default)]
+pub struct Action {
+    // start, approve, reject, retry, stop, abandon, rollback, enable-transition or disable-transition
+    pub action: &'static str,
+    pub pipeline: String,
+    pub stage: Option<String>,
+    pub execution_id: Option<String>,
+    // whatever else it was given: the variables, the comment, the reason, ...
+    pub detail: Option<String>,
+}
+
+// a line of the log, one JSON object each so it can be grepped or fed to jq during a retrospective
+#[derive(Serialize)]
+struct Entry<'a> {
+    at: String,
+    who: &'a str,
+    region: &'a str,
+    action: &'a str,
+    pipeline: &'a str,
+    stage: Option<&'a str>,
+    execution_id: Option<&'a str>,
+    detail: Option<&'a str>,
+    // null if it went through, otherwise why it didn't
+    error: Option<&'a str>,
+}
+
+// every mutating action, appended to a local file and, if the config names a log group, sent to CloudWatch Logs
+pub struct AuditLog {
+    enabled: bool,
+    log_group: Option<String>,
+    // a stream per run, created the first time there's something to put in it
+    log_stream: String,
+    stream_created: bool,
+    sequence_token: Option<String>,
+    // looked up the first time, and again after signing in again, which can be as somebody else
+    who: Option<String>,
+}
+
+impl AuditLog {
+    pub fn path() -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+        Ok(config::data_dir()?.join("audit.log"))
+    }
+
+    pub fn new(config: &AuditConfig) -> AuditLog {
+        AuditLog {
+            enabled: config.enabled,
+            log_group: config.log_group.clone().filter(|group| !group.is_empty()),
+            log_stream: format!(
+                "{}-{}",
+                Local::now().format("%Y-%m-%dT%H-%M-%S"),
+                std::process::id()
+            ),
+            stream_created: false,
+            sequence_token: None,
+            who: None,
+        }
+    }
+
+    // after signing in again, so the next entry says who that was
+    pub fn forget_identity(&mut self) {
+        self.who = None;
+    }
+
+    // never gets in the way of the action itself, if the log can't be written to we only complain about it
+    pub async fn record(&mut self, clients: &Clients, action: Action, error: Option<String>) {
+        if !self.enabled {
+            return;
+        }
+        if self.who.is_none() {
+            match aws::caller_arn(clients).await {
+                Ok(arn) => self.who = Some(arn),
+                Err(e) => warn!("Couldn't work out who's signed in for the audit log: {}", e),
+            }
+        }
+        let entry = Entry {
+            at: Local::now().to_rfc3339(),
+            who: self.who.as_deref().unwrap_or("unknown"),
+            region: clients.region.name(),
+            action: action.action,
+            pipeline: &action.pipeline,
+            stage: action.stage.as_deref(),
+            execution_id: action.execution_id.as_deref(),
+            detail: action.detail.as_deref(),
+            error: error.as_deref(),
+        };
+        let line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Couldn't write the audit log entry: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = append(&line) {
+            warn!("Couldn't write to the audit log: {}", e);
+        }
+        if let Some(group) = self.log_group.clone() {
+            if let Err(e) = self.send(clients, &group, line).await {
+                warn!("Couldn't send the audit log entry to {}: {}", group, e);
+            }
+        }
+    }
+
+    async fn send(
+        &mut self,
+        clients: &Clients,
+        group: &str,
+        message: String,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if !self.stream_created {
+            match clients
+                .logs
+                .create_log_stream(CreateLogStreamRequest {
+                    log_group_name: group.to_owned(),
+                    log_stream_name: self.log_stream.clone(),
+                })
+                .await
+            {
+                Ok(())
+                | Err(RusotoError::Service(CreateLogStreamError::ResourceAlreadyExists(_))) => {
+                    self.stream_created = true
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let output = clients
+            .logs
+            .put_log_events(PutLogEventsRequest {
+                log_group_name: group.to_owned(),
+                log_stream_name: self.log_stream.clone(),
+                log_events: vec![InputLogEvent {
+                    message,
+                    timestamp: Local::now().timestamp_millis(),
+                }],
+                sequence_token: self.sequence_token.take(),
+            })
+            .await?;
+        self.sequence_token = output.next_sequence_token;
+        Ok(())
+    }
+}
+
+// what went wrong, for the entry's error
+pub fn failure<T>(result: &Result<T, Box<dyn Error + Send + Sync>>) -> Option<String> {
+    result.as_ref().err().map(|e| e.to_string())
+}
+
+// opened fresh for each entry, append only so nothing already in it can be touched
+fn append(line: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let path = AuditLog::path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
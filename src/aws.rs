@@ -0,0 +1,1554 @@
+use crate::calls::{CallLog, Recording};
+use crate::config::EndpointConfig;
+use crate::credentials::{CachedCredentials, CredentialChain};
+use crate::partition;
+use futures::future::{self, join_all};
+use futures::stream::{self, StreamExt};
+use hyper_tls::HttpsConnector;
+use rusoto_cloudformation::{
+    CloudFormation, CloudFormationClient, DescribeStackEventsInput, DescribeStacksInput,
+    ListStackResourcesInput, StackEvent,
+};
+use rusoto_cloudwatch::CloudWatchClient;
+use rusoto_codebuild::{BatchGetBuildsInput, Build, CodeBuild, CodeBuildClient};
+use rusoto_codecommit::CodeCommitClient;
+use rusoto_codedeploy::CodeDeployClient;
+use rusoto_codepipeline::{
+    ActionExecution, ActionExecutionDetail, ApprovalResult, CodePipeline, CodePipelineClient,
+    DisableStageTransitionInput, EnableStageTransitionInput, ListPipelineExecutionsInput,
+    ListPipelinesInput, ListTagsForResourceInput, PipelineDeclaration, PipelineExecutionSummary,
+    PutApprovalResultInput, RetryStageExecutionInput, StageExecution, StageState,
+    StopPipelineExecutionInput,
+};
+use rusoto_codestar_connections::CodeStarConnectionsClient;
+use rusoto_core::credential::{ProfileProvider, StaticProvider};
+use rusoto_core::signature::SignedRequest;
+use rusoto_core::{Client, HttpClient, Region};
+use rusoto_ecs::EcsClient;
+use rusoto_logs::{CloudWatchLogs, CloudWatchLogsClient, GetLogEventsRequest};
+use rusoto_sts::{GetCallerIdentityRequest, Sts, StsClient};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+use std::collections::{HashMap, VecDeque};
+use std::env::var;
+use std::error::Error;
+use std::time::Duration;
+
+// how many of a parallel pipeline's executions are fetched at once, all within the one poll's permit
+const CONCURRENT_FETCHES: usize = 3;
+// how many pages of log events one refresh will fetch
+const MAX_LOG_PAGES: usize = 10;
+// how many pages of the pipeline's action executions are looked through for one action's previous runs
+const MAX_ACTION_PAGES: usize = 5;
+const ACTION_PAGE_SIZE: i64 = 100;
+// how long a connection is kept open between calls, comfortably longer than an idle pipeline's refresh interval
+// so even those don't pay for a fresh TLS handshake every time
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+// everything we need to talk to AWS, built once at startup
+#[derive(Clone)]
+pub struct Clients {
+    // keep hold of the underlying client too, so we can make calls that rusoto_codepipeline doesn't know about yet
+    pub client: Client,
+    // for everything that isn't AWS (GitHub, Bitbucket, ...), shared for the same reason
+    pub http: reqwest::Client,
+    pub region: Region,
+    // FIPS and dual-stack, which every client's endpoint has to agree on
+    pub endpoints: EndpointConfig,
+    // every call made through `client`, for the debug overlay
+    pub calls: CallLog,
+    // the real ones, for forgetting once they've expired and someone's signed in again
+    pub credentials: Option<CachedCredentials>,
+    pub codepipeline: CodePipelineClient,
+    pub codecommit: CodeCommitClient,
+    pub codestar_connections: CodeStarConnectionsClient,
+    pub codebuild: CodeBuildClient,
+    pub cloudformation: CloudFormationClient,
+    pub codedeploy: CodeDeployClient,
+    pub ecs: EcsClient,
+    pub sts: StsClient,
+    pub logs: CloudWatchLogsClient,
+    pub cloudwatch: CloudWatchClient,
+}
+
+impl Clients {
+    pub fn new(
+        profile: &str,
+        region: Region,
+        endpoints: EndpointConfig,
+    ) -> Result<Clients, Box<dyn Error + Send + Sync>> {
+        // a container or a bastion might not have a home directory at all, it'll be using a role instead
+        let credentials_dir = var("HOME").unwrap_or_default() + "/.aws/credentials";
+        let profile_provider = ProfileProvider::with_configuration(credentials_dir, profile);
+        // cached until they're about to expire, rather than asked for again on every call
+        let credentials = CachedCredentials::new(CredentialChain::new(profile_provider));
+        // one connection pool behind every service client, so a refresh's burst of calls reuses connections
+        // rather than each client opening its own
+        let mut builder = hyper::Client::builder();
+        builder.pool_idle_timeout(POOL_IDLE_TIMEOUT);
+        let http_client = HttpClient::from_builder(builder, HttpsConnector::new());
+        let calls = CallLog::default();
+        let client = Client::new_with(
+            credentials.clone(),
+            Recording::new(http_client, calls.clone()),
+        );
+        let http = reqwest::Client::builder()
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .build()?;
+        let mut clients = Clients::with_client(client, http, region, endpoints, calls);
+        clients.credentials = Some(credentials);
+        Ok(clients)
+    }
+
+    // made-up credentials for a server of our own on localhost, --demo's or the tests' mock, which signs
+    // requests the same as real ones would
+    pub fn local(region: Region) -> Result<Clients, Box<dyn Error + Send + Sync>> {
+        let credentials = StaticProvider::new_minimal("AKIDLOCAL".to_owned(), "local".to_owned());
+        let calls = CallLog::default();
+        Ok(Clients::with_client(
+            Client::new_with(
+                credentials,
+                Recording::new(HttpClient::new()?, calls.clone()),
+            ),
+            reqwest::Client::new(),
+            region,
+            EndpointConfig::default(),
+            calls,
+        ))
+    }
+
+    // the same credentials and connections pointed at another region, for cross-region actions
+    pub fn in_region(&self, region: Region) -> Clients {
+        Clients {
+            credentials: self.credentials.clone(),
+            ..Clients::with_client(
+                self.client.clone(),
+                self.http.clone(),
+                region,
+                self.endpoints,
+                self.calls.clone(),
+            )
+        }
+    }
+
+    fn with_client(
+        client: Client,
+        http: reqwest::Client,
+        region: Region,
+        endpoints: EndpointConfig,
+        calls: CallLog,
+    ) -> Clients {
+        let endpoint = |service| partition::endpoint(&region, service, endpoints);
+        Clients {
+            codepipeline: CodePipelineClient::new_with_client(
+                client.clone(),
+                endpoint("codepipeline"),
+            ),
+            codecommit: CodeCommitClient::new_with_client(client.clone(), endpoint("codecommit")),
+            codestar_connections: CodeStarConnectionsClient::new_with_client(
+                client.clone(),
+                endpoint("codestar-connections"),
+            ),
+            codebuild: CodeBuildClient::new_with_client(client.clone(), endpoint("codebuild")),
+            cloudformation: CloudFormationClient::new_with_client(
+                client.clone(),
+                endpoint("cloudformation"),
+            ),
+            codedeploy: CodeDeployClient::new_with_client(client.clone(), endpoint("codedeploy")),
+            ecs: EcsClient::new_with_client(client.clone(), endpoint("ecs")),
+            sts: StsClient::new_with_client(client.clone(), endpoint("sts")),
+            logs: CloudWatchLogsClient::new_with_client(client.clone(), endpoint("logs")),
+            cloudwatch: CloudWatchClient::new_with_client(client.clone(), endpoint("monitoring")),
+            client,
+            http,
+            region,
+            endpoints,
+            calls,
+            credentials: None,
+        }
+    }
+}
+
+// a pipeline as it appears in the selector
+#[derive(Clone, Debug)]
+pub struct PipelineListing {
+    pub name: String,
+    // sorted by key
+    pub tags: Vec<(String, String)>,
+    // how long its recent executions took, oldest first
+    pub durations: Vec<u64>,
+    // how its latest execution went, if it's ever run, and which execution that was
+    pub latest_status: Option<String>,
+    pub latest_execution_id: Option<String>,
+    // when that execution last moved on, as a Unix timestamp
+    pub last_run: Option<f64>,
+    // when the oldest of its recent executions that's still going started, if any are
+    pub running_since: Option<f64>,
+}
+
+// the account the credentials belong to
+pub async fn account_id(clients: &Clients) -> Result<String, Box<dyn Error + Send + Sync>> {
+    Ok(clients
+        .sts
+        .get_caller_identity(GetCallerIdentityRequest {})
+        .await?
+        .account
+        .ok_or("Couldn't work out which account we're in")?)
+}
+
+// the user or role the credentials belong to, for the audit log
+pub async fn caller_arn(clients: &Clients) -> Result<String, Box<dyn Error + Send + Sync>> {
+    Ok(clients
+        .sts
+        .get_caller_identity(GetCallerIdentityRequest {})
+        .await?
+        .arn
+        .ok_or("Couldn't work out who we're signed in as")?)
+}
+
+impl PipelineListing {
+    // what a poll of the pipeline found, for the fleet view's pipelines being polled like the open one is
+    pub fn follow(&mut self, executions: &[PipelineExecutionSummary]) {
+        let recent = Recent::of(executions);
+        self.durations = recent.durations;
+        self.latest_status = recent.latest_status;
+        self.latest_execution_id = recent.latest_execution_id;
+        self.last_run = recent.last_run;
+        self.running_since = recent.running_since;
+    }
+
+    // just the name, until the rest has been looked up
+    pub fn new(name: String) -> PipelineListing {
+        PipelineListing {
+            name,
+            tags: vec![],
+            durations: vec![],
+            latest_status: None,
+            latest_execution_id: None,
+            last_run: None,
+            running_since: None,
+        }
+    }
+}
+
+// the name of every pipeline in the region, which is quick, unlike everything else the selector shows
+pub async fn list_pipeline_names(
+    clients: &Clients,
+) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+    let mut names = vec![];
+    let mut next_token = None;
+    loop {
+        let page = clients
+            .codepipeline
+            .list_pipelines(ListPipelinesInput { next_token })
+            .await?;
+        names.extend(
+            page.pipelines
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|pipeline| pipeline.name),
+        );
+        next_token = page.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+    Ok(names)
+}
+
+// the tags and recent executions of each of these pipelines
+pub async fn fetch_listings(
+    clients: &Clients,
+    account: &str,
+    names: Vec<String>,
+    permits: &Semaphore,
+) -> Vec<PipelineListing> {
+    // a few hundred pipelines would be a few hundred requests at once, so each waits for a permit
+    join_all(names.into_iter().map(|name| async move {
+        let _permit = permits.acquire().await;
+        // tags are looked up by ARN, which list_pipelines doesn't give us, but we can build it from the account
+        // ID
+        let tags = fetch_tags(clients, account, &name).await;
+        let recent = fetch_recent(clients, &name).await;
+        PipelineListing {
+            name,
+            tags,
+            durations: recent.durations,
+            latest_status: recent.latest_status,
+            latest_execution_id: recent.latest_execution_id,
+            last_run: recent.last_run,
+            running_since: recent.running_since,
+        }
+    }))
+    .await
+}
+
+// what the listing shows of a pipeline's recent executions
+#[derive(Default)]
+struct Recent {
+    durations: Vec<u64>,
+    latest_status: Option<String>,
+    latest_execution_id: Option<String>,
+    last_run: Option<f64>,
+    running_since: Option<f64>,
+}
+
+impl Recent {
+    fn of(executions: &[PipelineExecutionSummary]) -> Recent {
+        let latest = executions.first();
+        Recent {
+            durations: crate::stats::execution_durations(executions),
+            latest_status: latest.and_then(|execution| execution.status.clone()),
+            latest_execution_id: latest
+                .and_then(|execution| execution.pipeline_execution_id.clone()),
+            last_run: latest
+                .and_then(|execution| execution.last_update_time.or(execution.start_time)),
+            // the latest one isn't necessarily the only one going
+            running_since: executions
+                .iter()
+                .filter(|execution| execution.status.as_deref() == Some("InProgress"))
+                .filter_map(|execution| execution.start_time)
+                .fold(None, |oldest: Option<f64>, start| {
+                    Some(oldest.map_or(start, |oldest| oldest.min(start)))
+                }),
+        }
+    }
+}
+
+// for the selector's sparklines and statuses, which are nice to have but not worth failing over
+async fn fetch_recent(clients: &Clients, name: &str) -> Recent {
+    match clients
+        .codepipeline
+        .list_pipeline_executions(ListPipelineExecutionsInput {
+            pipeline_name: name.to_owned(),
+            max_results: Some(20),
+            next_token: None,
+        })
+        .await
+    {
+        Ok(output) => Recent::of(&output.pipeline_execution_summaries.unwrap_or_default()),
+        Err(e) => {
+            warn!("Couldn't get executions for pipeline {}: {}", name, e);
+            Recent::default()
+        }
+    }
+}
+
+pub async fn fetch_tags(clients: &Clients, account: &str, name: &str) -> Vec<(String, String)> {
+    let arn = format!(
+        "arn:{}:codepipeline:{}:{}:{}",
+        partition::for_region(clients.region.name()).name,
+        clients.region.name(),
+        account,
+        name
+    );
+    match clients
+        .codepipeline
+        .list_tags_for_resource(ListTagsForResourceInput {
+            resource_arn: arn,
+            max_results: None,
+            next_token: None,
+        })
+        .await
+    {
+        Ok(output) => {
+            let mut tags = output
+                .tags
+                .unwrap_or_default()
+                .into_iter()
+                .map(|tag| (tag.key, tag.value))
+                .collect::<Vec<_>>();
+            tags.sort();
+            tags
+        }
+        // not being allowed to see a pipeline's tags shouldn't stop us listing it
+        Err(e) => {
+            warn!("Couldn't get tags for pipeline {}: {}", name, e);
+            vec![]
+        }
+    }
+}
+
+// one refresh's worth of information about a pipeline
+#[derive(Clone, Debug, Default)]
+pub struct Pipeline {
+    pub name: String,
+    pub stage_states: Vec<StageStateV2>,
+    // newest first
+    pub executions: Vec<PipelineExecutionSummary>,
+    // for a parallel pipeline, each running execution's own view of the stages, newest first
+    pub concurrent: Vec<ConcurrentExecution>,
+}
+
+// the stages as one of several executions running at once sees them
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConcurrentExecution {
+    pub execution_id: String,
+    pub stage_states: Vec<StageStateV2>,
+}
+
+impl Pipeline {
+    // the newest execution and how it's going, as far as the stages can tell without the execution list: the
+    // one the first stage has seen last, failed or stopped if any stage says so, succeeded once the last stage
+    // has, and in progress until then, between stages included
+    pub fn latest_run(&self) -> Option<(String, String)> {
+        let id = self
+            .stage_states
+            .iter()
+            .find_map(|stage| stage.state.latest_execution.as_ref())?
+            .pipeline_execution_id
+            .clone();
+        let statuses = self
+            .stage_states
+            .iter()
+            .filter_map(|stage| stage.state.latest_execution.as_ref())
+            .filter(|execution| execution.pipeline_execution_id == id)
+            .map(|execution| execution.status.as_str())
+            .collect::<Vec<_>>();
+        let last = self
+            .stage_states
+            .last()
+            .and_then(|stage| stage.state.latest_execution.as_ref())
+            .filter(|execution| execution.pipeline_execution_id == id);
+        let status = if statuses.contains(&"Failed") {
+            "Failed"
+        } else if statuses.contains(&"Stopped") {
+            "Stopped"
+        } else if statuses.contains(&"InProgress") || statuses.contains(&"Stopping") {
+            "InProgress"
+        } else if last.is_some_and(|last| last.status == "Succeeded") {
+            "Succeeded"
+        } else {
+            "InProgress"
+        };
+        Some((id, status.to_owned()))
+    }
+
+    // whether there's an execution going through the pipeline, or waiting to
+    pub fn active(&self) -> bool {
+        let running =
+            |status: Option<&str>| matches!(status, Some("InProgress") | Some("Stopping"));
+        running(
+            self.latest_run()
+                .as_ref()
+                .map(|(_, status)| status.as_str()),
+        ) || self.stage_states.iter().any(|stage| {
+            running(
+                stage
+                    .state
+                    .latest_execution
+                    .as_ref()
+                    .map(|execution| execution.status.as_str()),
+            ) || !stage.inbound_executions.is_empty()
+        })
+    }
+
+    // the executions still going, newest first, of which a parallel pipeline can have any number
+    pub fn running_executions(&self) -> Vec<&PipelineExecutionSummary> {
+        self.executions
+            .iter()
+            .filter(|e| still_running(e))
+            .collect()
+    }
+
+    pub fn concurrent_execution(&self, execution_id: &str) -> Option<&ConcurrentExecution> {
+        self.concurrent
+            .iter()
+            .find(|concurrent| concurrent.execution_id == execution_id)
+    }
+
+    // make every stage show what one particular execution did there, rather than whatever went through it last
+    pub fn pin_to(&mut self, execution_id: &str, details: &[ActionExecutionDetailV2]) {
+        for stage in &mut self.stage_states {
+            // when it is the latest, the real state has more in it (error details, approval tokens) than we could rebuild
+            if stage
+                .state
+                .latest_execution
+                .as_ref()
+                .map(|latest| latest.pipeline_execution_id.as_str())
+                == Some(execution_id)
+            {
+                continue;
+            }
+            let stage_details = details
+                .iter()
+                .filter(|detail| detail.detail.stage_name == stage.state.stage_name)
+                .collect::<Vec<_>>();
+            stage.state.latest_execution =
+                pinned_stage_status(&stage_details).map(|status| StageExecution {
+                    pipeline_execution_id: execution_id.to_owned(),
+                    status: status.to_owned(),
+                });
+            // they went however they went for the latest execution, which isn't this one
+            stage.before_entry_condition_state = None;
+            stage.on_success_condition_state = None;
+            stage.on_failure_condition_state = None;
+            for action in stage.state.action_states.iter_mut().flatten() {
+                // details are newest first, so a retried action's latest attempt wins
+                let detail = stage_details
+                    .iter()
+                    .find(|detail| detail.detail.action_name == action.action_name);
+                action.latest_execution = detail.map(|detail| {
+                    let result = detail
+                        .detail
+                        .output
+                        .as_ref()
+                        .and_then(|output| output.execution_result.clone())
+                        .unwrap_or_default();
+                    ActionExecution {
+                        status: detail.detail.status.clone(),
+                        last_status_change: detail.detail.last_update_time,
+                        external_execution_id: result.external_execution_id,
+                        external_execution_url: result.external_execution_url,
+                        summary: result.external_execution_summary,
+                        ..ActionExecution::default()
+                    }
+                });
+            }
+        }
+    }
+}
+
+fn still_running(execution: &PipelineExecutionSummary) -> bool {
+    matches!(
+        execution.status.as_deref(),
+        Some("InProgress") | Some("Stopping")
+    )
+}
+
+// where each of a parallel pipeline's running executions has got to, a ListActionExecutions each (but for the
+// pinned one, whose details we already have), a few at a time. Starting from the stages as GetPipelineState has
+// them rather than pinned to any one execution. One that can't be fetched keeps what it had last time
+pub async fn fetch_concurrent(
+    clients: &Clients,
+    name: &str,
+    stage_states: &[StageStateV2],
+    executions: &[PipelineExecutionSummary],
+    previous: &[ConcurrentExecution],
+    pinned: Option<(&str, &[ActionExecutionDetailV2])>,
+) -> Vec<ConcurrentExecution> {
+    // collected first, a lazy iterator of futures holding on to borrows wouldn't be Send
+    let fetches = executions
+        .iter()
+        .filter(|e| still_running(e))
+        .filter_map(|execution| execution.pipeline_execution_id.as_deref())
+        .map(|execution_id| {
+            fetch_one_concurrent(clients, name, stage_states, execution_id, previous, pinned)
+        })
+        .collect::<Vec<_>>();
+    stream::iter(fetches)
+        .buffered(CONCURRENT_FETCHES)
+        .filter_map(future::ready)
+        .collect()
+        .await
+}
+
+async fn fetch_one_concurrent(
+    clients: &Clients,
+    name: &str,
+    stage_states: &[StageStateV2],
+    execution_id: &str,
+    previous: &[ConcurrentExecution],
+    pinned: Option<(&str, &[ActionExecutionDetailV2])>,
+) -> Option<ConcurrentExecution> {
+    let details = match pinned {
+        Some((id, details)) if id == execution_id => details.to_vec(),
+        _ => match fetch_execution_actions(clients, name, execution_id).await {
+            Ok(details) => details,
+            Err(e) => {
+                warn!("Couldn't get actions for execution {}: {}", execution_id, e);
+                return previous
+                    .iter()
+                    .find(|concurrent| concurrent.execution_id == execution_id)
+                    .cloned();
+            }
+        },
+    };
+    let mut pipeline = Pipeline {
+        name: name.to_owned(),
+        stage_states: stage_states.to_vec(),
+        ..Pipeline::default()
+    };
+    pipeline.pin_to(execution_id, &details);
+    Some(ConcurrentExecution {
+        execution_id: execution_id.to_owned(),
+        stage_states: pipeline.stage_states,
+    })
+}
+
+// a stage's status going by its actions, or nothing if the execution never got that far
+fn pinned_stage_status(details: &[&ActionExecutionDetailV2]) -> Option<&'static str> {
+    if details.is_empty() {
+        return None;
+    }
+    let statuses = details
+        .iter()
+        .map(|detail| detail.detail.status.as_deref())
+        .collect::<Vec<_>>();
+    Some(if statuses.contains(&Some("InProgress")) {
+        "InProgress"
+    } else if statuses.contains(&Some("Failed")) {
+        "Failed"
+    } else if statuses.contains(&Some("Abandoned")) {
+        "Stopped"
+    } else {
+        "Succeeded"
+    })
+}
+
+pub async fn fetch_pipeline(
+    clients: &Clients,
+    name: &str,
+) -> Result<Pipeline, Box<dyn Error + Send + Sync>> {
+    Ok(Pipeline {
+        name: name.to_owned(),
+        stage_states: fetch_state(clients, name).await?,
+        executions: fetch_executions(clients, name).await?,
+        concurrent: vec![],
+    })
+}
+
+// the one call a poll makes, unless it's pinned to an execution or a view needs more
+pub async fn fetch_state(
+    clients: &Clients,
+    name: &str,
+) -> Result<Vec<StageStateV2>, Box<dyn Error + Send + Sync>> {
+    debug!("Getting state for pipeline {}...", name);
+    // rusoto's get_pipeline_state drops the inbound executions, so make the call ourselves
+    let state: PipelineState = call(
+        clients,
+        "GetPipelineState",
+        &GetPipelineStateRequest { name },
+    )
+    .await?;
+    Ok(state.stage_states)
+}
+
+pub async fn fetch_executions(
+    clients: &Clients,
+    name: &str,
+) -> Result<Vec<PipelineExecutionSummary>, Box<dyn Error + Send + Sync>> {
+    debug!("Getting recent executions for pipeline {}...", name);
+    Ok(clients
+        .codepipeline
+        .list_pipeline_executions(ListPipelineExecutionsInput {
+            pipeline_name: name.to_owned(),
+            max_results: Some(20),
+            next_token: None,
+        })
+        .await?
+        .pipeline_execution_summaries
+        .unwrap_or_default())
+}
+
+// rusoto_codepipeline was generated before CodePipeline grew some of the fields we care about (inbound executions, etc.)
+// rusoto ignores anything it doesn't know about when it deserializes, so for those calls we build the same signed JSON request ourselves
+// and deserialize the response into our own types instead
+pub async fn call<I, O>(
+    clients: &Clients,
+    operation: &str,
+    input: &I,
+) -> Result<O, Box<dyn Error + Send + Sync>>
+where
+    I: Serialize,
+    O: DeserializeOwned,
+{
+    let region = partition::endpoint(&clients.region, "codepipeline", clients.endpoints);
+    let mut request = SignedRequest::new("POST", "codepipeline", &region, "/");
+    request.set_content_type("application/x-amz-json-1.1".to_owned());
+    request.add_header(
+        "x-amz-target",
+        &format!("CodePipeline_20150709.{}", operation),
+    );
+    request.set_payload(Some(serde_json::to_vec(input)?));
+
+    // SignAndDispatchError doesn't implement Error, so turn it into something that does
+    let mut response = clients
+        .client
+        .sign_and_dispatch(request)
+        .await
+        .map_err(|e| format!("{} failed: {:?}", operation, e))?;
+    let response = response.buffer().await?;
+    if !response.status.is_success() {
+        return Err(format!(
+            "{} failed with {}: {}",
+            operation,
+            response.status,
+            response.body_as_str()
+        )
+        .into());
+    }
+
+    Ok(serde_json::from_slice(&response.body)?)
+}
+
+#[derive(Serialize)]
+pub struct GetPipelineStateRequest<'a> {
+    pub name: &'a str,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineState {
+    #[serde(default)]
+    pub stage_states: Vec<StageStateV2>,
+}
+
+// everything rusoto already knows about a stage lives in `state`, the fields it's missing sit next to it
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StageStateV2 {
+    #[serde(flatten)]
+    pub state: StageState,
+    pub inbound_execution: Option<InboundExecution>,
+    #[serde(default)]
+    pub inbound_executions: Vec<InboundExecution>,
+    // how the stage's conditions went for its latest execution
+    pub before_entry_condition_state: Option<StageConditionState>,
+    pub on_success_condition_state: Option<StageConditionState>,
+    pub on_failure_condition_state: Option<StageConditionState>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StageConditionState {
+    pub latest_execution: Option<ConditionExecution>,
+    #[serde(default)]
+    pub condition_states: Vec<ConditionState>,
+}
+
+// InProgress, Succeeded, Failed, Errored, Cancelled, Abandoned or Overridden, for a condition and its rules alike
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConditionExecution {
+    pub status: String,
+    pub summary: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConditionState {
+    pub latest_execution: Option<ConditionExecution>,
+    #[serde(default)]
+    pub rule_states: Vec<RuleState>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleState {
+    pub rule_name: Option<String>,
+    pub latest_execution: Option<ConditionExecution>,
+}
+
+impl StageConditionState {
+    pub fn status(&self) -> Option<&str> {
+        self.latest_execution
+            .as_ref()
+            .map(|execution| execution.status.as_str())
+    }
+}
+
+// an execution that's waiting to enter a stage (e.g. queued behind the one currently running it)
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InboundExecution {
+    pub pipeline_execution_id: String,
+    pub status: String,
+}
+
+impl StageStateV2 {
+    // the entry conditions keeping the execution out, or still making up their minds, which isn't the same as one
+    // of the stage's actions failing
+    pub fn blocked_by_conditions(&self) -> Option<&StageConditionState> {
+        self.before_entry_condition_state.as_ref().filter(|state| {
+            matches!(
+                state.status(),
+                Some("InProgress") | Some("Failed") | Some("Errored")
+            )
+        })
+    }
+
+    // the API reports a single `inboundExecution` and (for newer pipelines) a list of `inboundExecutions`,
+    // which usually overlap, so merge them into one list without duplicates
+    pub fn inbound(&self) -> Vec<&InboundExecution> {
+        let mut inbound = self.inbound_executions.iter().collect::<Vec<_>>();
+        if let Some(execution) = &self.inbound_execution {
+            if !inbound
+                .iter()
+                .any(|e| e.pipeline_execution_id == execution.pipeline_execution_id)
+            {
+                inbound.insert(0, execution);
+            }
+        }
+        inbound
+    }
+}
+
+// the pipeline's structure (stages, actions and their configuration), which only changes when someone edits the pipeline
+pub async fn fetch_definition(
+    clients: &Clients,
+    name: &str,
+) -> Result<PipelineDeclarationV2, Box<dyn Error + Send + Sync>> {
+    // rusoto's GetPipeline leaves out pipeline-level variables, so go around it
+    let output: serde_json::Value =
+        call(clients, "GetPipeline", &GetPipelineRequest { name }).await?;
+    let mut definition = serde_json::from_value::<GetPipelineOutputV2>(output.clone())?.pipeline;
+    // and the stages' conditions, which come from the same stages a second time
+    if let Some(stages) = output["pipeline"].get("stages") {
+        definition.conditions = serde_json::from_value(stages.clone())?;
+    }
+    Ok(definition)
+}
+
+#[derive(Serialize)]
+pub struct GetPipelineRequest<'a> {
+    pub name: &'a str,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GetPipelineOutputV2 {
+    pub pipeline: PipelineDeclarationV2,
+}
+
+// the pipeline definition plus the newer fields rusoto doesn't know about
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PipelineDeclarationV2 {
+    #[serde(flatten)]
+    pub declaration: PipelineDeclaration,
+    // V2 pipelines can declare variables that get set when an execution is started
+    #[serde(default)]
+    pub variables: Vec<VariableDeclaration>,
+    #[serde(rename = "executionMode", default)]
+    pub execution_mode: ExecutionMode,
+    // one for every stage, in the same order
+    #[serde(skip)]
+    pub conditions: Vec<StageConditions>,
+}
+
+// the rules a stage checks before letting an execution in, once its actions have succeeded, and once one of them
+// has failed, e.g. a CloudWatch alarm that has to be quiet or a deployment window
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StageConditions {
+    pub name: String,
+    pub before_entry: Option<Conditions>,
+    pub on_success: Option<Conditions>,
+    pub on_failure: Option<FailureConditions>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Conditions {
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FailureConditions {
+    // ROLLBACK, RETRY or FAIL, what happens to a stage that's failed when there are no conditions or they're met
+    pub result: Option<String>,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    // FAIL or SKIP for an entry condition's rules not being met
+    pub result: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<RuleDeclaration>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleDeclaration {
+    pub name: String,
+    pub rule_type_id: RuleTypeId,
+    #[serde(default)]
+    pub configuration: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleTypeId {
+    // CloudWatchAlarm, DeploymentWindow, LambdaInvoke, VariableCheck, Commands...
+    pub provider: String,
+}
+
+impl StageConditions {
+    pub fn is_empty(&self) -> bool {
+        self.before_entry.is_none() && self.on_success.is_none() && self.on_failure.is_none()
+    }
+
+    // a rule going by the name its state has, for its provider and configuration
+    pub fn rule(&self, name: &str) -> Option<&RuleDeclaration> {
+        let entry = self.before_entry.iter().flat_map(|c| &c.conditions);
+        let success = self.on_success.iter().flat_map(|c| &c.conditions);
+        let failure = self.on_failure.iter().flat_map(|c| &c.conditions);
+        entry
+            .chain(success)
+            .chain(failure)
+            .flat_map(|condition| &condition.rules)
+            .find(|rule| rule.name == name)
+    }
+}
+
+// what the pipeline does with a new execution while another's still going through it
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ExecutionMode {
+    // the newer one takes over as soon as it reaches a stage, what a pipeline does unless it says otherwise
+    #[default]
+    Superseded,
+    // the newer one waits its turn at each stage
+    Queued,
+    // they all run at once, each on its own, so a stage has no single latest execution worth talking about
+    Parallel,
+}
+
+impl ExecutionMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExecutionMode::Superseded => "superseded",
+            ExecutionMode::Queued => "queued",
+            ExecutionMode::Parallel => "parallel",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariableDeclaration {
+    pub name: String,
+    pub default_value: Option<String>,
+    pub description: Option<String>,
+}
+
+// the values a particular execution's pipeline-level variables ended up with
+pub async fn fetch_execution_variables(
+    clients: &Clients,
+    pipeline_name: &str,
+    pipeline_execution_id: &str,
+) -> Result<Vec<ResolvedVariable>, Box<dyn Error + Send + Sync>> {
+    let output: GetPipelineExecutionOutputV2 = call(
+        clients,
+        "GetPipelineExecution",
+        &GetPipelineExecutionRequest {
+            pipeline_name,
+            pipeline_execution_id,
+        },
+    )
+    .await?;
+    Ok(output.pipeline_execution.variables)
+}
+
+// where a single execution has got to (InProgress, Succeeded, Failed, ...), however many have started since
+pub async fn fetch_execution_status(
+    clients: &Clients,
+    pipeline_name: &str,
+    pipeline_execution_id: &str,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let output: GetPipelineExecutionOutputV2 = call(
+        clients,
+        "GetPipelineExecution",
+        &GetPipelineExecutionRequest {
+            pipeline_name,
+            pipeline_execution_id,
+        },
+    )
+    .await?;
+    Ok(output
+        .pipeline_execution
+        .status
+        .unwrap_or_else(|| "Unknown".to_owned()))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPipelineExecutionRequest<'a> {
+    pub pipeline_name: &'a str,
+    pub pipeline_execution_id: &'a str,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPipelineExecutionOutputV2 {
+    pub pipeline_execution: PipelineExecutionV2,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PipelineExecutionV2 {
+    pub status: Option<String>,
+    #[serde(default)]
+    pub variables: Vec<ResolvedVariable>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedVariable {
+    pub name: String,
+    pub resolved_value: String,
+}
+
+// the most recent executions that made it all the way through the given stage, newest first
+pub async fn fetch_succeeded_in_stage(
+    clients: &Clients,
+    pipeline_name: &str,
+    stage_name: &str,
+) -> Result<Vec<PipelineExecutionSummary>, Box<dyn Error + Send + Sync>> {
+    // rusoto's ListPipelineExecutions has no filter
+    let output: PipelineExecutionSummaries = call(
+        clients,
+        "ListPipelineExecutions",
+        &ListPipelineExecutionsRequest {
+            pipeline_name,
+            filter: PipelineExecutionFilter {
+                succeeded_in_stage: SucceededInStageFilter { stage_name },
+            },
+        },
+    )
+    .await?;
+    Ok(output.pipeline_execution_summaries)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPipelineExecutionsRequest<'a> {
+    pub pipeline_name: &'a str,
+    pub filter: PipelineExecutionFilter<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineExecutionFilter<'a> {
+    pub succeeded_in_stage: SucceededInStageFilter<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SucceededInStageFilter<'a> {
+    pub stage_name: &'a str,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineExecutionSummaries {
+    #[serde(default)]
+    pub pipeline_execution_summaries: Vec<PipelineExecutionSummary>,
+}
+
+// put a stage back to what it ran in an earlier successful execution, returning the ID of the rollback execution
+pub async fn rollback_stage(
+    clients: &Clients,
+    pipeline_name: &str,
+    stage_name: &str,
+    target_pipeline_execution_id: &str,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let output: StartPipelineExecutionOutputV2 = call(
+        clients,
+        "RollbackStage",
+        &RollbackStageRequest {
+            pipeline_name,
+            stage_name,
+            target_pipeline_execution_id,
+        },
+    )
+    .await?;
+    Ok(output.pipeline_execution_id)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackStageRequest<'a> {
+    pub pipeline_name: &'a str,
+    pub stage_name: &'a str,
+    pub target_pipeline_execution_id: &'a str,
+}
+
+// let executions move into the stage again
+pub async fn enable_transition(
+    clients: &Clients,
+    pipeline_name: &str,
+    stage_name: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    clients
+        .codepipeline
+        .enable_stage_transition(EnableStageTransitionInput {
+            pipeline_name: pipeline_name.to_owned(),
+            stage_name: stage_name.to_owned(),
+            transition_type: "Inbound".to_owned(),
+        })
+        .await?;
+    Ok(())
+}
+
+// hold executions before the stage, e.g. while something downstream is being fixed
+pub async fn disable_transition(
+    clients: &Clients,
+    pipeline_name: &str,
+    stage_name: &str,
+    reason: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    clients
+        .codepipeline
+        .disable_stage_transition(DisableStageTransitionInput {
+            pipeline_name: pipeline_name.to_owned(),
+            stage_name: stage_name.to_owned(),
+            transition_type: "Inbound".to_owned(),
+            reason: reason.to_owned(),
+        })
+        .await?;
+    Ok(())
+}
+
+// answer the manual approval the action is waiting on, which takes the token from its current execution,
+// returning the ID of the execution that was waiting
+pub async fn submit_approval(
+    clients: &Clients,
+    pipeline_name: &str,
+    stage_name: &str,
+    action_name: &str,
+    approved: bool,
+    summary: &str,
+) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+    let states = fetch_state(clients, pipeline_name).await?;
+    let stage = states
+        .iter()
+        .find(|stage| stage.state.stage_name.as_deref() == Some(stage_name))
+        .ok_or_else(|| format!("{} has no stage called {}", pipeline_name, stage_name))?;
+    let action = stage
+        .state
+        .action_states
+        .iter()
+        .flatten()
+        .find(|action| action.action_name.as_deref() == Some(action_name))
+        .ok_or_else(|| format!("{} has no action called {}", stage_name, action_name))?;
+    let token = action
+        .latest_execution
+        .as_ref()
+        .filter(|execution| execution.status.as_deref() == Some("InProgress"))
+        .and_then(|execution| execution.token.clone())
+        .ok_or_else(|| format!("{} isn't waiting for an approval", action_name))?;
+    clients
+        .codepipeline
+        .put_approval_result(PutApprovalResultInput {
+            pipeline_name: pipeline_name.to_owned(),
+            stage_name: stage_name.to_owned(),
+            action_name: action_name.to_owned(),
+            result: ApprovalResult {
+                status: if approved { "Approved" } else { "Rejected" }.to_owned(),
+                summary: summary.to_owned(),
+            },
+            token,
+        })
+        .await?;
+    Ok(stage
+        .state
+        .latest_execution
+        .as_ref()
+        .map(|execution| execution.pipeline_execution_id.clone()))
+}
+
+// run a failed stage again within the same execution, just the actions that failed or all of them
+pub async fn retry_stage(
+    clients: &Clients,
+    pipeline_name: &str,
+    stage_name: &str,
+    all_actions: bool,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let states = fetch_state(clients, pipeline_name).await?;
+    let execution = states
+        .iter()
+        .find(|stage| stage.state.stage_name.as_deref() == Some(stage_name))
+        .ok_or_else(|| format!("{} has no stage called {}", pipeline_name, stage_name))?
+        .state
+        .latest_execution
+        .as_ref()
+        .ok_or_else(|| format!("{} has never run", stage_name))?;
+    let output = clients
+        .codepipeline
+        .retry_stage_execution(RetryStageExecutionInput {
+            pipeline_name: pipeline_name.to_owned(),
+            stage_name: stage_name.to_owned(),
+            pipeline_execution_id: execution.pipeline_execution_id.clone(),
+            retry_mode: if all_actions {
+                "ALL_ACTIONS"
+            } else {
+                "FAILED_ACTIONS"
+            }
+            .to_owned(),
+        })
+        .await?;
+    Ok(output
+        .pipeline_execution_id
+        .unwrap_or_else(|| execution.pipeline_execution_id.clone()))
+}
+
+// stop an execution, letting the running actions finish unless it's abandoned
+pub async fn stop_execution(
+    clients: &Clients,
+    pipeline_name: &str,
+    pipeline_execution_id: &str,
+    abandon: bool,
+    reason: Option<String>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    clients
+        .codepipeline
+        .stop_pipeline_execution(StopPipelineExecutionInput {
+            pipeline_name: pipeline_name.to_owned(),
+            pipeline_execution_id: pipeline_execution_id.to_owned(),
+            abandon: Some(abandon),
+            reason,
+        })
+        .await?;
+    Ok(())
+}
+
+// kick off a new execution with the given variable values, returning its ID
+pub async fn start_execution(
+    clients: &Clients,
+    name: &str,
+    variables: &[(String, String)],
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let output: StartPipelineExecutionOutputV2 = call(
+        clients,
+        "StartPipelineExecution",
+        &StartPipelineExecutionRequest {
+            name,
+            variables: variables
+                .iter()
+                .map(|(name, value)| PipelineVariable { name, value })
+                .collect(),
+        },
+    )
+    .await?;
+    Ok(output.pipeline_execution_id)
+}
+
+#[derive(Serialize)]
+pub struct StartPipelineExecutionRequest<'a> {
+    pub name: &'a str,
+    // V1 pipelines reject the field entirely, even when it's empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<PipelineVariable<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct PipelineVariable<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartPipelineExecutionOutputV2 {
+    pub pipeline_execution_id: String,
+}
+
+pub async fn fetch_build(
+    clients: &Clients,
+    build_id: &str,
+) -> Result<Build, Box<dyn Error + Send + Sync>> {
+    let build = clients
+        .codebuild
+        .batch_get_builds(BatchGetBuildsInput {
+            ids: vec![build_id.to_owned()],
+        })
+        .await?
+        .builds
+        .and_then(|builds| builds.into_iter().next())
+        .ok_or_else(|| format!("Couldn't find build {}", build_id))?;
+    Ok(build)
+}
+
+// the log lines written after `next_token` (or from the start without one), and where to carry on from next time
+pub async fn fetch_log_events(
+    clients: &Clients,
+    group: &str,
+    stream: &str,
+    mut next_token: Option<String>,
+) -> Result<(Vec<String>, Option<String>), Box<dyn Error + Send + Sync>> {
+    let mut lines = vec![];
+    // a chatty build can have a lot of catching up to do, leave the rest for the next refresh
+    for _ in 0..MAX_LOG_PAGES {
+        let response = clients
+            .logs
+            .get_log_events(GetLogEventsRequest {
+                log_group_name: group.to_owned(),
+                log_stream_name: stream.to_owned(),
+                next_token: next_token.clone(),
+                start_from_head: Some(true),
+                ..Default::default()
+            })
+            .await?;
+        for event in response.events.unwrap_or_default() {
+            let message = event.message.unwrap_or_default();
+            lines.extend(message.trim_end().lines().map(str::to_owned));
+        }
+        // the token stays the same once we've reached the end
+        let done =
+            response.next_forward_token.is_none() || response.next_forward_token == next_token;
+        if response.next_forward_token.is_some() {
+            next_token = response.next_forward_token;
+        }
+        if done {
+            break;
+        }
+    }
+    Ok((lines, next_token))
+}
+
+// newest first, the first page is plenty to see what went wrong in the latest deployment
+pub async fn fetch_stack_events(
+    clients: &Clients,
+    stack_name: &str,
+) -> Result<Vec<StackEvent>, Box<dyn Error + Send + Sync>> {
+    Ok(clients
+        .cloudformation
+        .describe_stack_events(DescribeStackEventsInput {
+            next_token: None,
+            stack_name: Some(stack_name.to_owned()),
+        })
+        .await?
+        .stack_events
+        .unwrap_or_default())
+}
+
+// the pipeline a stack (a CDK app's pipeline stack, say) created, whatever name CDK generated for it: a pipeline
+// in the stack or any of its nested stacks, or failing that an output pointing at one
+pub async fn pipeline_for_stack(
+    clients: &Clients,
+    stack_name: &str,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let mut pipelines = vec![];
+    let mut stacks = VecDeque::from(vec![stack_name.to_owned()]);
+    while let Some(stack) = stacks.pop_front() {
+        let mut next_token = None;
+        loop {
+            let page = clients
+                .cloudformation
+                .list_stack_resources(ListStackResourcesInput {
+                    next_token,
+                    stack_name: stack.clone(),
+                })
+                .await?;
+            for resource in page.stack_resource_summaries.unwrap_or_default() {
+                match (
+                    resource.resource_type.as_str(),
+                    resource.physical_resource_id,
+                ) {
+                    ("AWS::CodePipeline::Pipeline", Some(name)) => pipelines.push(name),
+                    // a nested stack's physical ID is its ARN, which works as a stack name
+                    ("AWS::CloudFormation::Stack", Some(arn)) => stacks.push_back(arn),
+                    _ => {}
+                }
+            }
+            next_token = page.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+    }
+
+    if pipelines.is_empty() {
+        let outputs = clients
+            .cloudformation
+            .describe_stacks(DescribeStacksInput {
+                next_token: None,
+                stack_name: Some(stack_name.to_owned()),
+            })
+            .await?
+            .stacks
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|stack| stack.outputs.unwrap_or_default());
+        for output in outputs {
+            let is_pipeline = output
+                .output_key
+                .as_deref()
+                .is_some_and(|key| key.to_lowercase().contains("pipeline"));
+            if let (true, Some(value)) = (is_pipeline, output.output_value) {
+                // either the name or the ARN, which ends in the name
+                pipelines.push(match value.strip_prefix("arn:") {
+                    Some(_) => value.rsplit(':').next().unwrap_or_default().to_owned(),
+                    None => value,
+                });
+            }
+        }
+    }
+
+    match pipelines.len() {
+        0 => Err(format!("{} doesn't have a pipeline in it", stack_name).into()),
+        1 => Ok(pipelines.remove(0)),
+        _ => Err(format!(
+            "{} has more than one pipeline in it ({}), pick one with --pipeline",
+            stack_name,
+            pipelines.join(", ")
+        )
+        .into()),
+    }
+}
+
+// previous runs of one particular action, newest first, and whether we stopped looking before finding `wanted`
+// finished ones
+#[derive(Clone, Debug, Default)]
+pub struct ActionRuns {
+    pub runs: Vec<ActionExecutionDetailV2>,
+    pub cut_off: bool,
+}
+
+impl ActionRuns {
+    // how far back we looked when cut off, in every action's executions
+    pub const LOOKED_THROUGH: usize = MAX_ACTION_PAGES * ACTION_PAGE_SIZE as usize;
+}
+
+pub async fn fetch_action_executions(
+    clients: &Clients,
+    pipeline_name: &str,
+    stage_name: &str,
+    action_name: &str,
+    wanted: usize,
+) -> Result<ActionRuns, Box<dyn Error + Send + Sync>> {
+    // there's no filter for a single action, so go through every action's executions a page at a time and pick
+    // ours out, giving up after a few pages on a pipeline with a lot of other actions
+    let mut runs: Vec<ActionExecutionDetailV2> = vec![];
+    let mut next_token = None;
+    for _ in 0..MAX_ACTION_PAGES {
+        let page: ActionExecutions = call(
+            clients,
+            "ListActionExecutions",
+            &ListActionExecutionsRequest {
+                pipeline_name,
+                filter: None,
+                max_results: ACTION_PAGE_SIZE,
+                next_token,
+            },
+        )
+        .await?;
+        runs.extend(
+            page.action_execution_details
+                .into_iter()
+                .filter(|execution| {
+                    execution.detail.stage_name.as_deref() == Some(stage_name)
+                        && execution.detail.action_name.as_deref() == Some(action_name)
+                }),
+        );
+        next_token = page.next_token;
+        let finished = runs
+            .iter()
+            .filter(|run| run.detail.status.as_deref() != Some("InProgress"))
+            .count();
+        if next_token.is_none() || finished >= wanted {
+            return Ok(ActionRuns {
+                runs,
+                cut_off: false,
+            });
+        }
+    }
+    Ok(ActionRuns {
+        runs,
+        cut_off: true,
+    })
+}
+
+// every action's executions across the pipeline, newest first, stopping once we have at least `limit` of them
+pub async fn list_action_executions(
+    clients: &Clients,
+    pipeline_name: &str,
+    limit: usize,
+) -> Result<Vec<ActionExecutionDetailV2>, Box<dyn Error + Send + Sync>> {
+    let mut details = vec![];
+    let mut next_token = None;
+    loop {
+        let page: ActionExecutions = call(
+            clients,
+            "ListActionExecutions",
+            &ListActionExecutionsRequest {
+                pipeline_name,
+                filter: None,
+                max_results: 100,
+                next_token,
+            },
+        )
+        .await?;
+        details.extend(page.action_execution_details);
+        next_token = page.next_token;
+        if next_token.is_none() || details.len() >= limit {
+            return Ok(details);
+        }
+    }
+}
+
+// the action executions belonging to a single pipeline execution
+pub async fn fetch_execution_actions(
+    clients: &Clients,
+    pipeline_name: &str,
+    pipeline_execution_id: &str,
+) -> Result<Vec<ActionExecutionDetailV2>, Box<dyn Error + Send + Sync>> {
+    // a big pipeline, or one whose actions were retried a lot, can have more than one page of them
+    let mut details = vec![];
+    let mut next_token = None;
+    loop {
+        let page: ActionExecutions = call(
+            clients,
+            "ListActionExecutions",
+            &ListActionExecutionsRequest {
+                pipeline_name,
+                filter: Some(ActionExecutionFilter {
+                    pipeline_execution_id,
+                }),
+                max_results: ACTION_PAGE_SIZE,
+                next_token,
+            },
+        )
+        .await?;
+        details.extend(page.action_execution_details);
+        next_token = page.next_token;
+        if next_token.is_none() {
+            return Ok(details);
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListActionExecutionsRequest<'a> {
+    pub pipeline_name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<ActionExecutionFilter<'a>>,
+    pub max_results: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionExecutionFilter<'a> {
+    pub pipeline_execution_id: &'a str,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionExecutions {
+    #[serde(default)]
+    pub action_execution_details: Vec<ActionExecutionDetailV2>,
+    pub next_token: Option<String>,
+}
+
+// rusoto doesn't know who last updated an action execution, which for approvals is whoever approved or rejected it
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionExecutionDetailV2 {
+    #[serde(flatten)]
+    pub detail: ActionExecutionDetail,
+    pub updated_by: Option<String>,
+}
+
+// execution IDs are UUIDs, the first chunk is plenty to tell them apart on screen
+pub fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
@@ -0,0 +1,80 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// plenty for flicking between the actions of a few pipelines, without holding on to every build we ever looked at
+const MAX_ENTRIES: usize = 200;
+
+// the calls worth caching, each with its own idea of how long an answer stays good
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Api {
+    Build,
+    StackEvents,
+    Deployment,
+    Approvals,
+    StageActions,
+}
+
+impl Api {
+    // running builds and rollouts move on quickly, but going back and forth between actions shouldn't fetch them
+    // all over again, and approval history hardly changes at all
+    fn ttl(self) -> Duration {
+        match self {
+            Api::Build | Api::StackEvents | Api::Deployment | Api::StageActions => {
+                Duration::from_secs(4)
+            }
+            Api::Approvals => Duration::from_secs(30),
+        }
+    }
+}
+
+struct Entry {
+    at: Instant,
+    value: Box<dyn Any + Send + Sync>,
+}
+
+// recent AWS responses, keyed by the call and whatever it was about (a build ID, a stack name, ...)
+#[derive(Default)]
+pub struct Cache {
+    entries: HashMap<(Api, String), Entry>,
+}
+
+impl Cache {
+    pub fn get<T: Clone + 'static>(&self, api: Api, resource: &str) -> Option<T> {
+        let entry = self.entries.get(&(api, resource.to_owned()))?;
+        if entry.at.elapsed() > api.ttl() {
+            return None;
+        }
+        entry.value.downcast_ref::<T>().cloned()
+    }
+
+    pub fn insert<T: Send + Sync + 'static>(&mut self, api: Api, resource: String, value: T) {
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries
+                .retain(|(api, _), entry| entry.at.elapsed() <= api.ttl());
+        }
+        // still full of fresh answers, so make room by dropping the oldest
+        if self.entries.len() >= MAX_ENTRIES {
+            if let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.at)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(
+            (api, resource),
+            Entry {
+                at: Instant::now(),
+                value: Box::new(value),
+            },
+        );
+    }
+
+    // for a forced refresh, when whatever we have might be out of date
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
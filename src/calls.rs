@@ -0,0 +1,311 @@
+use chrono::Local;
+use rusoto_core::request::{DispatchSignedRequestFuture, HttpResponse};
+use rusoto_core::signature::{SignedRequest, SignedRequestPayload};
+use rusoto_core::{ByteStream, DispatchSignedRequest};
+use serde_json::Value;
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// how far back the debug overlay looks
+const WINDOW: Duration = Duration::from_secs(60);
+// how many calls the inspector keeps the requests and responses of, and how much of each body it shows
+const MAX_EXCHANGES: usize = 50;
+const MAX_BODY_LINES: usize = 200;
+
+// every AWS call the clients have made in the last minute, for the debug overlay (F12) to show where a slow or
+// throttled refresh is spending its time, and what the latest ones sent and got back, for the inspector (I).
+// Cloned into everything that needs it, they all share the one log
+#[derive(Clone, Default)]
+pub struct CallLog {
+    inner: Arc<Mutex<Calls>>,
+}
+
+#[derive(Default)]
+struct Calls {
+    // oldest first
+    recent: VecDeque<Call>,
+    // per API, how many calls in a row haven't gone through, so the next one is that many retries in
+    failing: HashMap<String, u32>,
+    // the latest calls' requests and responses as the inspector (I) shows them, oldest first
+    exchanges: VecDeque<Vec<String>>,
+    // how many of the inspector's lines have gone from the front, for keeping its scrolling steady
+    dropped_lines: usize,
+    // every call there's ever been
+    total: usize,
+}
+
+struct Call {
+    api: String,
+    at: Instant,
+    latency: Duration,
+    retries: u32,
+    succeeded: bool,
+}
+
+// one API's line of the overlay
+pub struct ApiCalls {
+    // the service and the operation, e.g. codepipeline GetPipelineState
+    pub api: String,
+    pub calls: usize,
+    pub failed: usize,
+    // the latest call's, and the slowest in the last minute
+    pub latency: Duration,
+    pub slowest: Duration,
+    // how many failed calls came before the latest one since the API last answered properly, which is how many
+    // times the poller's backoff has had to go round for it
+    pub retries: u32,
+}
+
+impl CallLog {
+    fn record(&self, api: String, started: Instant, succeeded: bool, exchange: Vec<String>) {
+        let mut calls = self.inner.lock().unwrap();
+        calls.total += 1;
+        calls.exchanges.push_back(exchange);
+        if calls.exchanges.len() > MAX_EXCHANGES {
+            if let Some(oldest) = calls.exchanges.pop_front() {
+                calls.dropped_lines += oldest.len();
+            }
+        }
+        let failing = calls.failing.entry(api.clone()).or_default();
+        let retries = *failing;
+        *failing = if succeeded { 0 } else { *failing + 1 };
+        calls.recent.push_back(Call {
+            api,
+            at: started,
+            latency: started.elapsed(),
+            retries,
+            succeeded,
+        });
+        while calls
+            .recent
+            .front()
+            .is_some_and(|call| call.at.elapsed() > WINDOW)
+        {
+            calls.recent.pop_front();
+        }
+    }
+
+    // every API called in the last minute, by name
+    pub fn summary(&self) -> Vec<ApiCalls> {
+        let calls = self.inner.lock().unwrap();
+        let mut apis: BTreeMap<&str, ApiCalls> = BTreeMap::new();
+        for call in calls
+            .recent
+            .iter()
+            .filter(|call| call.at.elapsed() <= WINDOW)
+        {
+            let api = apis.entry(&call.api).or_insert_with(|| ApiCalls {
+                api: call.api.clone(),
+                calls: 0,
+                failed: 0,
+                latency: Duration::default(),
+                slowest: Duration::default(),
+                retries: 0,
+            });
+            api.calls += 1;
+            if !call.succeeded {
+                api.failed += 1;
+            }
+            // oldest first, so the latest call is whichever comes last
+            api.latency = call.latency;
+            api.slowest = api.slowest.max(call.latency);
+            api.retries = call.retries;
+        }
+        apis.into_values().collect()
+    }
+
+    // every line of the inspector, along with how many have been dropped from the front so far
+    pub fn lines(&self) -> (Vec<String>, usize) {
+        let calls = self.inner.lock().unwrap();
+        let lines = calls.exchanges.iter().flatten().cloned().collect();
+        (lines, calls.dropped_lines)
+    }
+
+    pub fn total(&self) -> usize {
+        self.inner.lock().unwrap().total
+    }
+
+    pub fn per_minute(&self) -> usize {
+        let calls = self.inner.lock().unwrap();
+        calls
+            .recent
+            .iter()
+            .filter(|call| call.at.elapsed() <= WINDOW)
+            .count()
+    }
+}
+
+// sits in front of the real HTTP client, timing every request the service clients send through it
+pub struct Recording<D> {
+    dispatcher: D,
+    log: CallLog,
+}
+
+impl<D> Recording<D> {
+    pub fn new(dispatcher: D, log: CallLog) -> Recording<D> {
+        Recording { dispatcher, log }
+    }
+}
+
+impl<D: DispatchSignedRequest> DispatchSignedRequest for Recording<D> {
+    fn dispatch(
+        &self,
+        request: SignedRequest,
+        timeout: Option<Duration>,
+    ) -> DispatchSignedRequestFuture {
+        let api = format!("{} {}", request.service, operation(&request));
+        let sent = match &request.payload {
+            Some(SignedRequestPayload::Buffer(body)) => body_lines(body),
+            Some(SignedRequestPayload::Stream(_)) => vec!["(streamed)".to_owned()],
+            None => vec![],
+        };
+        let log = self.log.clone();
+        let at = Local::now();
+        let started = Instant::now();
+        let response = self.dispatcher.dispatch(request, timeout);
+        Box::pin(async move {
+            let at = at.format("%H:%M:%S");
+            // everything we call answers with a little JSON or XML, so it's read in full here and handed on as
+            // if it hadn't been
+            let buffered = match response.await {
+                Ok(mut response) => response.buffer().await,
+                Err(e) => Err(e),
+            };
+            let buffered = match buffered {
+                Ok(buffered) => buffered,
+                Err(e) => {
+                    let mut exchange = vec![format!(
+                        "{}  {} failed after {}ms: {}",
+                        at,
+                        api,
+                        started.elapsed().as_millis(),
+                        e
+                    )];
+                    exchange.extend(indent("→ ", sent));
+                    log.record(api, started, false, exchange);
+                    return Err(e);
+                }
+            };
+            let succeeded = buffered.status.is_success();
+            let mut exchange = vec![format!(
+                "{}  {} {} {} after {}ms",
+                at,
+                api,
+                if succeeded { "answered" } else { "failed with" },
+                buffered.status.as_u16(),
+                started.elapsed().as_millis()
+            )];
+            exchange.extend(indent("→ ", sent));
+            exchange.extend(indent("← ", body_lines(&buffered.body)));
+            log.record(api, started, succeeded, exchange);
+            Ok(HttpResponse {
+                status: buffered.status,
+                body: ByteStream::from(buffered.body.to_vec()),
+                headers: buffered.headers,
+            })
+        })
+    }
+}
+
+// under the call's line, the request marked → and the response ←
+fn indent(arrow: &str, lines: Vec<String>) -> Vec<String> {
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let prefix = if index == 0 { arrow } else { "  " };
+            format!("  {}{}", prefix, line)
+        })
+        .collect()
+}
+
+// JSON pretty-printed and forms a field a line, with anything secret taken out, XML as it came
+fn body_lines(body: &[u8]) -> Vec<String> {
+    let text = match serde_json::from_slice::<Value>(body) {
+        Ok(mut json) => {
+            redact(&mut json);
+            serde_json::to_string_pretty(&json).unwrap_or_default()
+        }
+        Err(_) => {
+            let text = String::from_utf8_lossy(body).into_owned();
+            if text.starts_with('<') || text.is_empty() {
+                text
+            } else {
+                text.split('&')
+                    .map(|pair| match pair.split_once('=') {
+                        Some((key, _)) if secret(key) => format!("{}=[redacted]", key),
+                        _ => pair.to_owned(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        }
+    };
+    let mut lines = text.lines().map(str::to_owned).collect::<Vec<_>>();
+    if lines.len() > MAX_BODY_LINES {
+        let more = lines.len() - MAX_BODY_LINES;
+        lines.truncate(MAX_BODY_LINES);
+        lines.push(format!("... {} more lines", more));
+    }
+    lines
+}
+
+// approval tokens, OAuth tokens, passwords and the like, but not the paging tokens, which are half of what the
+// inspector's for
+fn secret(key: &str) -> bool {
+    let key = key.to_lowercase();
+    if key.ends_with("nexttoken") {
+        return false;
+    }
+    ["token", "secret", "password", "credential", "authorization"]
+        .iter()
+        .any(|word| key.contains(word))
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            // a CodeBuild environment variable, whose value could be anything
+            let environment_variable = fields.contains_key("name")
+                && fields
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .is_some_and(|kind| {
+                        ["PLAINTEXT", "PARAMETER_STORE", "SECRETS_MANAGER"].contains(&kind)
+                    });
+            for (key, field) in fields.iter_mut() {
+                if secret(key) || (environment_variable && key == "value") {
+                    *field = Value::String("[redacted]".to_owned());
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+// the JSON services name the operation in X-Amz-Target, the query ones (STS, CloudFormation, CloudWatch) in the
+// form they post
+fn operation(request: &SignedRequest) -> String {
+    if let Some(target) = request
+        .headers()
+        .get("x-amz-target")
+        .and_then(|values| values.first())
+    {
+        let target = String::from_utf8_lossy(target);
+        return target.rsplit('.').next().unwrap_or_default().to_owned();
+    }
+    if let Some(SignedRequestPayload::Buffer(body)) = &request.payload {
+        let action = String::from_utf8_lossy(body)
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("Action=").map(str::to_owned));
+        if let Some(action) = action {
+            return action;
+        }
+    }
+    format!("{} {}", request.method(), request.path())
+}
@@ -0,0 +1,248 @@
+use crate::partition::parse_region;
+use rusoto_core::Region;
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+// structopt turns the doc comments below into --help text
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Visually show the state of every stage in an AWS CodePipeline")]
+pub struct Opt {
+    /// AWS profile to read credentials from
+    #[structopt(long, default_value = "cdk")]
+    pub profile: String,
+
+    /// AWS region the pipelines live in, GovCloud and China included
+    #[structopt(long, default_value = "us-west-2", parse(try_from_str = parse_region))]
+    pub region: Region,
+
+    /// Go straight to this pipeline instead of picking one from the list
+    #[structopt(long)]
+    pub pipeline: Option<String>,
+
+    /// Go straight to the pipeline this CloudFormation (or CDK) stack created, whatever it ended up being called
+    #[structopt(long, conflicts_with = "pipeline")]
+    pub stack: Option<String>,
+
+    /// Show the pipeline list even inside a repository we could guess the pipeline from
+    #[structopt(long)]
+    pub no_infer: bool,
+
+    /// Follow this execution's progress through the pipeline (given with --pipeline or --stack), even after newer
+    /// ones start
+    #[structopt(long)]
+    pub execution_id: Option<String>,
+
+    /// Only list pipelines tagged with key=value (repeat to require several tags)
+    #[structopt(long = "tag", parse(try_from_str = parse_tag))]
+    pub tags: Vec<(String, String)>,
+
+    /// Only list the pipelines in this file: one name or regex per line, # for comments, so the set can be kept
+    /// in version control next to the infrastructure code
+    #[structopt(long)]
+    pub watchlist: Option<PathBuf>,
+
+    /// Leave stages matching this off the dashboard, * matching anything (repeat for several), as well as any
+    /// [stages] hides in the config
+    #[structopt(long = "hide-stage")]
+    pub hide_stages: Vec<String>,
+
+    /// Only show the stages matching this, * matching anything (repeat for several), instead of the config's
+    #[structopt(long = "only-stage")]
+    pub only_stages: Vec<String>,
+
+    /// Instead of the dashboard, print each change as a line of plain text, for screen readers (needs --pipeline
+    /// or --stack)
+    #[structopt(long)]
+    pub linear: bool,
+
+    /// Start on the fleet view, every pipeline as a colored cell, for a dashboard on the wall
+    #[structopt(long)]
+    pub fleet: bool,
+
+    /// Made-up pipelines that start, pass and fail by themselves, served locally with no AWS account involved
+    #[structopt(long)]
+    pub demo: bool,
+
+    /// The demo's pipelines and everything they go through, so a run can be repeated from a bug report
+    #[structopt(long, requires = "demo")]
+    pub seed: Option<u64>,
+
+    /// Turn off everything that changes a pipeline (starting, stopping, retrying, approving, rolling back and
+    /// transitions), for a shared dashboard
+    #[structopt(long)]
+    pub read_only: bool,
+
+    /// What a production pipeline's typed confirmation asks for (its name, or the stage's), so a script can go
+    /// ahead without a terminal
+    #[structopt(long, global = true)]
+    pub confirm: Option<String>,
+
+    /// Start with the high-contrast theme: bright colors, bold text and heavier status symbols
+    #[structopt(long)]
+    pub high_contrast: bool,
+
+    #[structopt(subcommand)]
+    pub command: Option<Subcommand>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Subcommand {
+    /// Wait for an execution to finish, for release scripts: exits 0 if it succeeded, 1 if it didn't, 2 on timeout, 3 on errors
+    Wait {
+        pipeline: String,
+
+        /// The execution to wait for, instead of the latest one
+        #[structopt(long)]
+        execution_id: Option<String>,
+
+        /// Give up after this long, in seconds or with a unit (90s, 30m, 2h)
+        #[structopt(long, parse(try_from_str = parse_duration))]
+        timeout: Option<Duration>,
+    },
+
+    /// Start an execution and follow it on the dashboard, or with --wait the way `wait` does
+    Trigger {
+        pipeline: String,
+
+        /// Set a pipeline variable for the execution, as name=value (repeat for several)
+        #[structopt(long = "variable", parse(try_from_str = parse_tag))]
+        variables: Vec<(String, String)>,
+
+        /// Block until the execution finishes instead of opening the dashboard, with the same exit codes as `wait`
+        #[structopt(long)]
+        wait: bool,
+
+        /// With --wait, give up after this long
+        #[structopt(long, requires = "wait", parse(try_from_str = parse_duration))]
+        timeout: Option<Duration>,
+    },
+
+    /// Print every pipeline with its latest status and when it last ran, honoring --tag
+    List {
+        /// table for reading and grepping, json for feeding other scripts
+        #[structopt(long, default_value = "table", possible_values = &["table", "json"])]
+        output: Output,
+    },
+
+    /// Approve the manual approval an action is waiting on, for ChatOps bots and scripts gating a deploy
+    Approve {
+        pipeline: String,
+        stage: String,
+        action: String,
+
+        /// Shown alongside the approval in the console and the action's history
+        #[structopt(long)]
+        comment: Option<String>,
+    },
+
+    /// Reject the manual approval an action is waiting on, failing the stage
+    Reject {
+        pipeline: String,
+        stage: String,
+        action: String,
+
+        /// Shown alongside the rejection in the console and the action's history
+        #[structopt(long)]
+        comment: Option<String>,
+    },
+
+    /// Retry a failed stage within the execution it failed in
+    Retry {
+        pipeline: String,
+        stage: String,
+
+        /// Run every action in the stage again, not just the ones that failed
+        #[structopt(long)]
+        all_actions: bool,
+    },
+
+    /// Stop the pipeline's running execution, letting the actions in progress finish first
+    Stop {
+        pipeline: String,
+
+        /// The execution to stop, instead of the latest running one
+        #[structopt(long)]
+        execution_id: Option<String>,
+
+        /// Stop straight away without waiting for the actions in progress, which are abandoned
+        #[structopt(long)]
+        abandon: bool,
+
+        /// Recorded against the execution, for whoever looks at it later
+        #[structopt(long)]
+        reason: Option<String>,
+    },
+
+    /// Replace this binary with the latest release from GitHub, once the download matches the release's checksum
+    SelfUpdate {
+        /// Only say whether there's a newer release, without installing it
+        #[structopt(long)]
+        check: bool,
+    },
+
+    /// Share a setup with the rest of a team: export the config without anyone's tokens, passwords or webhooks,
+    /// or import one
+    Config(ConfigCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ConfigCommand {
+    /// Write the config out without its secrets, to FILE or otherwise standard output
+    Export { file: Option<PathBuf> },
+
+    /// Replace the config with FILE, keeping your own secrets, the old config is kept as config.toml.bak
+    Import { file: PathBuf },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Output {
+    Table,
+    Json,
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(output: &str) -> Result<Output, String> {
+        match output {
+            "table" => Ok(Output::Table),
+            "json" => Ok(Output::Json),
+            _ => Err(format!("expected table or json, got \"{}\"", output)),
+        }
+    }
+}
+
+fn parse_tag(tag: &str) -> Result<(String, String), String> {
+    match tag.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+        _ => Err(format!("expected key=value, got \"{}\"", tag)),
+    }
+}
+
+fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let (number, unit) = match duration.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => duration.split_at(index),
+        None => (duration, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => {
+            return Err(format!(
+                "expected a number of s, m or h, got \"{}\"",
+                duration
+            ))
+        }
+    };
+    match number.parse::<u64>() {
+        Ok(number) => Ok(Duration::from_secs(number * multiplier)),
+        Err(_) => Err(format!(
+            "expected a number of s, m or h, got \"{}\"",
+            duration
+        )),
+    }
+}
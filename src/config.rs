@@ -0,0 +1,624 @@
+use crate::theme::CustomTheme;
+use serde::Deserialize;
+
+use std::env::var;
+use std::error::Error;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+// settings read from ~/.config/codepipeline-status/config.toml (or $XDG_CONFIG_HOME if it's set)
+// every field has a default so an empty or missing file is fine
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // nothing that changes a pipeline, for a shared dashboard or someone who's only there to watch
+    pub read_only: bool,
+    pub github: GitHubConfig,
+    pub bitbucket: BitbucketConfig,
+    pub toasts: ToastConfig,
+    pub layout: LayoutConfig,
+    pub theme: ThemeConfig,
+    pub history: HistoryConfig,
+    pub logs: LogConfig,
+    pub alarms: AlarmsConfig,
+    pub endpoints: EndpointConfig,
+    pub audit: AuditConfig,
+    pub confirm: ConfirmConfig,
+    pub notifications: NotificationsConfig,
+    pub sla: SlaConfig,
+    pub stages: StagesConfig,
+    pub polling: PollingConfig,
+    pub credentials: CredentialsConfig,
+    pub guardrails: GuardrailsConfig,
+    pub macros: Vec<MacroConfig>,
+}
+
+// who can change which pipelines, checked against who the credentials say they are before anything's changed, so
+// signing in with the wrong profile can't touch production by accident
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct GuardrailsConfig {
+    // a pipeline none of them match is anyone's
+    pub pipelines: Vec<Guardrail>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Guardrail {
+    #[serde(default = "default_pipeline_pattern")]
+    pub pipeline: String,
+    // ARNs the way get_caller_identity has them, * matching anything, e.g.
+    // "arn:aws:sts::*:assumed-role/ProdDeployer/*". None at all keeps everyone out
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+impl GuardrailsConfig {
+    // the ARNs allowed to change the pipeline, None if anyone can
+    pub fn roles(&self, pipeline: &str) -> Option<&[String]> {
+        self.pipelines
+            .iter()
+            .find(|guardrail| wildcard_match(&guardrail.pipeline, pipeline))
+            .map(|guardrail| &guardrail.roles[..])
+    }
+
+    pub fn allows(&self, pipeline: &str, arn: &str) -> bool {
+        self.roles(pipeline)
+            .is_none_or(|roles| roles.iter().any(|role| wildcard_match(role, arn)))
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CredentialsConfig {
+    // run in the terminal to sign in again once the credentials have expired, e.g. an MFA script writing to
+    // ~/.aws/credentials. Without one it's `aws sso login` for the --profile
+    pub reauth: Option<String>,
+}
+
+// how often the open pipeline is polled, for one that has to be up to the second or one that isn't worth the API
+// calls. Without a match, every 5 seconds while something's running and every 90 otherwise
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PollingConfig {
+    pub pipelines: Vec<PipelinePolling>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PipelinePolling {
+    #[serde(default = "default_pipeline_pattern")]
+    pub pipeline: String,
+    // while something's running, and the rest of the time as well unless idle_seconds says otherwise
+    pub seconds: u64,
+    pub idle_seconds: Option<u64>,
+}
+
+// stages left off the dashboard, for a pipeline with a dozen of them on a narrow terminal
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct StagesConfig {
+    // * matching anything, e.g. ["Source"] for a stage that's always green
+    pub hide: Vec<String>,
+    // with any of these, only the stages matching one are shown
+    pub only: Vec<String>,
+}
+
+impl StagesConfig {
+    pub fn shows(&self, stage: &str) -> bool {
+        let matches = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| wildcard_match(pattern, stage))
+        };
+        (self.only.is_empty() || matches(&self.only)) && !matches(&self.hide)
+    }
+}
+
+// one key pressing several others in turn, or opening a pipeline or its failed action on the way
+#[derive(Clone, Debug, Deserialize)]
+pub struct MacroConfig {
+    // a character, "F5", or a named key like "enter" or "tab"
+    pub key: String,
+    pub name: Option<String>,
+    // keys as above, "pipeline NAME" for opening a pipeline and "failed" for picking out the failed action, e.g.
+    // ["pipeline payments-prod", "failed", "enter", "l"] for the failed build's log
+    pub steps: Vec<String>,
+}
+
+// how long stages are allowed to run before they count as hung, CodePipeline itself only gives up on an action
+// after an hour or more
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SlaConfig {
+    // the stage has to match as well as the pipeline
+    pub stages: Vec<StageSla>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct StageSla {
+    #[serde(default = "default_pipeline_pattern")]
+    pub pipeline: String,
+    // * matches here too
+    pub stage: String,
+    pub minutes: u64,
+}
+
+impl SlaConfig {
+    // in seconds, None if the stage can take as long as it likes
+    pub fn limit(&self, pipeline: &str, stage: &str) -> Option<u64> {
+        self.stages
+            .iter()
+            .find(|sla| {
+                wildcard_match(&sla.pipeline, pipeline) && wildcard_match(&sla.stage, stage)
+            })
+            .map(|sla| sla.minutes * 60)
+    }
+}
+
+// how sure we want to be before doing something that changes a pipeline, by default a y/n is enough
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ConfirmConfig {
+    // pipeline names that count as production, * matching anything, e.g. "*-prod"
+    pub production: Vec<String>,
+    // the actions that have to be confirmed by typing the stage's (or pipeline's) name on those, named the same
+    // as in the audit log
+    pub typed: Vec<String>,
+}
+
+impl Default for ConfirmConfig {
+    fn default() -> Self {
+        ConfirmConfig {
+            production: vec![],
+            typed: vec![
+                "stop".to_owned(),
+                "abandon".to_owned(),
+                "rollback".to_owned(),
+                "disable-transition".to_owned(),
+            ],
+        }
+    }
+}
+
+impl ConfirmConfig {
+    pub fn is_production(&self, pipeline: &str) -> bool {
+        self.production
+            .iter()
+            .any(|pattern| wildcard_match(pattern, pipeline))
+    }
+
+    pub fn needs_typing(&self, pipeline: &str, action: &str) -> bool {
+        self.typed.iter().any(|typed| typed == action) && self.is_production(pipeline)
+    }
+}
+
+// where executions finishing (or starting, or recovering) get sent besides the corner of the screen, nowhere
+// unless there's a rule saying so
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    // a pipeline's own rule goes before a "*"
+    pub rules: Vec<NotificationRule>,
+    // an incoming webhook, for rules sending to "slack"
+    pub slack_webhook: Option<String>,
+    // posted a JSON object for every notification, for rules sending to "webhook"
+    pub webhook: Option<String>,
+    // played for rules sending to "sound", a .wav or whatever afplay (on a Mac) or paplay/aplay can play. Without
+    // one it's the terminal's bell
+    pub sound: Option<PathBuf>,
+    // nothing's sent between these times of day
+    pub quiet_hours: Option<QuietHours>,
+    // once a pipeline's failure has gone out, any more from it in the next this many minutes are only counted,
+    // and go out as one "still failing" when they're up. 0 sends every one
+    pub failure_minutes: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct QuietHours {
+    // local time, "22:00" to "07:00" running past midnight
+    pub from: String,
+    pub to: String,
+    // hold on to everything and send it all at once when they're over, rather than dropping it
+    #[serde(default)]
+    pub batch: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NotificationRule {
+    #[serde(default = "default_pipeline_pattern")]
+    pub pipeline: String,
+    // the statuses it's for, CodePipeline's own (InProgress, Succeeded, Failed, Stopped and Superseded) and
+    // Recovered, for succeeding after the execution before failed, and Overdue, for a stage running past its
+    // [sla]. None at all keeps the pipeline quiet
+    #[serde(default)]
+    pub on: Vec<String>,
+    #[serde(default)]
+    pub channels: Vec<Channel>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Desktop,
+    Slack,
+    Webhook,
+    // for someone with the dashboard on another monitor, who'd otherwise miss a failure
+    Sound,
+}
+
+// a rule's pipeline when it's left out, which matches every one
+fn default_pipeline_pattern() -> String {
+    "*".to_owned()
+}
+
+// where what we keep between runs goes: ~/.local/share/codepipeline-status, or under $XDG_DATA_HOME if it's set
+pub fn data_dir() -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let data_dir = match var("XDG_DATA_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => PathBuf::from(var("HOME")?).join(".local").join("share"),
+    };
+    Ok(data_dir.join("codepipeline-status"))
+}
+
+// * matches any run of characters, everything else only itself. The notification, [sla], [polling] and
+// [guardrails] rules go by this, each list tried in order with the first whose pipeline matches deciding
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let mut middle = parts.collect::<Vec<_>>();
+    // without a * the whole name has to match
+    let last = match middle.pop() {
+        Some(last) => last,
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+// a record of everything done to a pipeline through us, for going over an incident afterwards
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    // a CloudWatch Logs group to send each entry to as well as the local file, it has to exist already
+    pub log_group: Option<String>,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        AuditConfig {
+            enabled: true,
+            log_group: None,
+        }
+    }
+}
+
+// which of each service's endpoints every AWS client talks to, regulated environments tend to need one or both
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct EndpointConfig {
+    // FIPS 140 validated endpoints
+    pub fips: bool,
+    // endpoints reachable over IPv6 as well as IPv4
+    pub dual_stack: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct GitHubConfig {
+    // a personal access token with read access to the source repositories
+    // without one we fall back to whatever revision summary CodePipeline gives us
+    pub token: Option<String>,
+    // point this at your GitHub Enterprise instance's API if you have one
+    pub api_url: String,
+}
+
+impl Default for GitHubConfig {
+    fn default() -> Self {
+        GitHubConfig {
+            token: None,
+            api_url: "https://api.github.com".to_owned(),
+        }
+    }
+}
+
+// credentials for Bitbucket sources behind a CodeStar connection, only needed for private repositories
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct BitbucketConfig {
+    pub username: Option<String>,
+    pub app_password: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ToastConfig {
+    // how long a notification stays in the corner before it goes, they can still be found with 'n' after that
+    pub seconds: u64,
+}
+
+impl Default for ToastConfig {
+    fn default() -> Self {
+        ToastConfig { seconds: 5 }
+    }
+}
+
+// what the stages view shows, top to bottom, leave a pane out to hide it
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    pub panes: Vec<PaneLayout>,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            panes: vec![
+                PaneLayout {
+                    pane: Pane::Stages,
+                    size: 1,
+                },
+                PaneLayout {
+                    pane: Pane::Commits,
+                    size: 1,
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PaneLayout {
+    pub pane: Pane,
+    // relative to the other panes, so sizes of 2 and 1 split the screen two thirds to one third
+    #[serde(default = "default_pane_size")]
+    pub size: u32,
+}
+
+fn default_pane_size() -> u32 {
+    1
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Pane {
+    Stages,
+    Commits,
+    History,
+    // the notifications so far this session
+    Notifications,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    // which theme to start with, 'c' goes through them all
+    pub name: String,
+    // more to choose from on top of the built-in "dark" and "light"
+    pub custom: Vec<CustomTheme>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            name: "dark".to_owned(),
+            custom: vec![],
+        }
+    }
+}
+
+// stage runs we've seen are kept in ~/.local/share/codepipeline-status/history.db, so the statistics can look
+// further back than CodePipeline's recent action executions
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    // how far back the statistics go, anything older is forgotten
+    pub days: u32,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            enabled: true,
+            days: 90,
+        }
+    }
+}
+
+// how much of a build's log we hang on to, the oldest lines go once it's over either limit
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    pub max_lines: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            max_lines: 50_000,
+            max_bytes: 16 * 1024 * 1024,
+        }
+    }
+}
+
+// CloudWatch alarms to show next to a pipeline's deploy stage, so a deploy that went through but left the service
+// alarming doesn't look all green
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct AlarmsConfig {
+    // a pipeline tag listing its service's alarms, leave it empty to only go by the entries below
+    pub tag: String,
+    pub pipelines: Vec<PipelineAlarms>,
+}
+
+impl Default for AlarmsConfig {
+    fn default() -> Self {
+        AlarmsConfig {
+            tag: "codepipeline-status:alarms".to_owned(),
+            pipelines: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PipelineAlarms {
+    pub pipeline: String,
+    #[serde(default)]
+    pub names: Vec<String>,
+    // every alarm whose name starts with this
+    pub prefix: Option<String>,
+    // which stage they go next to, by default the last one with a deploy action in it
+    pub stage: Option<String>,
+}
+
+// whatever in the config belongs to whoever's running it rather than to the setup, by section and key, which
+// `config export` leaves out and `config import` keeps from the config it replaces
+pub const SECRETS: &[(&str, &str)] = &[
+    ("github", "token"),
+    ("bitbucket", "username"),
+    ("bitbucket", "app_password"),
+    // incoming webhook URLs are as good as a password
+    ("notifications", "slack_webhook"),
+    ("notifications", "webhook"),
+];
+
+// takes the secrets out of a config, returning which ones there were, e.g. github.token
+pub fn without_secrets(config: &mut toml::Value) -> Vec<String> {
+    let mut removed = vec![];
+    let config = match config.as_table_mut() {
+        Some(config) => config,
+        None => return removed,
+    };
+    for (section, key) in SECRETS {
+        let table = match config.get_mut(*section).and_then(toml::Value::as_table_mut) {
+            Some(table) => table,
+            None => continue,
+        };
+        if table.remove(*key).is_some() {
+            removed.push(format!("{}.{}", section, key));
+        }
+        // a [github] with nothing left in it is only noise
+        if table.is_empty() {
+            config.remove(*section);
+        }
+    }
+    removed
+}
+
+// fills in the secrets a config's left out from another, returning which ones were kept
+pub fn with_secrets(config: &mut toml::Value, from: &toml::Value) -> Vec<String> {
+    let mut kept = vec![];
+    for (section, key) in SECRETS {
+        let secret = match from.get(section).and_then(|table| table.get(key)) {
+            Some(secret) => secret.clone(),
+            None => continue,
+        };
+        let table = match config.as_table_mut() {
+            Some(table) => table
+                .entry(section.to_string())
+                .or_insert_with(|| toml::Value::Table(Default::default())),
+            None => continue,
+        };
+        if let Some(table) = table.as_table_mut() {
+            if !table.contains_key(*key) {
+                table.insert(key.to_string(), secret);
+                kept.push(format!("{}.{}", section, key));
+            }
+        }
+    }
+    kept
+}
+
+// the file as it's written, checked to be a config we can use
+fn read_config(path: &Path) -> Result<toml::Value, Box<dyn Error + Send + Sync>> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    toml::from_str::<Config>(&contents)
+        .map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))?;
+    Ok(toml::from_str(&contents)?)
+}
+
+impl Config {
+    // the config for sharing with the rest of a team, nothing that's only someone's own, to the file or stdout
+    pub fn export(to: Option<&Path>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let path = Config::path()?;
+        let mut config = if path.exists() {
+            read_config(&path)?
+        } else {
+            toml::Value::Table(Default::default())
+        };
+        let removed = without_secrets(&mut config);
+        let contents = format!(
+            "# codepipeline-status config, `codepipeline-status config import` to use it\n{}",
+            toml::to_string_pretty(&config)?
+        );
+        match to {
+            Some(to) => {
+                fs::write(to, contents)
+                    .map_err(|e| format!("Couldn't write {}: {}", to.display(), e))?;
+                println!("Exported {} to {}", path.display(), to.display());
+            }
+            None => print!("{}", contents),
+        }
+        // stderr, so stdout is only ever the config
+        if !removed.is_empty() {
+            eprintln!("Left out {}", removed.join(", "));
+        }
+        Ok(())
+    }
+
+    // swaps the config for a shared one, keeping whatever secrets the old one had, and the old one itself as
+    // config.toml.bak
+    pub fn import(from: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut config = read_config(from)?;
+        let path = Config::path()?;
+        let mut kept = vec![];
+        if path.exists() {
+            let current = read_config(&path)?;
+            kept = with_secrets(&mut config, &current);
+            let backup = path.with_extension("toml.bak");
+            fs::copy(&path, &backup)
+                .map_err(|e| format!("Couldn't back up {}: {}", path.display(), e))?;
+            println!("The old config is in {}", backup.display());
+        } else if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, toml::to_string_pretty(&config)?)
+            .map_err(|e| format!("Couldn't write {}: {}", path.display(), e))?;
+        println!("Imported {} into {}", from.display(), path.display());
+        if !kept.is_empty() {
+            println!("Kept your {}", kept.join(", "));
+        }
+        Ok(())
+    }
+
+    pub fn path() -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+        let config_dir = match var("XDG_CONFIG_HOME") {
+            Ok(dir) => PathBuf::from(dir),
+            Err(_) => PathBuf::from(var("HOME")?).join(".config"),
+        };
+        Ok(config_dir.join("codepipeline-status").join("config.toml"))
+    }
+
+    pub fn load() -> Result<Config, Box<dyn Error + Send + Sync>> {
+        let path = Config::path()?;
+        match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents)
+                .map_err(|e| format!("Couldn't parse {}: {}", path.display(), e).into()),
+            // not having a config file at all is perfectly normal
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("Couldn't read {}: {}", path.display(), e).into()),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::partition;
+
+use std::io;
+use std::process::{Command, Stdio};
+
+// the console lives somewhere else in the China and GovCloud partitions
+fn console_host(region: &str) -> &'static str {
+    partition::for_region(region).console_host
+}
+
+pub fn pipeline_url(region: &str, pipeline: &str) -> String {
+    format!(
+        "https://{}/codesuite/codepipeline/pipelines/{}/view?region={}",
+        console_host(region),
+        pipeline,
+        region
+    )
+}
+
+pub fn execution_url(region: &str, pipeline: &str, execution_id: &str) -> String {
+    format!(
+        "https://{}/codesuite/codepipeline/pipelines/{}/executions/{}/timeline?region={}",
+        console_host(region),
+        pipeline,
+        execution_id,
+        region
+    )
+}
+
+// build IDs are "project:uuid"
+pub fn build_url(region: &str, build_id: &str) -> Option<String> {
+    let project = build_id.split(':').next()?;
+    Some(format!(
+        "https://{}/codesuite/codebuild/projects/{}/build/{}/log?region={}",
+        console_host(region),
+        project,
+        build_id.replace(':', "%3A"),
+        region
+    ))
+}
+
+// hand the URL to whatever opens links on this platform, without letting it scribble over the TUI
+pub fn open(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
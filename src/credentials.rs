@@ -0,0 +1,184 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use rusoto_core::credential::{
+    AwsCredentials, ContainerProvider, CredentialsError, EnvironmentProvider, ProfileProvider,
+    ProvideAwsCredentials,
+};
+use tokio::sync::Mutex;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+// the instance metadata service is link-local, if it hasn't answered by now it isn't there
+const METADATA_TIMEOUT: Duration = Duration::from_secs(2);
+const METADATA_URL: &str = "http://169.254.169.254/latest";
+// how long a session token is good for, the longest IMDSv2 allows
+const METADATA_TOKEN_TTL: &str = "21600";
+
+// wherever credentials can be found: the profile we were given, the usual environment variables, the task role
+// of the ECS (or Fargate) task we're running in, and the instance profile of the EC2 instance we're on
+pub struct CredentialChain {
+    profile: ProfileProvider,
+    environment: EnvironmentProvider,
+    container: ContainerProvider,
+    instance: InstanceMetadata,
+}
+
+impl CredentialChain {
+    pub fn new(profile: ProfileProvider) -> CredentialChain {
+        CredentialChain {
+            profile,
+            environment: EnvironmentProvider::default(),
+            container: ContainerProvider::new(),
+            instance: InstanceMetadata::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl ProvideAwsCredentials for CredentialChain {
+    async fn credentials(&self) -> Result<AwsCredentials, CredentialsError> {
+        let mut failures = vec![];
+        match self.profile.credentials().await {
+            Ok(credentials) => return Ok(credentials),
+            Err(e) => failures.push(format!("profile: {}", e.message)),
+        }
+        match self.environment.credentials().await {
+            Ok(credentials) => return Ok(credentials),
+            Err(e) => failures.push(format!("environment: {}", e.message)),
+        }
+        match self.container.credentials().await {
+            Ok(credentials) => return Ok(credentials),
+            Err(e) => failures.push(format!("container: {}", e.message)),
+        }
+        match self.instance.credentials().await {
+            Ok(credentials) => return Ok(credentials),
+            Err(e) => failures.push(format!("instance metadata: {}", e.message)),
+        }
+        Err(CredentialsError::new(format!(
+            "Couldn't find any credentials ({})",
+            failures.join("; ")
+        )))
+    }
+}
+
+// how long before they run out we'd rather have new credentials, the same as rusoto's own
+const EXPIRY_MARGIN: i64 = 20;
+// how AWS (and the SSO credential_process) say a session token has run out, as opposed to being wrong
+const EXPIRED: &[&str] = &[
+    "ExpiredToken",
+    "security token included in the request is expired",
+    "Token has expired",
+];
+
+// whether an error is down to the credentials having run out, which every call after it will be too until
+// someone signs in again
+pub fn expired(error: &str) -> bool {
+    EXPIRED.iter().any(|expired| error.contains(expired))
+}
+
+// the chain's credentials, kept until they're about to run out like rusoto's AutoRefreshingProvider does, but
+// with a way to throw them away once they've stopped working, whatever they say about when they expire
+#[derive(Clone)]
+pub struct CachedCredentials {
+    chain: Arc<dyn ProvideAwsCredentials + Send + Sync>,
+    // only ones that were found, a failure (the metadata service timing out, starting before signing in) goes
+    // round the chain again next call rather than failing every call after it
+    current: Arc<Mutex<Option<AwsCredentials>>>,
+}
+
+impl CachedCredentials {
+    pub fn new(chain: impl ProvideAwsCredentials + Send + Sync + 'static) -> CachedCredentials {
+        CachedCredentials {
+            chain: Arc::new(chain),
+            current: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // after signing in again, so the next call reads the new ones
+    pub async fn forget(&self) {
+        *self.current.lock().await = None;
+    }
+}
+
+#[async_trait]
+impl ProvideAwsCredentials for CachedCredentials {
+    async fn credentials(&self) -> Result<AwsCredentials, CredentialsError> {
+        let mut current = self.current.lock().await;
+        if let Some(credentials) = current.as_ref() {
+            if !about_to_expire(credentials) {
+                return Ok(credentials.clone());
+            }
+        }
+        let fresh = self.chain.credentials().await?;
+        *current = Some(fresh.clone());
+        Ok(fresh)
+    }
+}
+
+// with a little to spare, so they don't run out between being handed over and the request being signed
+fn about_to_expire(credentials: &AwsCredentials) -> bool {
+    credentials
+        .expires_at()
+        .is_some_and(|at| at < Utc::now() + chrono::Duration::seconds(EXPIRY_MARGIN))
+}
+
+// rusoto's instance metadata provider only speaks IMDSv1, which instances set to require IMDSv2 refuse, so this
+// asks for a session token first, falling back to going without on the odd instance that doesn't hand them out
+#[derive(Default)]
+struct InstanceMetadata {
+    client: reqwest::Client,
+}
+
+impl InstanceMetadata {
+    async fn token(&self) -> Option<String> {
+        let response = self
+            .client
+            .put(&format!("{}/api/token", METADATA_URL))
+            .header("X-aws-ec2-metadata-token-ttl-seconds", METADATA_TOKEN_TTL)
+            .timeout(METADATA_TIMEOUT)
+            .send()
+            .await
+            .ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        response.text().await.ok()
+    }
+
+    async fn get(&self, path: &str, token: &Option<String>) -> Result<String, CredentialsError> {
+        let mut request = self
+            .client
+            .get(&format!(
+                "{}/meta-data/iam/security-credentials/{}",
+                METADATA_URL, path
+            ))
+            .timeout(METADATA_TIMEOUT);
+        if let Some(token) = token {
+            request = request.header("X-aws-ec2-metadata-token", token);
+        }
+        let response = request.send().await.map_err(CredentialsError::new)?;
+        if !response.status().is_success() {
+            return Err(CredentialsError::new(format!(
+                "the instance metadata service answered {}",
+                response.status()
+            )));
+        }
+        response.text().await.map_err(CredentialsError::new)
+    }
+}
+
+#[async_trait]
+impl ProvideAwsCredentials for InstanceMetadata {
+    async fn credentials(&self) -> Result<AwsCredentials, CredentialsError> {
+        let token = self.token().await;
+        // the instance profile's role, there's only ever the one
+        let role = self.get("", &token).await?;
+        let role = role.lines().next().unwrap_or_default().trim().to_owned();
+        if role.is_empty() {
+            return Err(CredentialsError::new("the instance has no role"));
+        }
+        let body = self.get(&role, &token).await?;
+        serde_json::from_str::<AwsCredentials>(&body).map_err(CredentialsError::new)
+    }
+}
@@ -0,0 +1,707 @@
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use rusoto_core::Region;
+use serde_json::{json, Value};
+
+use std::convert::Infallible;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// how often the made-up pipelines move on, about as often as the dashboard looks at them
+const STEP: Duration = Duration::from_secs(5);
+const ACCOUNT: &str = "123456789012";
+
+// each service with the team that owns it
+const SERVICES: &[(&str, &str)] = &[
+    ("payments", "payments"),
+    ("checkout", "payments"),
+    ("search", "discovery"),
+    ("catalog", "discovery"),
+    ("inventory", "fulfilment"),
+    ("shipping", "fulfilment"),
+    ("accounts", "identity"),
+    ("auth", "identity"),
+    ("notifications", "platform"),
+    ("reporting", "data"),
+];
+
+const COMMITS: &[&str] = &[
+    "Add retries to the payment client",
+    "Bump the SDK to the latest version",
+    "Fix the flaky integration test",
+    "Cache the catalog lookups",
+    "Log the request ID on errors",
+    "Raise the Lambda memory limit",
+    "Remove the old feature flag",
+    "Tighten the IAM policy",
+    "Handle empty search results",
+    "Move the nightly job to EventBridge",
+];
+
+// a local stand-in for CodePipeline serving pipelines made up from the seed, which keep starting, passing and
+// failing as time goes on. The same seed always makes the same pipelines go through the same steps, so a bug
+// seen in the demo can be reported by its seed
+pub async fn serve(seed: u64, region: &str) -> Result<Region, Box<dyn Error + Send + Sync>> {
+    let world = Arc::new(Mutex::new(World::new(seed)));
+    let make_service = make_service_fn(move |_| {
+        let world = world.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |request| handle(world.clone(), request))) }
+    });
+    let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))?.serve(make_service);
+    let addr = server.local_addr();
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            error!("The demo server stopped: {}", e);
+        }
+    });
+    Ok(Region::Custom {
+        name: region.to_owned(),
+        endpoint: format!("http://{}", addr),
+    })
+}
+
+// a seed for when --seed isn't given, which gets logged so the run can be repeated
+pub fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64)
+        % 1_000_000
+}
+
+// splitmix64, which is plenty random enough for made-up pipelines and the same everywhere
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n.max(1)
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len() as u64) as usize]
+    }
+
+    // shaped like the UUIDs CodePipeline hands out
+    fn id(&mut self) -> String {
+        let (a, b) = (self.next(), self.next());
+        format!(
+            "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+            a >> 32,
+            (a >> 16) & 0xffff,
+            a & 0xfff,
+            b >> 48,
+            b & 0xffff_ffff_ffff
+        )
+    }
+
+    fn revision(&mut self) -> String {
+        format!(
+            "{:016x}{:016x}{:08x}",
+            self.next(),
+            self.next(),
+            self.next() >> 32
+        )
+    }
+}
+
+struct Action {
+    name: &'static str,
+    category: &'static str,
+    provider: &'static str,
+}
+
+struct Stage {
+    name: &'static str,
+    actions: Vec<Action>,
+}
+
+#[derive(Clone)]
+struct StageRun {
+    status: &'static str,
+    started: f64,
+    changed: f64,
+}
+
+struct Execution {
+    id: String,
+    status: &'static str,
+    trigger: &'static str,
+    revision: String,
+    summary: &'static str,
+    started: f64,
+    changed: f64,
+    // how far it got through each of the pipeline's stages, None for the ones it never reached
+    stages: Vec<Option<StageRun>>,
+}
+
+struct Pipeline {
+    name: String,
+    team: &'static str,
+    stages: Vec<Stage>,
+    // newest first
+    executions: Vec<Execution>,
+}
+
+struct World {
+    rng: Rng,
+    pipelines: Vec<Pipeline>,
+    // where the made-up clock started, in Unix time, and how many steps it's taken since
+    base: f64,
+    steps: u64,
+    started: Instant,
+}
+
+impl World {
+    fn new(seed: u64) -> World {
+        let mut rng = Rng(seed);
+        let base = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |since| since.as_secs() as f64);
+        let mut pipelines = vec![];
+        let count = 6 + rng.below(7);
+        // a production and a staging pipeline for each service
+        for index in 0..count as usize {
+            let (service, team) = SERVICES[index / 2 % SERVICES.len()];
+            let environment = if index % 2 == 0 { "prod" } else { "staging" };
+            let mut pipeline = Pipeline {
+                name: format!("{}-{}", service, environment),
+                team,
+                stages: stages(&mut rng, environment == "prod"),
+                executions: vec![],
+            };
+            pipeline.history(&mut rng, base);
+            pipelines.push(pipeline);
+        }
+        World {
+            rng,
+            pipelines,
+            base,
+            steps: 0,
+            started: Instant::now(),
+        }
+    }
+
+    fn now(&self) -> f64 {
+        self.base + (self.steps * STEP.as_secs()) as f64
+    }
+
+    // every step in order, however long it's been since anyone asked, so what happens never depends on when
+    fn catch_up(&mut self) {
+        let due = self.started.elapsed().as_secs() / STEP.as_secs();
+        while self.steps < due {
+            self.steps += 1;
+            let now = self.now();
+            let rng = &mut self.rng;
+            for pipeline in &mut self.pipelines {
+                let stage_count = pipeline.stages.len();
+                match pipeline.executions.first_mut() {
+                    Some(execution) if execution.status == "InProgress" => {
+                        execution.advance(rng, stage_count, now)
+                    }
+                    _ => {
+                        if rng.chance(0.05) {
+                            pipeline.start(rng, "Webhook", now);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn pipeline(&self, name: &str) -> Option<&Pipeline> {
+        self.pipelines.iter().find(|pipeline| pipeline.name == name)
+    }
+}
+
+// a source, a build and a deploy or two, with an approval in front of production
+fn stages(rng: &mut Rng, production: bool) -> Vec<Stage> {
+    let mut stages = vec![
+        Stage {
+            name: "Source",
+            actions: vec![Action {
+                name: "Source",
+                category: "Source",
+                provider: "S3",
+            }],
+        },
+        Stage {
+            name: "Build",
+            actions: vec![
+                Action {
+                    name: "Compile",
+                    category: "Build",
+                    provider: "CodeBuild",
+                },
+                Action {
+                    name: "UnitTests",
+                    category: "Test",
+                    provider: "CodeBuild",
+                },
+            ],
+        },
+    ];
+    if !production || rng.chance(0.5) {
+        stages.push(Stage {
+            name: "Beta",
+            actions: vec![Action {
+                name: "DeployBeta",
+                category: "Deploy",
+                provider: "CloudFormation",
+            }],
+        });
+    }
+    if production {
+        stages.push(Stage {
+            name: "Approval",
+            actions: vec![Action {
+                name: "ApproveRelease",
+                category: "Approval",
+                provider: "Manual",
+            }],
+        });
+        stages.push(Stage {
+            name: "Prod",
+            actions: vec![Action {
+                name: "DeployProd",
+                category: "Deploy",
+                provider: "CodeDeploy",
+            }],
+        });
+    }
+    stages
+}
+
+impl Pipeline {
+    // a few finished executions over the last day or so, and maybe one still going
+    fn history(&mut self, rng: &mut Rng, now: f64) {
+        let count = 3 + rng.below(6);
+        let mut at = now - 3600.0 * (count + 1) as f64;
+        for _ in 0..count {
+            at += 1800.0 + rng.below(1800) as f64;
+            let mut started = at;
+            let roll = rng.below(100);
+            // where it stopped, if it didn't make it all the way through
+            let (stopped_at, status, stage_status) = match roll {
+                0..=69 => (self.stages.len(), "Succeeded", "Succeeded"),
+                70..=92 => (
+                    1 + rng.below(self.stages.len() as u64) as usize,
+                    "Failed",
+                    "Failed",
+                ),
+                _ => (1, "Superseded", "Cancelled"),
+            };
+            let mut runs = vec![None; self.stages.len()];
+            for (index, run) in runs
+                .iter_mut()
+                .enumerate()
+                .take(stopped_at.min(self.stages.len()))
+            {
+                let took = 30.0 + rng.below(400) as f64;
+                let finished = if index + 1 == stopped_at && status != "Succeeded" {
+                    stage_status
+                } else {
+                    "Succeeded"
+                };
+                *run = Some(StageRun {
+                    status: finished,
+                    started,
+                    changed: started + took,
+                });
+                started += took;
+            }
+            self.executions.insert(
+                0,
+                Execution {
+                    id: rng.id(),
+                    status,
+                    trigger: rng.pick(&["Webhook", "Webhook", "StartPipelineExecution"]),
+                    revision: rng.revision(),
+                    summary: rng.pick(COMMITS),
+                    started: at,
+                    changed: started,
+                    stages: runs,
+                },
+            );
+        }
+        if rng.chance(0.3) {
+            let started = now - rng.below(120) as f64;
+            self.start(rng, "Webhook", started);
+        }
+    }
+
+    fn start(&mut self, rng: &mut Rng, trigger: &'static str, now: f64) -> String {
+        // a newer execution takes over from one that's still going
+        if let Some(running) = self
+            .executions
+            .first_mut()
+            .filter(|execution| execution.status == "InProgress")
+        {
+            running.status = "Superseded";
+            running.changed = now;
+            for run in running.stages.iter_mut().flatten() {
+                if run.status == "InProgress" {
+                    run.status = "Cancelled";
+                    run.changed = now;
+                }
+            }
+        }
+        let mut stages = vec![None; self.stages.len()];
+        stages[0] = Some(StageRun {
+            status: "InProgress",
+            started: now,
+            changed: now,
+        });
+        let id = rng.id();
+        self.executions.insert(
+            0,
+            Execution {
+                id: id.clone(),
+                status: "InProgress",
+                trigger,
+                revision: rng.revision(),
+                summary: rng.pick(COMMITS),
+                started: now,
+                changed: now,
+                stages,
+            },
+        );
+        self.executions.truncate(20);
+        id
+    }
+
+    fn summaries(&self, limit: usize) -> Value {
+        let summaries = self
+            .executions
+            .iter()
+            .take(limit)
+            .map(|execution| {
+                json!({
+                    "pipelineExecutionId": execution.id,
+                    "status": execution.status,
+                    "startTime": execution.started,
+                    "lastUpdateTime": execution.changed,
+                    "trigger": { "triggerType": execution.trigger },
+                    "sourceRevisions": [{
+                        "actionName": "Source",
+                        "revisionId": execution.revision,
+                        "revisionSummary": execution.summary,
+                    }],
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({ "pipelineExecutionSummaries": summaries })
+    }
+
+    fn state(&self) -> Value {
+        let stages = self
+            .stages
+            .iter()
+            .enumerate()
+            .map(|(index, stage)| {
+                // the last execution to get this far
+                let latest = self
+                    .executions
+                    .iter()
+                    .find_map(|execution| Some((execution, execution.stages[index].as_ref()?)));
+                let mut state = json!({
+                    "stageName": stage.name,
+                    "inboundTransitionState": { "enabled": true },
+                });
+                if let Some((execution, run)) = latest {
+                    state["latestExecution"] = json!({
+                        "pipelineExecutionId": execution.id,
+                        "status": run.status,
+                    });
+                    state["actionStates"] = stage
+                        .actions
+                        .iter()
+                        .enumerate()
+                        .map(|(position, action)| {
+                            let mut action_state = json!({
+                                "actionName": action.name,
+                                "latestExecution": {
+                                    "actionExecutionId": action_id(execution, stage, action),
+                                    "status": action_status(run, position, stage.actions.len()),
+                                    "lastStatusChange": run.changed,
+                                },
+                            });
+                            if action.category == "Source" {
+                                action_state["latestExecution"]["summary"] =
+                                    json!(execution.summary);
+                            }
+                            action_state
+                        })
+                        .collect();
+                } else {
+                    state["actionStates"] = stage
+                        .actions
+                        .iter()
+                        .map(|action| json!({ "actionName": action.name }))
+                        .collect();
+                }
+                state
+            })
+            .collect::<Vec<_>>();
+        json!({ "pipelineName": self.name, "pipelineVersion": 1, "stageStates": stages })
+    }
+
+    fn definition(&self) -> Value {
+        let stages = self
+            .stages
+            .iter()
+            .map(|stage| {
+                let actions = stage
+                    .actions
+                    .iter()
+                    .map(|action| {
+                        json!({
+                            "name": action.name,
+                            "actionTypeId": {
+                                "category": action.category,
+                                "owner": "AWS",
+                                "provider": action.provider,
+                                "version": "1",
+                            },
+                            "runOrder": 1,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                json!({ "name": stage.name, "actions": actions })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "pipeline": {
+                "name": self.name,
+                "roleArn": format!("arn:aws:iam::{}:role/{}-pipeline", ACCOUNT, self.name),
+                "version": 1,
+                "stages": stages,
+            }
+        })
+    }
+
+    // every action of every execution, or only of the one asked after, newest first
+    fn action_executions(&self, execution_id: Option<&str>) -> Value {
+        let mut details = vec![];
+        for execution in &self.executions {
+            if execution_id.is_some_and(|id| id != execution.id) {
+                continue;
+            }
+            for (stage, run) in self.stages.iter().zip(&execution.stages) {
+                let run = match run {
+                    Some(run) => run,
+                    None => continue,
+                };
+                for (position, action) in stage.actions.iter().enumerate() {
+                    details.push(json!({
+                        "pipelineExecutionId": execution.id,
+                        "actionExecutionId": action_id(execution, stage, action),
+                        "pipelineVersion": 1,
+                        "stageName": stage.name,
+                        "actionName": action.name,
+                        "status": action_status(run, position, stage.actions.len()),
+                        "startTime": run.started,
+                        "lastUpdateTime": run.changed,
+                    }));
+                }
+            }
+        }
+        details.truncate(100);
+        json!({ "actionExecutionDetails": details })
+    }
+}
+
+impl Execution {
+    // the running stage might finish, and the next one start
+    fn advance(&mut self, rng: &mut Rng, stage_count: usize, now: f64) {
+        let index = match self
+            .stages
+            .iter()
+            .position(|run| matches!(run, Some(run) if run.status == "InProgress"))
+        {
+            Some(index) => index,
+            None => return,
+        };
+        if !rng.chance(0.4) {
+            return;
+        }
+        let failed = rng.chance(if index == 0 { 0.02 } else { 0.1 });
+        if let Some(run) = &mut self.stages[index] {
+            run.status = if failed { "Failed" } else { "Succeeded" };
+            run.changed = now;
+        }
+        self.changed = now;
+        if failed {
+            self.status = "Failed";
+        } else if index + 1 < stage_count {
+            self.stages[index + 1] = Some(StageRun {
+                status: "InProgress",
+                started: now,
+                changed: now,
+            });
+        } else {
+            self.status = "Succeeded";
+        }
+    }
+}
+
+fn action_id(execution: &Execution, stage: &Stage, action: &Action) -> String {
+    format!("{}-{}-{}", &execution.id[..8], stage.name, action.name)
+}
+
+// a stage's actions all go the way the stage did, except that it's the last one that fails
+fn action_status(run: &StageRun, position: usize, count: usize) -> &'static str {
+    match run.status {
+        "Failed" if position + 1 < count => "Succeeded",
+        "Cancelled" => "Abandoned",
+        status => status,
+    }
+}
+
+async fn handle(
+    world: Arc<Mutex<World>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, hyper::Error> {
+    let operation = request
+        .headers()
+        .get("x-amz-target")
+        .and_then(|target| target.to_str().ok())
+        .and_then(|target| target.rsplit('.').next())
+        .map(str::to_owned);
+    let body = hyper::body::to_bytes(request.into_body()).await?;
+
+    let mut world = world.lock().unwrap();
+    world.catch_up();
+    let (status, content_type, body) = match operation {
+        Some(operation) => {
+            let input = serde_json::from_slice(&body).unwrap_or(Value::Null);
+            let (status, output) = match answer(&mut world, &operation, &input) {
+                Ok(output) => (200, output),
+                Err((error_type, message)) => {
+                    (400, json!({ "__type": error_type, "message": message }))
+                }
+            };
+            (status, "application/x-amz-json-1.1", output.to_string())
+        }
+        // STS still speaks the query protocol, and who we are is all it's asked
+        None if body.starts_with(b"Action=GetCallerIdentity") => (
+            200,
+            "text/xml",
+            format!(
+                "<GetCallerIdentityResponse xmlns=\"https://sts.amazonaws.com/doc/2011-06-15/\">\
+                 <GetCallerIdentityResult><Arn>arn:aws:iam::{0}:user/demo</Arn><UserId>AIDADEMO</UserId>\
+                 <Account>{0}</Account></GetCallerIdentityResult>\
+                 <ResponseMetadata><RequestId>demo</RequestId></ResponseMetadata></GetCallerIdentityResponse>",
+                ACCOUNT
+            ),
+        ),
+        None => (
+            400,
+            "text/xml",
+            "<ErrorResponse><Error><Code>InvalidAction</Code><Message>That isn't part of the demo</Message>\
+             </Error></ErrorResponse>"
+                .to_owned(),
+        ),
+    };
+    let response = Response::builder()
+        .status(status)
+        .header("content-type", content_type)
+        .body(Body::from(body))
+        .expect("Couldn't build the demo response");
+    Ok(response)
+}
+
+fn answer(
+    world: &mut World,
+    operation: &str,
+    input: &Value,
+) -> Result<Value, (&'static str, String)> {
+    let name = input["name"]
+        .as_str()
+        .or_else(|| input["pipelineName"].as_str())
+        .or_else(|| {
+            input["resourceArn"]
+                .as_str()
+                .and_then(|arn| arn.rsplit(':').next())
+        })
+        .unwrap_or_default()
+        .to_owned();
+    if operation == "ListPipelines" {
+        let pipelines = world
+            .pipelines
+            .iter()
+            .map(|pipeline| json!({ "name": pipeline.name, "version": 1 }))
+            .collect::<Vec<_>>();
+        return Ok(json!({ "pipelines": pipelines }));
+    }
+    if world.pipeline(&name).is_none() {
+        return Err((
+            "PipelineNotFoundException",
+            format!("There's no pipeline called {} in the demo", name),
+        ));
+    }
+    if operation == "StartPipelineExecution" {
+        let now = world.now();
+        let World { rng, pipelines, .. } = world;
+        let pipeline = pipelines
+            .iter_mut()
+            .find(|pipeline| pipeline.name == name)
+            .expect("looked up above");
+        let id = pipeline.start(rng, "StartPipelineExecution", now);
+        return Ok(json!({ "pipelineExecutionId": id }));
+    }
+    let pipeline = world.pipeline(&name).expect("looked up above");
+    match operation {
+        "ListTagsForResource" => Ok(json!({
+            "tags": [
+                { "key": "team", "value": pipeline.team },
+                { "key": "environment", "value": pipeline.name.rsplit('-').next() },
+            ]
+        })),
+        "ListPipelineExecutions" => {
+            Ok(pipeline.summaries(input["maxResults"].as_u64().unwrap_or(100) as usize))
+        }
+        "GetPipelineState" => Ok(pipeline.state()),
+        "GetPipeline" => Ok(pipeline.definition()),
+        "GetPipelineExecution" => {
+            let id = input["pipelineExecutionId"].as_str().unwrap_or_default();
+            match pipeline
+                .executions
+                .iter()
+                .find(|execution| execution.id == id)
+            {
+                Some(execution) => Ok(json!({
+                    "pipelineExecution": {
+                        "pipelineName": pipeline.name,
+                        "pipelineExecutionId": execution.id,
+                        "status": execution.status,
+                        "variables": [],
+                    }
+                })),
+                None => Err((
+                    "PipelineExecutionNotFoundException",
+                    format!("{} has no execution {}", pipeline.name, id),
+                )),
+            }
+        }
+        "ListActionExecutions" => {
+            Ok(pipeline.action_executions(input["filter"]["pipelineExecutionId"].as_str()))
+        }
+        _ => Err((
+            "ValidationException",
+            format!("{} isn't part of the demo", operation),
+        )),
+    }
+}
@@ -0,0 +1,222 @@
+use crate::aws::Clients;
+use rusoto_codedeploy::{
+    BatchGetDeploymentTargetsInput, CodeDeploy, GetDeploymentInput, LifecycleEvent,
+    ListDeploymentTargetsInput,
+};
+use rusoto_ecs::{DescribeServicesRequest, Ecs};
+
+use std::error::Error;
+
+// where to look for a deploy action's rollout, depending on its provider
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeploymentSource {
+    // CodeDeploy and CodeDeployToECS actions, identified by the deployment ID the action reports
+    CodeDeploy(String),
+    // standard (rolling) ECS deploy actions, which only tell us the service they updated
+    Ecs { cluster: String, service: String },
+}
+
+// how far along a rollout is, flattened into something we can draw for either kind of deployment
+#[derive(Clone, Debug, Default)]
+pub struct DeploymentProgress {
+    pub id: String,
+    pub status: String,
+    // "3 succeeded, 1 in progress" style instance counts for EC2/on-prem deployments
+    pub overview: Option<String>,
+    pub task_sets: Vec<TaskSetProgress>,
+    pub lifecycle_events: Vec<LifecycleHook>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TaskSetProgress {
+    // Blue/Green for CodeDeploy task sets, PRIMARY/ACTIVE for ECS rolling deployments
+    pub label: String,
+    pub status: String,
+    pub running: i64,
+    pub pending: i64,
+    pub desired: i64,
+    pub traffic_weight: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LifecycleHook {
+    pub target: String,
+    pub name: String,
+    pub status: String,
+    pub message: Option<String>,
+}
+
+pub async fn fetch_deployment(
+    clients: &Clients,
+    source: &DeploymentSource,
+) -> Result<DeploymentProgress, Box<dyn Error + Send + Sync>> {
+    match source {
+        DeploymentSource::CodeDeploy(deployment_id) => {
+            fetch_codedeploy(clients, deployment_id).await
+        }
+        DeploymentSource::Ecs { cluster, service } => fetch_ecs(clients, cluster, service).await,
+    }
+}
+
+async fn fetch_codedeploy(
+    clients: &Clients,
+    deployment_id: &str,
+) -> Result<DeploymentProgress, Box<dyn Error + Send + Sync>> {
+    let info = clients
+        .codedeploy
+        .get_deployment(GetDeploymentInput {
+            deployment_id: deployment_id.to_owned(),
+        })
+        .await?
+        .deployment_info
+        .ok_or_else(|| format!("Couldn't find deployment {}", deployment_id))?;
+
+    let mut progress = DeploymentProgress {
+        id: deployment_id.to_owned(),
+        status: info.status.unwrap_or_else(|| "Unknown".to_owned()),
+        overview: info.deployment_overview.map(|overview| {
+            [
+                ("succeeded", overview.succeeded),
+                ("in progress", overview.in_progress),
+                ("pending", overview.pending),
+                ("failed", overview.failed),
+                ("skipped", overview.skipped),
+                ("ready", overview.ready),
+            ]
+            .iter()
+            .filter_map(|(label, count)| match count {
+                Some(count) if *count > 0 => Some(format!("{} {}", count, label)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+        }),
+        error: info
+            .error_information
+            .and_then(|error| error.message.or(error.code)),
+        ..DeploymentProgress::default()
+    };
+
+    // the per-target detail (task sets, lifecycle hooks) lives on the deployment's targets
+    // a deployment rarely has more than a handful, and batch_get_deployment_targets takes up to 25 at a time
+    let target_ids = clients
+        .codedeploy
+        .list_deployment_targets(ListDeploymentTargetsInput {
+            deployment_id: Some(deployment_id.to_owned()),
+            next_token: None,
+            target_filters: None,
+        })
+        .await?
+        .target_ids
+        .unwrap_or_default();
+    if target_ids.is_empty() {
+        return Ok(progress);
+    }
+    let targets = clients
+        .codedeploy
+        .batch_get_deployment_targets(BatchGetDeploymentTargetsInput {
+            deployment_id: Some(deployment_id.to_owned()),
+            target_ids: Some(target_ids.into_iter().take(25).collect()),
+        })
+        .await?
+        .deployment_targets
+        .unwrap_or_default();
+
+    for target in targets {
+        if let Some(ecs) = target.ecs_target {
+            let name = ecs.target_id.unwrap_or_default();
+            progress
+                .task_sets
+                .extend(
+                    ecs.task_sets_info
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|set| TaskSetProgress {
+                            label: set
+                                .task_set_label
+                                .or(set.identifer)
+                                .unwrap_or_else(|| "?".to_owned()),
+                            status: set.status.unwrap_or_default(),
+                            running: set.running_count.unwrap_or(0),
+                            pending: set.pending_count.unwrap_or(0),
+                            desired: set.desired_count.unwrap_or(0),
+                            traffic_weight: set.traffic_weight,
+                        }),
+                );
+            progress
+                .lifecycle_events
+                .extend(lifecycle_hooks(&name, ecs.lifecycle_events));
+        } else if let Some(instance) = target.instance_target {
+            let name = instance.target_id.unwrap_or_default();
+            progress
+                .lifecycle_events
+                .extend(lifecycle_hooks(&name, instance.lifecycle_events));
+        }
+    }
+    Ok(progress)
+}
+
+// a rolling ECS deployment doesn't go through CodeDeploy at all, so the service's own deployments are the progress
+async fn fetch_ecs(
+    clients: &Clients,
+    cluster: &str,
+    service: &str,
+) -> Result<DeploymentProgress, Box<dyn Error + Send + Sync>> {
+    let service = clients
+        .ecs
+        .describe_services(DescribeServicesRequest {
+            cluster: Some(cluster.to_owned()),
+            include: None,
+            services: vec![service.to_owned()],
+        })
+        .await?
+        .services
+        .and_then(|services| services.into_iter().next())
+        .ok_or_else(|| format!("Couldn't find ECS service {} in {}", service, cluster))?;
+
+    let deployments = service.deployments.unwrap_or_default();
+    // once everything but the PRIMARY deployment has drained away, the rollout is done
+    let status = if deployments.len() > 1 {
+        "InProgress"
+    } else {
+        "Stable"
+    };
+    Ok(DeploymentProgress {
+        id: service.service_name.unwrap_or_default(),
+        status: status.to_owned(),
+        task_sets: deployments
+            .into_iter()
+            .map(|deployment| TaskSetProgress {
+                label: deployment.status.clone().unwrap_or_default(),
+                status: deployment
+                    .task_definition
+                    .as_deref()
+                    // the ARN is long, "family:revision" on the end is what changes between deployments
+                    .and_then(|arn| arn.rsplit('/').next())
+                    .unwrap_or_default()
+                    .to_owned(),
+                running: deployment.running_count.unwrap_or(0),
+                pending: deployment.pending_count.unwrap_or(0),
+                desired: deployment.desired_count.unwrap_or(0),
+                traffic_weight: None,
+            })
+            .collect(),
+        ..DeploymentProgress::default()
+    })
+}
+
+fn lifecycle_hooks(target: &str, events: Option<Vec<LifecycleEvent>>) -> Vec<LifecycleHook> {
+    events
+        .unwrap_or_default()
+        .into_iter()
+        .map(|event| LifecycleHook {
+            target: target.to_owned(),
+            name: event.lifecycle_event_name.unwrap_or_default(),
+            status: event.status.unwrap_or_default(),
+            message: event
+                .diagnostics
+                .and_then(|diagnostics| diagnostics.message),
+        })
+        .collect()
+}
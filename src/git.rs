@@ -0,0 +1,594 @@
+use crate::aws::Clients;
+use crate::config::{BitbucketConfig, Config, GitHubConfig};
+use rusoto_codecommit::{CodeCommit, CodeCommitClient, GetCommitInput};
+use rusoto_codepipeline::{
+    ActionDeclaration, PipelineDeclaration, PipelineExecutionSummary, SourceRevision,
+};
+use rusoto_codestar_connections::{CodeStarConnections, GetConnectionInput, GetHostInput};
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::error::Error;
+
+// the bits of a commit people actually recognize
+#[derive(Clone, Debug)]
+pub struct CommitInfo {
+    pub message: String,
+    pub author: Option<String>,
+    pub branch: Option<String>,
+}
+
+// where a source action pulls its code from, worked out from the action's configuration in the pipeline definition
+#[derive(Clone, Debug)]
+pub enum SourceRepo {
+    CodeCommit {
+        repository: String,
+        branch: Option<String>,
+    },
+    GitHub {
+        owner: String,
+        repo: String,
+        branch: Option<String>,
+    },
+    Bitbucket {
+        workspace: String,
+        repo: String,
+        branch: Option<String>,
+    },
+    // a CodeStar Connections (GitHub v2, Bitbucket, ...) source whose provider we haven't asked about yet
+    Connection {
+        connection_arn: String,
+        full_repository_id: String,
+        branch: Option<String>,
+    },
+    // S3 and friends, there's no commit to look up
+    Other,
+}
+
+impl SourceRepo {
+    fn from_action(action: &ActionDeclaration) -> SourceRepo {
+        let configuration = action.configuration.clone().unwrap_or_default();
+        let get = |key: &str| configuration.get(key).cloned();
+        match action.action_type_id.provider.as_str() {
+            "CodeCommit" => match get("RepositoryName") {
+                Some(repository) => SourceRepo::CodeCommit {
+                    repository,
+                    branch: get("BranchName"),
+                },
+                None => SourceRepo::Other,
+            },
+            // the original (OAuth token) GitHub source action
+            "GitHub" => match (get("Owner"), get("Repo")) {
+                (Some(owner), Some(repo)) => SourceRepo::GitHub {
+                    owner,
+                    repo,
+                    branch: get("Branch"),
+                },
+                _ => SourceRepo::Other,
+            },
+            // the action doesn't say which provider is behind the connection, the connection itself does
+            "CodeStarSourceConnection" => match (get("ConnectionArn"), get("FullRepositoryId")) {
+                (Some(connection_arn), Some(full_repository_id)) => SourceRepo::Connection {
+                    connection_arn,
+                    full_repository_id,
+                    branch: get("BranchName"),
+                },
+                _ => SourceRepo::Other,
+            },
+            _ => SourceRepo::Other,
+        }
+    }
+
+    // swap a Connection for the provider it actually points at, so we know which API to ask for commits
+    async fn resolve_connection(self, clients: &Clients, github: &GitHubConfig) -> SourceRepo {
+        let (connection_arn, full_repository_id, branch) = match self {
+            SourceRepo::Connection {
+                connection_arn,
+                full_repository_id,
+                branch,
+            } => (connection_arn, full_repository_id, branch),
+            other => return other,
+        };
+
+        let connection = match clients
+            .codestar_connections
+            .get_connection(GetConnectionInput {
+                connection_arn: connection_arn.clone(),
+            })
+            .await
+        {
+            Ok(output) => output.connection,
+            Err(e) => {
+                warn!("Couldn't look up connection {}: {}", connection_arn, e);
+                None
+            }
+        };
+        let (provider_type, host_arn) = match connection {
+            Some(connection) => (connection.provider_type, connection.host_arn),
+            None => (None, None),
+        };
+        // FullRepositoryId is "owner/repo" for GitHub and "workspace/repo" for Bitbucket
+        let (owner, repo) = match full_repository_id.split_once('/') {
+            Some((owner, repo)) => (owner.to_owned(), repo.to_owned()),
+            None => return SourceRepo::Other,
+        };
+        match provider_type.as_deref() {
+            Some("GitHub") => SourceRepo::GitHub {
+                owner,
+                repo,
+                branch,
+            },
+            // only worth asking when [github] points at the same server, the token's no use (or business) anywhere
+            // else
+            Some("GitHubEnterpriseServer") => {
+                match enterprise_api_url(clients, host_arn.as_deref()).await {
+                    Some(api_url) if api_url == github.api_url.trim_end_matches('/') => {
+                        SourceRepo::GitHub {
+                            owner,
+                            repo,
+                            branch,
+                        }
+                    }
+                    api_url => {
+                        warn!(
+                            "{} is on GitHub Enterprise Server, set [github] api_url to {} to look up its commits",
+                            full_repository_id,
+                            api_url.as_deref().unwrap_or("its API")
+                        );
+                        SourceRepo::Other
+                    }
+                }
+            }
+            Some("Bitbucket") => SourceRepo::Bitbucket {
+                workspace: owner,
+                repo,
+                branch,
+            },
+            _ => SourceRepo::Other,
+        }
+    }
+
+    fn branch(&self) -> Option<String> {
+        match self {
+            SourceRepo::CodeCommit { branch, .. }
+            | SourceRepo::GitHub { branch, .. }
+            | SourceRepo::Bitbucket { branch, .. }
+            | SourceRepo::Connection { branch, .. } => branch.clone(),
+            SourceRepo::Other => None,
+        }
+    }
+}
+
+// a GitHub Enterprise Server connection's host knows where the server is, whose API is under /api/v3
+async fn enterprise_api_url(clients: &Clients, host_arn: Option<&str>) -> Option<String> {
+    let host_arn = host_arn?;
+    match clients
+        .codestar_connections
+        .get_host(GetHostInput {
+            host_arn: host_arn.to_owned(),
+        })
+        .await
+    {
+        Ok(host) => Some(format!(
+            "{}/api/v3",
+            host.provider_endpoint?.trim_end_matches('/')
+        )),
+        Err(e) => {
+            warn!("Couldn't look up host {}: {}", host_arn, e);
+            None
+        }
+    }
+}
+
+// how one source action's revision differs between two executions
+#[derive(Clone, Debug)]
+pub struct SourceChange {
+    pub action_name: String,
+    pub base: Option<String>,
+    pub head: Option<String>,
+    // newest first, when the repository could tell us
+    pub commits: Option<Vec<(String, CommitInfo)>>,
+    // the commits stop short of the base, so there are more in between than are listed
+    pub truncated: bool,
+}
+
+impl SourceChange {
+    pub fn changed(&self) -> bool {
+        self.base != self.head
+    }
+}
+
+// walking CodeCommit history is one call per commit, so don't go too far back
+const MAX_CODECOMMIT_COMMITS: usize = 50;
+
+#[derive(Deserialize)]
+struct GitHubCommit {
+    sha: String,
+    commit: GitHubCommitDetail,
+}
+
+#[derive(Deserialize)]
+struct GitHubComparison {
+    // oldest first, and no more than 250 of them however many there are
+    commits: Vec<GitHubCommit>,
+    total_commits: usize,
+}
+
+#[derive(Deserialize)]
+struct BitbucketCommits {
+    values: Vec<BitbucketCommit>,
+    // only there when there's another page
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubCommitDetail {
+    message: String,
+    author: Option<GitHubAuthor>,
+}
+
+#[derive(Deserialize)]
+struct GitHubAuthor {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct BitbucketCommit {
+    hash: String,
+    message: String,
+    author: Option<BitbucketAuthor>,
+}
+
+#[derive(Deserialize)]
+struct BitbucketAuthor {
+    // "Name <email>", always there even when the author isn't a Bitbucket user
+    raw: String,
+}
+
+// for connection sources CodePipeline's summary is a little JSON document rather than the commit message itself
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConnectionRevisionSummary {
+    commit_message: String,
+}
+
+pub struct CommitResolver {
+    http: reqwest::Client,
+    github: GitHubConfig,
+    bitbucket: BitbucketConfig,
+    codecommit: CodeCommitClient,
+    // keyed by source action name, which is what a SourceRevision tells us
+    sources: HashMap<String, SourceRepo>,
+}
+
+impl CommitResolver {
+    pub async fn new(
+        config: &Config,
+        clients: &Clients,
+        declaration: &PipelineDeclaration,
+    ) -> CommitResolver {
+        let mut sources = HashMap::new();
+        for action in declaration
+            .stages
+            .iter()
+            .flat_map(|stage| stage.actions.iter())
+            .filter(|action| action.action_type_id.category == "Source")
+        {
+            let source = SourceRepo::from_action(action)
+                .resolve_connection(clients, &config.github)
+                .await;
+            sources.insert(action.name.clone(), source);
+        }
+        CommitResolver {
+            http: clients.http.clone(),
+            github: config.github.clone(),
+            bitbucket: config.bitbucket.clone(),
+            codecommit: clients.codecommit.clone(),
+            sources,
+        }
+    }
+
+    // CodePipeline's revision summary is often truncated or empty, so ask the repository itself whenever we can
+    // and fall back to the summary when we can't (or aren't allowed to)
+    pub async fn resolve(&self, revision: &SourceRevision) -> CommitInfo {
+        let source = self
+            .sources
+            .get(&revision.action_name)
+            .unwrap_or(&SourceRepo::Other);
+        let revision_id = revision.revision_id.as_deref().unwrap_or_default();
+
+        match self.look_up(source, revision_id).await {
+            Ok(Some(info)) => info,
+            Ok(None) => summary_commit(revision, source),
+            Err(e) => {
+                warn!("Couldn't look up commit {}: {}", revision_id, e);
+                summary_commit(revision, source)
+            }
+        }
+    }
+
+    async fn look_up(
+        &self,
+        source: &SourceRepo,
+        revision_id: &str,
+    ) -> Result<Option<CommitInfo>, Box<dyn Error + Send + Sync>> {
+        match source {
+            SourceRepo::CodeCommit { repository, branch } => {
+                let commit = self
+                    .codecommit
+                    .get_commit(GetCommitInput {
+                        commit_id: revision_id.to_owned(),
+                        repository_name: repository.clone(),
+                    })
+                    .await?
+                    .commit;
+                Ok(Some(CommitInfo {
+                    message: first_line(commit.message.as_deref().unwrap_or_default()),
+                    author: commit.author.and_then(|author| author.name),
+                    branch: branch.clone(),
+                }))
+            }
+            SourceRepo::GitHub {
+                owner,
+                repo,
+                branch,
+            } => {
+                let token = match &self.github.token {
+                    Some(token) => token,
+                    None => return Ok(None),
+                };
+                let commit = self
+                    .http
+                    .get(&format!(
+                        "{}/repos/{}/{}/commits/{}",
+                        self.github.api_url, owner, repo, revision_id
+                    ))
+                    .header("Authorization", format!("token {}", token))
+                    .header("Accept", "application/vnd.github.v3+json")
+                    // GitHub rejects requests without one
+                    .header("User-Agent", "codepipeline-status")
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<GitHubCommit>()
+                    .await?
+                    .commit;
+                Ok(Some(CommitInfo {
+                    message: first_line(&commit.message),
+                    author: commit.author.map(|author| author.name),
+                    branch: branch.clone(),
+                }))
+            }
+            SourceRepo::Bitbucket {
+                workspace,
+                repo,
+                branch,
+            } => {
+                let request = self.http.get(&format!(
+                    "https://api.bitbucket.org/2.0/repositories/{}/{}/commit/{}",
+                    workspace, repo, revision_id
+                ));
+                // public repositories don't need credentials
+                let request = match (&self.bitbucket.username, &self.bitbucket.app_password) {
+                    (Some(username), Some(password)) => {
+                        request.basic_auth(username, Some(password))
+                    }
+                    _ => request,
+                };
+                let commit = request
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<BitbucketCommit>()
+                    .await?;
+                Ok(Some(CommitInfo {
+                    message: first_line(&commit.message),
+                    author: commit.author.map(|author| bitbucket_name(author.raw)),
+                    branch: branch.clone(),
+                }))
+            }
+            SourceRepo::Connection { .. } | SourceRepo::Other => Ok(None),
+        }
+    }
+
+    // what each source action was running in `head` compared to `base`, with the commits in between where we can get them
+    pub async fn compare(
+        &self,
+        base: &PipelineExecutionSummary,
+        head: &PipelineExecutionSummary,
+    ) -> Vec<SourceChange> {
+        let base_revisions = base.source_revisions.clone().unwrap_or_default();
+        let mut changes = vec![];
+        for revision in head.source_revisions.iter().flatten() {
+            let base_id = base_revisions
+                .iter()
+                .find(|other| other.action_name == revision.action_name)
+                .and_then(|other| other.revision_id.clone());
+            let mut change = SourceChange {
+                action_name: revision.action_name.clone(),
+                base: base_id,
+                head: revision.revision_id.clone(),
+                commits: None,
+                truncated: false,
+            };
+            if let (true, Some(base_id), Some(head_id)) =
+                (change.changed(), &change.base, &change.head)
+            {
+                let source = self
+                    .sources
+                    .get(&revision.action_name)
+                    .unwrap_or(&SourceRepo::Other);
+                match self.commits_between(source, base_id, head_id).await {
+                    Ok(commits) => {
+                        change.truncated = commits.as_ref().is_some_and(|(_, reached)| !reached);
+                        change.commits = commits.map(|(commits, _)| commits);
+                    }
+                    Err(e) => {
+                        warn!("Couldn't list commits {}..{}: {}", base_id, head_id, e)
+                    }
+                }
+            }
+            changes.push(change);
+        }
+        changes
+    }
+
+    async fn commits_between(
+        &self,
+        source: &SourceRepo,
+        base: &str,
+        head: &str,
+    ) -> Result<Option<(Vec<(String, CommitInfo)>, bool)>, Box<dyn Error + Send + Sync>> {
+        // along with the commits, whether they go all the way back to the base
+        match source {
+            // there's no log API, so follow first parents back from the head until we hit the base
+            SourceRepo::CodeCommit { repository, branch } => {
+                let mut commits = vec![];
+                let mut next = head.to_owned();
+                while next != base && commits.len() < MAX_CODECOMMIT_COMMITS {
+                    let commit = self
+                        .codecommit
+                        .get_commit(GetCommitInput {
+                            commit_id: next.clone(),
+                            repository_name: repository.clone(),
+                        })
+                        .await?
+                        .commit;
+                    let parent = commit
+                        .parents
+                        .as_ref()
+                        .and_then(|parents| parents.first())
+                        .cloned();
+                    commits.push((
+                        next,
+                        CommitInfo {
+                            message: first_line(commit.message.as_deref().unwrap_or_default()),
+                            author: commit.author.and_then(|author| author.name),
+                            branch: branch.clone(),
+                        },
+                    ));
+                    next = match parent {
+                        Some(parent) => parent,
+                        // the start of the history without coming across the base
+                        None => return Ok(Some((commits, false))),
+                    };
+                }
+                let reached = next == base;
+                Ok(Some((commits, reached)))
+            }
+            SourceRepo::GitHub {
+                owner,
+                repo,
+                branch,
+            } => {
+                let token = match &self.github.token {
+                    Some(token) => token,
+                    None => return Ok(None),
+                };
+                let comparison = self
+                    .http
+                    .get(&format!(
+                        "{}/repos/{}/{}/compare/{}...{}",
+                        self.github.api_url, owner, repo, base, head
+                    ))
+                    .header("Authorization", format!("token {}", token))
+                    .header("Accept", "application/vnd.github.v3+json")
+                    .header("User-Agent", "codepipeline-status")
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<GitHubComparison>()
+                    .await?;
+                let reached = comparison.commits.len() >= comparison.total_commits;
+                Ok(Some((
+                    comparison
+                        .commits
+                        .into_iter()
+                        .rev()
+                        .map(|commit| {
+                            (
+                                commit.sha,
+                                CommitInfo {
+                                    message: first_line(&commit.commit.message),
+                                    author: commit.commit.author.map(|author| author.name),
+                                    branch: branch.clone(),
+                                },
+                            )
+                        })
+                        .collect(),
+                    reached,
+                )))
+            }
+            SourceRepo::Bitbucket {
+                workspace,
+                repo,
+                branch,
+            } => {
+                let request = self
+                    .http
+                    .get(&format!(
+                        "https://api.bitbucket.org/2.0/repositories/{}/{}/commits/{}",
+                        workspace, repo, head
+                    ))
+                    .query(&[("exclude", base), ("pagelen", "100")]);
+                let request = match (&self.bitbucket.username, &self.bitbucket.app_password) {
+                    (Some(username), Some(password)) => {
+                        request.basic_auth(username, Some(password))
+                    }
+                    _ => request,
+                };
+                let commits = request
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<BitbucketCommits>()
+                    .await?;
+                let reached = commits.next.is_none();
+                Ok(Some((
+                    commits
+                        .values
+                        .into_iter()
+                        .map(|commit| {
+                            (
+                                commit.hash,
+                                CommitInfo {
+                                    message: first_line(&commit.message),
+                                    author: commit.author.map(|author| bitbucket_name(author.raw)),
+                                    branch: branch.clone(),
+                                },
+                            )
+                        })
+                        .collect(),
+                    reached,
+                )))
+            }
+            SourceRepo::Connection { .. } | SourceRepo::Other => Ok(None),
+        }
+    }
+}
+
+// Bitbucket gives us "Name <email>", drop the "<email>" part
+fn bitbucket_name(raw: String) -> String {
+    match raw.split_once(" <") {
+        Some((name, _)) => name.to_owned(),
+        None => raw,
+    }
+}
+
+fn summary_commit(revision: &SourceRevision, source: &SourceRepo) -> CommitInfo {
+    let summary = revision.revision_summary.as_deref().unwrap_or_default();
+    let message = match serde_json::from_str::<ConnectionRevisionSummary>(summary) {
+        Ok(parsed) => parsed.commit_message,
+        Err(_) => summary.to_owned(),
+    };
+    CommitInfo {
+        message: first_line(&message),
+        author: None,
+        branch: source.branch(),
+    }
+}
+
+// commit subjects are what fit on screen, the body can stay in git
+fn first_line(message: &str) -> String {
+    message.lines().next().unwrap_or_default().to_owned()
+}
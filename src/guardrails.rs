@@ -0,0 +1,40 @@
+use crate::aws::{self, Clients};
+use crate::config::GuardrailsConfig;
+
+use std::error::Error;
+use std::future::Future;
+
+// the call that changes the pipeline, but only once the credentials have turned out to belong to someone its
+// [guardrails] allow, so a refusal goes in the audit log like any other failure. Asked every time rather than
+// once at startup, since signing in again can leave us as someone else
+pub async fn guarded<T>(
+    clients: &Clients,
+    config: &GuardrailsConfig,
+    pipeline: &str,
+    action: &str,
+    call: impl Future<Output = Result<T, Box<dyn Error + Send + Sync>>>,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    if let Some(roles) = config.roles(pipeline) {
+        let arn = aws::caller_arn(clients).await.map_err(|e| {
+            format!(
+                "Couldn't check who we're signed in as, so not going to {} {}: {}",
+                action, pipeline, e
+            )
+        })?;
+        if !config.allows(pipeline, &arn) {
+            return Err(format!(
+                "{} isn't allowed to {} {}, only {}",
+                arn,
+                action,
+                pipeline,
+                if roles.is_empty() {
+                    "nobody".to_owned()
+                } else {
+                    roles.join(", ")
+                }
+            )
+            .into());
+        }
+    }
+    call.await
+}
@@ -0,0 +1,275 @@
+use crate::config;
+use crate::stats::{ObservedRun, StageRun, StageStats};
+use rusoto_codepipeline::{PipelineDeclaration, PipelineExecutionSummary};
+use rusqlite::{params, Connection};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+// every stage run and execution we've seen finish, so the statistics survive restarts and can go back further
+// than the handful of executions CodePipeline hands back
+pub struct History {
+    connection: Connection,
+    // how far back the statistics look, in seconds
+    window: f64,
+}
+
+// an execution kept for finding again later, say after an incident
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bookmark {
+    pub pipeline: String,
+    pub execution_id: String,
+    // only once we've seen it finish
+    pub status: Option<String>,
+    pub started: Option<f64>,
+    pub seconds: Option<u64>,
+    pub note: Option<String>,
+}
+
+impl History {
+    pub fn path() -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+        Ok(config::data_dir()?.join("history.db"))
+    }
+
+    pub fn open(days: u32) -> Result<History, Box<dyn Error + Send + Sync>> {
+        let path = History::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let connection = Connection::open(&path)
+            .map_err(|e| format!("Couldn't open {}: {}", path.display(), e))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS stage_runs (
+                pipeline TEXT NOT NULL,
+                execution_id TEXT NOT NULL,
+                stage TEXT NOT NULL,
+                started REAL NOT NULL,
+                seconds INTEGER NOT NULL,
+                succeeded INTEGER NOT NULL,
+                PRIMARY KEY (pipeline, execution_id, stage)
+            );
+            CREATE TABLE IF NOT EXISTS executions (
+                pipeline TEXT NOT NULL,
+                execution_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                started REAL NOT NULL,
+                seconds INTEGER NOT NULL,
+                PRIMARY KEY (pipeline, execution_id)
+            );
+            CREATE TABLE IF NOT EXISTS bookmarks (
+                pipeline TEXT NOT NULL,
+                execution_id TEXT NOT NULL,
+                bookmarked REAL NOT NULL,
+                PRIMARY KEY (pipeline, execution_id)
+            );
+            CREATE TABLE IF NOT EXISTS notes (
+                pipeline TEXT NOT NULL,
+                execution_id TEXT NOT NULL,
+                note TEXT NOT NULL,
+                written REAL NOT NULL,
+                PRIMARY KEY (pipeline, execution_id)
+            );",
+        )?;
+        let history = History {
+            connection,
+            window: f64::from(days) * 24.0 * 60.0 * 60.0,
+        };
+        history.forget_old()?;
+        Ok(history)
+    }
+
+    // a stage that's retried later in the same execution replaces its earlier run
+    pub fn record_runs(
+        &self,
+        pipeline: &str,
+        runs: &[ObservedRun],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut statement = self.connection.prepare_cached(
+            "INSERT OR REPLACE INTO stage_runs (pipeline, execution_id, stage, started, seconds, succeeded)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for observed in runs {
+            statement.execute(params![
+                pipeline,
+                observed.execution_id,
+                observed.stage,
+                observed.started,
+                observed.run.seconds as i64,
+                observed.run.succeeded,
+            ])?;
+        }
+        Ok(())
+    }
+
+    // only the finished ones, a running execution's duration would be out of date straight away
+    pub fn record_executions(
+        &self,
+        pipeline: &str,
+        executions: &[PipelineExecutionSummary],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut statement = self.connection.prepare_cached(
+            "INSERT OR REPLACE INTO executions (pipeline, execution_id, status, started, seconds)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for execution in executions {
+            let status = execution.status.as_deref().unwrap_or_default();
+            if status == "InProgress" || status == "Stopping" {
+                continue;
+            }
+            if let (Some(id), Some(start), Some(end)) = (
+                &execution.pipeline_execution_id,
+                execution.start_time,
+                execution.last_update_time,
+            ) {
+                statement.execute(params![
+                    pipeline,
+                    id,
+                    status,
+                    start,
+                    (end - start).max(0.0) as i64,
+                ])?;
+            }
+        }
+        Ok(())
+    }
+
+    // the same statistics `stats::compute` works out, but from everything we've kept rather than the last few runs
+    pub fn stats(
+        &self,
+        pipeline: &str,
+        definition: &PipelineDeclaration,
+    ) -> Result<Vec<StageStats>, Box<dyn Error + Send + Sync>> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT seconds, succeeded FROM stage_runs
+            WHERE pipeline = ?1 AND stage = ?2 AND started >= ?3
+            ORDER BY started",
+        )?;
+        let since = now() - self.window;
+        let mut stats = vec![];
+        for stage in &definition.stages {
+            let runs = statement
+                .query_map(params![pipeline, stage.name, since], |row| {
+                    Ok(StageRun {
+                        seconds: row.get::<_, i64>(0)?.max(0) as u64,
+                        succeeded: row.get(1)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            stats.push(StageStats {
+                stage: stage.name.clone(),
+                runs,
+            });
+        }
+        Ok(stats)
+    }
+
+    // "rollback of #123", "hotfix for incident-42": whatever someone wants to remember about an execution, which
+    // CodePipeline has nowhere to put. An empty note takes it off again
+    pub fn set_note(
+        &self,
+        pipeline: &str,
+        execution_id: &str,
+        note: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if note.trim().is_empty() {
+            self.connection.execute(
+                "DELETE FROM notes WHERE pipeline = ?1 AND execution_id = ?2",
+                params![pipeline, execution_id],
+            )?;
+        } else {
+            self.connection.execute(
+                "INSERT OR REPLACE INTO notes (pipeline, execution_id, note, written) VALUES (?1, ?2, ?3, ?4)",
+                params![pipeline, execution_id, note.trim(), now()],
+            )?;
+        }
+        Ok(())
+    }
+
+    // every note on the pipeline's executions, by execution ID
+    pub fn notes(
+        &self,
+        pipeline: &str,
+    ) -> Result<HashMap<String, String>, Box<dyn Error + Send + Sync>> {
+        let mut statement = self
+            .connection
+            .prepare_cached("SELECT execution_id, note FROM notes WHERE pipeline = ?1")?;
+        let notes = statement
+            .query_map(params![pipeline], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(notes)
+    }
+
+    pub fn set_bookmark(
+        &self,
+        pipeline: &str,
+        execution_id: &str,
+        bookmarked: bool,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if bookmarked {
+            self.connection.execute(
+                "INSERT OR IGNORE INTO bookmarks (pipeline, execution_id, bookmarked) VALUES (?1, ?2, ?3)",
+                params![pipeline, execution_id, now()],
+            )?;
+        } else {
+            self.connection.execute(
+                "DELETE FROM bookmarks WHERE pipeline = ?1 AND execution_id = ?2",
+                params![pipeline, execution_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    // every bookmarked execution of every pipeline, the latest bookmarked first, along with whatever we saw of
+    // it finishing and any note on it
+    pub fn bookmarks(&self) -> Result<Vec<Bookmark>, Box<dyn Error + Send + Sync>> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT bookmarks.pipeline, bookmarks.execution_id, executions.status, executions.started,
+                executions.seconds, notes.note
+            FROM bookmarks
+            LEFT JOIN executions USING (pipeline, execution_id)
+            LEFT JOIN notes USING (pipeline, execution_id)
+            ORDER BY bookmarks.bookmarked DESC",
+        )?;
+        let bookmarks = statement
+            .query_map(params![], |row| {
+                Ok(Bookmark {
+                    pipeline: row.get(0)?,
+                    execution_id: row.get(1)?,
+                    status: row.get(2)?,
+                    started: row.get(3)?,
+                    seconds: row
+                        .get::<_, Option<i64>>(4)?
+                        .map(|seconds| seconds.max(0) as u64),
+                    note: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bookmarks)
+    }
+
+    // notes are left alone, they're what someone took the trouble to write down, and so is everything about a
+    // bookmarked execution
+    fn forget_old(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let since = now() - self.window;
+        for table in ["stage_runs", "executions"] {
+            self.connection.execute(
+                &format!(
+                    "DELETE FROM {table} WHERE started < ?1 AND NOT EXISTS (
+                        SELECT 1 FROM bookmarks
+                        WHERE bookmarks.pipeline = {table}.pipeline
+                        AND bookmarks.execution_id = {table}.execution_id
+                    )",
+                    table = table
+                ),
+                params![since],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn now() -> f64 {
+    chrono::Local::now().timestamp() as f64
+}
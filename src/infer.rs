@@ -0,0 +1,166 @@
+use crate::aws::{self, Clients};
+use serde::Deserialize;
+use serde_json::Value;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// what a service repo can say about its own pipeline, at its root
+const REPO_FILE: &str = ".codepipeline-status";
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RepoFile {
+    pipeline: Option<String>,
+    stack: Option<String>,
+}
+
+// which pipeline the repository we're running in deploys through, trying in turn: a .codepipeline-status file,
+// the templates in CDK's output directory, and a pipeline named after the repository's remote
+pub async fn infer_pipeline(clients: &Clients) -> Option<String> {
+    let root = repo_root()?;
+
+    let path = root.join(REPO_FILE);
+    if let Ok(text) = fs::read_to_string(&path) {
+        match toml::from_str::<RepoFile>(&text) {
+            Ok(RepoFile {
+                pipeline: Some(pipeline),
+                ..
+            }) => return Some(pipeline),
+            Ok(RepoFile {
+                stack: Some(stack), ..
+            }) => return from_stack(clients, &stack).await,
+            Ok(_) => warn!("{} doesn't name a pipeline or a stack", path.display()),
+            Err(e) => warn!("Couldn't read {}: {}", path.display(), e),
+        }
+    }
+
+    if let Some(found) = from_cdk_out(&root) {
+        match found {
+            Found::Pipeline(pipeline) => return Some(pipeline),
+            Found::Stack(stack) => {
+                if let Some(pipeline) = from_stack(clients, &stack).await {
+                    return Some(pipeline);
+                }
+            }
+        }
+    }
+
+    from_remote(clients, &root).await
+}
+
+enum Found {
+    Pipeline(String),
+    // the pipeline's name is generated, but the stack it's in knows it
+    Stack(String),
+}
+
+async fn from_stack(clients: &Clients, stack: &str) -> Option<String> {
+    match aws::pipeline_for_stack(clients, stack).await {
+        Ok(pipeline) => Some(pipeline),
+        Err(e) => {
+            warn!("Couldn't find the pipeline in stack {}: {}", stack, e);
+            None
+        }
+    }
+}
+
+// the repository we're in, or the current directory outside of one
+fn repo_root() -> Option<PathBuf> {
+    match git(&["rev-parse", "--show-toplevel"]) {
+        Some(root) => Some(PathBuf::from(root)),
+        None => std::env::current_dir().ok(),
+    }
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?.trim().to_owned();
+    Some(text).filter(|text| !text.is_empty())
+}
+
+// a synthesized CDK app has a template per stack, only one of which should have a pipeline in it
+fn from_cdk_out(root: &Path) -> Option<Found> {
+    let cdk_json = fs::read_to_string(root.join("cdk.json")).ok()?;
+    let output = serde_json::from_str::<Value>(&cdk_json)
+        .ok()
+        .and_then(|cdk| cdk["output"].as_str().map(str::to_owned))
+        .unwrap_or_else(|| "cdk.out".to_owned());
+
+    let mut found = vec![];
+    for entry in fs::read_dir(root.join(output)).ok()?.flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let stack = match file_name.strip_suffix(".template.json") {
+            Some(stack) => stack.to_owned(),
+            None => continue,
+        };
+        let template = match fs::read_to_string(entry.path())
+            .ok()
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+        {
+            Some(template) => template,
+            None => continue,
+        };
+        let pipelines = template["Resources"]
+            .as_object()
+            .into_iter()
+            .flat_map(|resources| resources.values())
+            .filter(|resource| resource["Type"] == "AWS::CodePipeline::Pipeline")
+            .collect::<Vec<_>>();
+        match pipelines.as_slice() {
+            [] => {}
+            [pipeline] => found.push(match pipeline["Properties"]["Name"].as_str() {
+                Some(name) => Found::Pipeline(name.to_owned()),
+                None => Found::Stack(stack),
+            }),
+            _ => found.push(Found::Stack(stack)),
+        }
+    }
+    if found.len() == 1 {
+        found.pop()
+    } else {
+        None
+    }
+}
+
+// pipelines tend to be named after the service, so the one pipeline with the repository's name in its own
+async fn from_remote(clients: &Clients, root: &Path) -> Option<String> {
+    let root = root.display().to_string();
+    // git@github.com:owner/repo.git, https://github.com/owner/repo, codecommit::us-west-2://repo, ...
+    let url = git(&["-C", &root, "config", "--get", "remote.origin.url"])?;
+    let repo = url
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .rsplit(['/', ':'])
+        .next()
+        .map(normalize)
+        .filter(|repo| !repo.is_empty())?;
+    let names = match aws::list_pipeline_names(clients).await {
+        Ok(names) => names,
+        Err(e) => {
+            warn!("Couldn't list the pipelines: {}", e);
+            return None;
+        }
+    };
+    let mut matching = names
+        .into_iter()
+        .filter(|name| normalize(name).contains(&repo))
+        .collect::<Vec<_>>();
+    if matching.len() == 1 {
+        matching.pop()
+    } else {
+        None
+    }
+}
+
+// MyService, my-service and my_service are all the same service
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
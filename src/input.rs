@@ -0,0 +1,86 @@
+use crossterm::event::{self, Event, KeyCode};
+use tokio::sync::mpsc;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// how long the thread waits on the terminal before looking at whether it's been paused, so pausing takes at most
+// this long to come into effect
+pub const PAUSE_LATENCY: Duration = Duration::from_millis(100);
+
+// terminal events, read on a thread of their own since crossterm's read blocks, so a slow fetch or a redraw
+// never holds up reading them and none get lost. While paused it leaves the terminal alone, for something else
+// running in it that wants the keys
+pub fn spawn(paused: Arc<AtomicBool>) -> mpsc::UnboundedReceiver<Event> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    thread::spawn(move || loop {
+        if paused.load(Ordering::Relaxed) {
+            thread::sleep(PAUSE_LATENCY);
+            continue;
+        }
+        match event::poll(PAUSE_LATENCY) {
+            Ok(false) => continue,
+            Ok(true) => {}
+            Err(e) => {
+                warn!("Couldn't read from the terminal: {}", e);
+                return;
+            }
+        }
+        match event::read() {
+            Ok(event) => {
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                warn!("Couldn't read from the terminal: {}", e);
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+// what a batch of events that piled up amounts to
+pub struct Batch {
+    pub keys: Vec<KeyCode>,
+    // only the last size matters, dragging a window edge sends a burst of these
+    pub resize: Option<(u16, u16)>,
+}
+
+// a held-down arrow key queues up repeats faster than we get through them while something's loading, so a run
+// of the same movement key counts once rather than sending the selection flying off once we catch up
+pub fn coalesce(events: Vec<Event>) -> Batch {
+    let mut batch = Batch {
+        keys: vec![],
+        resize: None,
+    };
+    for event in events {
+        match event {
+            Event::Key(key) => {
+                if movement(key.code) && batch.keys.last() == Some(&key.code) {
+                    continue;
+                }
+                batch.keys.push(key.code);
+            }
+            Event::Resize(width, height) => batch.resize = Some((width, height)),
+            Event::Mouse(_) => {}
+        }
+    }
+    batch
+}
+
+// typing is never coalesced, a form wants every character
+fn movement(key: KeyCode) -> bool {
+    matches!(
+        key,
+        KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+    )
+}
@@ -0,0 +1,37 @@
+// everything but the terminal loop, so the integration tests can get at it too
+#[macro_use]
+extern crate log;
+
+pub mod alarms;
+pub mod app;
+pub mod audit;
+pub mod aws;
+pub mod cache;
+pub mod calls;
+pub mod cli;
+pub mod config;
+pub mod console;
+pub mod credentials;
+pub mod demo;
+pub mod deploy;
+pub mod git;
+pub mod guardrails;
+pub mod history;
+pub mod infer;
+pub mod input;
+pub mod list;
+pub mod logging;
+pub mod logview;
+pub mod macros;
+#[cfg(feature = "test-util")]
+pub mod mock;
+pub mod notify;
+pub mod partition;
+pub mod poller;
+pub mod provenance;
+pub mod stats;
+pub mod theme;
+pub mod ui;
+pub mod update;
+pub mod wait;
+pub mod watchlist;
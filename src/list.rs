@@ -0,0 +1,121 @@
+use crate::aws::{self, Clients, PipelineListing};
+use crate::cli::Output;
+use crate::poller::MAX_CONCURRENT_REQUESTS;
+use crate::ui::format_timestamp;
+use crate::watchlist::Watchlist;
+use chrono::{Local, TimeZone};
+use serde::Serialize;
+use tokio::sync::Semaphore;
+
+use std::error::Error;
+use std::io::{self, Write};
+
+// one line (or object) per pipeline, the same for every output
+#[derive(Serialize)]
+struct Row<'a> {
+    name: &'a str,
+    // null in JSON for a pipeline that's never run
+    status: Option<&'a str>,
+    // RFC 3339 in JSON, so other tools can parse it, and local time in the table
+    last_run: Option<String>,
+    region: &'a str,
+}
+
+// every pipeline in the region carrying all the given tags (and in the watchlist, with one), the same ones
+// the selector would show
+pub async fn list(
+    clients: &Clients,
+    tags: &[(String, String)],
+    watchlist: Option<&Watchlist>,
+    output: Output,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let account = aws::account_id(clients).await?;
+    let names = aws::list_pipeline_names(clients)
+        .await?
+        .into_iter()
+        .filter(|name| watchlist.is_none_or(|watchlist| watchlist.matches(name)))
+        .collect();
+    let listings = aws::fetch_listings(
+        clients,
+        &account,
+        names,
+        &Semaphore::new(MAX_CONCURRENT_REQUESTS),
+    )
+    .await
+    .into_iter()
+    .filter(|listing| tags.iter().all(|wanted| listing.tags.contains(wanted)))
+    .collect::<Vec<_>>();
+    let region = clients.region.name();
+
+    let mut stdout = io::stdout();
+    match output {
+        Output::Json => {
+            let rows = listings
+                .iter()
+                .map(|listing| {
+                    row(listing, region, |at| {
+                        Local
+                            .timestamp_opt(at as i64, 0)
+                            .single()
+                            .map(|at| at.to_rfc3339())
+                    })
+                })
+                .collect::<Vec<_>>();
+            serde_json::to_writer_pretty(&mut stdout, &rows)?;
+            writeln!(stdout)?;
+        }
+        Output::Table => {
+            let rows = listings
+                .iter()
+                .map(|listing| row(listing, region, |at| Some(format_timestamp(at))))
+                .collect::<Vec<_>>();
+            let name_width = rows
+                .iter()
+                .map(|row| row.name.len())
+                .max()
+                .unwrap_or(0)
+                .max(4);
+            let status_width = rows
+                .iter()
+                .map(|row| row.status.map_or(1, str::len))
+                .max()
+                .unwrap_or(0)
+                .max(6);
+            writeln!(
+                stdout,
+                "{:name_width$}  {:status_width$}  {:16}  REGION",
+                "NAME",
+                "STATUS",
+                "LAST RUN",
+                name_width = name_width,
+                status_width = status_width
+            )?;
+            for row in rows {
+                writeln!(
+                    stdout,
+                    "{:name_width$}  {:status_width$}  {:16}  {}",
+                    row.name,
+                    row.status.unwrap_or("-"),
+                    row.last_run.as_deref().unwrap_or("-"),
+                    row.region,
+                    name_width = name_width,
+                    status_width = status_width
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn row<'a>(
+    listing: &'a PipelineListing,
+    region: &'a str,
+    format: impl Fn(f64) -> Option<String>,
+) -> Row<'a> {
+    Row {
+        name: &listing.name,
+        status: listing.latest_status.as_deref(),
+        last_run: listing.last_run.and_then(format),
+        region,
+    }
+}
@@ -0,0 +1,66 @@
+use chrono::Local;
+use log::{Log, Metadata, Record, SetLoggerError};
+
+use std::env::var;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// everything logged this session, for the log view
+static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+// stderr is fine before and after the TUI, but while it's up anything written there scribbles over the screen
+static TO_STDERR: AtomicBool = AtomicBool::new(true);
+
+// pretty_env_logger's output, which also keeps a copy of every line
+struct CaptureLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.inner.matches(record) {
+            return;
+        }
+        if let Ok(mut lines) = LINES.lock() {
+            lines.push(format!(
+                "{} {:<5} {}",
+                Local::now().format("%H:%M:%S"),
+                record.level(),
+                record.args()
+            ));
+        }
+        if TO_STDERR.load(Ordering::Relaxed) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+// the same filtering try_init_timed_custom_env would do, reading the filters from `environment_variable_name`
+pub fn init(environment_variable_name: &str) -> Result<(), SetLoggerError> {
+    let mut builder = pretty_env_logger::formatted_timed_builder();
+    if let Ok(filters) = var(environment_variable_name) {
+        builder.parse_filters(&filters);
+    }
+    let inner = builder.build();
+    log::set_max_level(inner.filter());
+    log::set_boxed_logger(Box::new(CaptureLogger { inner }))
+}
+
+pub fn set_stderr(enabled: bool) {
+    TO_STDERR.store(enabled, Ordering::Relaxed)
+}
+
+// whatever's been logged since we'd already seen `seen` lines
+pub fn lines_since(seen: usize) -> Vec<String> {
+    match LINES.lock() {
+        Ok(lines) => lines.get(seen..).unwrap_or_default().to_vec(),
+        Err(_) => vec![],
+    }
+}
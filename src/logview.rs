@@ -0,0 +1,156 @@
+use crossterm::event::KeyCode;
+
+// how far PgUp and PgDn move
+const PAGE_LINES: usize = 20;
+
+// where a log pane is scrolled to and what it's searching for, the lines themselves live wherever they come from
+#[derive(Clone, Debug)]
+pub struct LogView {
+    // how many lines up from the end the bottom of the pane is, 0 keeps showing the newest lines
+    pub offset: usize,
+    // following, new lines scroll into view, otherwise the pane stays on the lines it's showing
+    pub follow: bool,
+    // how many lines there had ever been last time we looked, to tell how far new ones would push things along
+    seen: usize,
+    // and how many of those had been dropped from the front, which moves every line's index
+    dropped: usize,
+    pub query: Option<String>,
+    // the search being typed after '/', it takes every key until enter or esc
+    pub typing: Option<String>,
+    // the line of the match we last jumped to
+    pub current_match: Option<usize>,
+}
+
+impl Default for LogView {
+    fn default() -> Self {
+        LogView {
+            offset: 0,
+            follow: true,
+            seen: 0,
+            dropped: 0,
+            query: None,
+            typing: None,
+            current_match: None,
+        }
+    }
+}
+
+impl LogView {
+    // keep up with the log having grown to `len` lines, after `dropped` more have gone from the front
+    pub fn update(&mut self, len: usize, dropped: usize) {
+        let total = len + dropped;
+        let added = total.saturating_sub(self.seen);
+        self.seen = total;
+        let newly_dropped = dropped.saturating_sub(self.dropped);
+        self.dropped = dropped;
+        self.current_match = self
+            .current_match
+            .and_then(|line| line.checked_sub(newly_dropped));
+        if self.follow {
+            self.offset = 0;
+        } else {
+            self.offset = (self.offset + added).min(len.saturating_sub(1));
+        }
+    }
+
+    // whether the key was for us, the rest (esc, q, ...) are left to the view
+    pub fn on_key(&mut self, key: KeyCode, lines: &[String]) -> bool {
+        if let Some(typing) = &mut self.typing {
+            match key {
+                KeyCode::Enter => {
+                    let query = self.typing.take().unwrap_or_default();
+                    self.query = Some(query).filter(|query| !query.is_empty());
+                    self.current_match = None;
+                    self.jump(lines, Jump::Nearest);
+                }
+                KeyCode::Esc => self.typing = None,
+                KeyCode::Backspace => {
+                    typing.pop();
+                }
+                KeyCode::Char(c) => typing.push(c),
+                _ => {}
+            }
+            return true;
+        }
+
+        let last = lines.len().saturating_sub(1);
+        match key {
+            KeyCode::Char('/') => self.typing = Some(String::new()),
+            KeyCode::Char('n') if self.query.is_some() => self.jump(lines, Jump::Next),
+            KeyCode::Char('N') if self.query.is_some() => self.jump(lines, Jump::Previous),
+            KeyCode::Char('f') => {
+                self.follow = !self.follow;
+                if self.follow {
+                    self.offset = 0;
+                }
+            }
+            // going back up to read something means stopping following, or the next lines would drag us away again
+            KeyCode::Up => self.scroll_to((self.offset + 1).min(last)),
+            KeyCode::Down => self.scroll_to(self.offset.saturating_sub(1)),
+            KeyCode::PageUp => self.scroll_to((self.offset + PAGE_LINES).min(last)),
+            KeyCode::PageDown => self.scroll_to(self.offset.saturating_sub(PAGE_LINES)),
+            KeyCode::Home => self.scroll_to(last),
+            KeyCode::End => self.scroll_to(0),
+            _ => return false,
+        }
+        true
+    }
+
+    fn scroll_to(&mut self, offset: usize) {
+        self.offset = offset;
+        if offset > 0 {
+            self.follow = false;
+        }
+    }
+
+    // the lines containing the query, ignoring case
+    pub fn matches(&self, lines: &[String]) -> Vec<usize> {
+        let query = match &self.query {
+            Some(query) => query.to_ascii_lowercase(),
+            None => return vec![],
+        };
+        lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.to_ascii_lowercase().contains(&query))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    // move the match to the bottom of the pane, since that's where the latest output is read from anyway
+    fn jump(&mut self, lines: &[String], jump: Jump) {
+        let matches = self.matches(lines);
+        if matches.is_empty() {
+            self.current_match = None;
+            return;
+        }
+        let bottom = lines.len().saturating_sub(1 + self.offset);
+        let target = match (jump, self.current_match) {
+            // the closest match at or above what's on screen, builds tend to fail near the end
+            (Jump::Nearest, _) | (_, None) => matches
+                .iter()
+                .rev()
+                .find(|line| **line <= bottom)
+                .unwrap_or(&matches[0]),
+            // both wrap around at the ends
+            (Jump::Next, Some(current)) => matches
+                .iter()
+                .find(|line| **line > current)
+                .unwrap_or(&matches[0]),
+            (Jump::Previous, Some(current)) => matches
+                .iter()
+                .rev()
+                .find(|line| **line < current)
+                .unwrap_or(&matches[matches.len() - 1]),
+        };
+        self.current_match = Some(*target);
+        self.scroll_to(lines.len().saturating_sub(1 + target));
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Jump {
+    Nearest,
+    Next,
+    Previous,
+}
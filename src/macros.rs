@@ -0,0 +1,92 @@
+use crate::config::MacroConfig;
+use crossterm::event::KeyCode;
+
+// one key standing for a run of steps from the config, e.g. M for opening the prod pipeline's failed action's
+// build log
+#[derive(Clone, Debug, PartialEq)]
+pub struct Macro {
+    pub key: KeyCode,
+    // what it's called in the notifications, its key if it hasn't been given a name
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    // as if it had been pressed
+    Key(KeyCode),
+    // "pipeline payments-prod", opening it whichever view we're in
+    Pipeline(String),
+    // "failed", the open pipeline's failed action, the first one down the stages
+    Failed,
+}
+
+// the config's macros, along with anything wrong with them for warning about at startup. A macro with a step
+// we don't understand is left out altogether rather than half run
+pub fn parse(config: &[MacroConfig]) -> (Vec<Macro>, Vec<String>) {
+    let mut macros = vec![];
+    let mut problems = vec![];
+    for entry in config {
+        let key = match parse_key(&entry.key) {
+            Some(key) => key,
+            None => {
+                problems.push(format!("The macro on {} isn't on a key we know", entry.key));
+                continue;
+            }
+        };
+        let steps = entry
+            .steps
+            .iter()
+            .map(|step| parse_step(step).ok_or(step))
+            .collect::<Result<Vec<_>, _>>();
+        match steps {
+            Ok(steps) => macros.push(Macro {
+                key,
+                name: entry.name.clone().unwrap_or_else(|| entry.key.clone()),
+                steps,
+            }),
+            Err(step) => problems.push(format!(
+                "The macro on {} has a step \"{}\" that isn't a key, \"pipeline NAME\" or \"failed\"",
+                entry.key, step
+            )),
+        }
+    }
+    (macros, problems)
+}
+
+fn parse_step(step: &str) -> Option<Step> {
+    if let Some(name) = step.strip_prefix("pipeline ") {
+        return Some(Step::Pipeline(name.trim().to_owned()));
+    }
+    if step == "failed" {
+        return Some(Step::Failed);
+    }
+    parse_key(step).map(Step::Key)
+}
+
+// a single character as itself, "F5", or one of the named keys: "enter", "esc", "tab", "space", the arrows...
+pub fn parse_key(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    if let Some(number) = key.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+        return Some(KeyCode::F(number)).filter(|_| (1..=12).contains(&number));
+    }
+    Some(match key.to_lowercase().as_str() {
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        _ => return None,
+    })
+}
@@ -1,189 +1,1928 @@
-extern crate pretty_env_logger;
 #[macro_use]
 extern crate log;
 
-use rusoto_codepipeline::{
-    CodePipeline, CodePipelineClient, GetPipelineStateInput, ListPipelinesInput, StageExecution,
-    StageState,
+use codepipeline_status::{
+    alarms, app, audit, aws, cache, cli, config, console, credentials, demo, deploy, git,
+    guardrails, history, infer, input, list, logging, macros, notify, partition, poller,
+    provenance, stats, theme, ui, update, wait, watchlist,
 };
-use rusoto_core::credential::ProfileProvider;
-use rusoto_core::{HttpClient, Region};
 
-use std::env::{set_var, var};
+use alarms::AlarmSource;
+use app::{App, BuildLog, Command, Detail, Modal, RollbackPlan, ToastLevel, View};
+use arboard::Clipboard;
+use audit::{failure, Action, AuditLog};
+use aws::{Clients, ExecutionMode, PipelineListing};
+use cache::{Api, Cache};
+use chrono::Local;
+use cli::{ConfigCommand, Opt, Subcommand};
+use config::{Config, LogConfig, Pane};
+use crossterm::event::Event;
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::tty::IsTty;
+use git::CommitResolver;
+use guardrails::guarded;
+use history::History;
+use notify::Notifier;
+use poller::{Supervisor, Update};
+use rusoto_codepipeline::StageState;
+
+use std::collections::{HashMap, VecDeque};
+use std::env::set_var;
 use std::error::Error;
-use std::io;
+use std::future::Future;
+use std::io::{self, Write};
+use std::iter;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+use theme::CustomTheme;
+use tokio::time::delay_for;
 use tui::backend::CrosstermBackend;
-use tui::layout::{Constraint, Direction, Layout};
-use tui::style::{Color, Modifier, Style};
-use tui::text::Span;
-use tui::widgets::BorderType;
-use tui::widgets::{Block, Borders};
+use tui::layout::Rect;
 use tui::Terminal;
+use watchlist::Watchlist;
+
+// timers only show whole seconds, so there's no point redrawing them any more often than this
+const ANIMATION_INTERVAL: Duration = Duration::from_secs(1);
+// the longest the main loop sleeps with nothing happening, it still has our own log lines to pick up
+const IDLE_WAIT: Duration = Duration::from_millis(250);
+// how often the fleet view looks over every pipeline again, it's up on a wall with nobody to press refresh
+const FLEET_INTERVAL: Duration = Duration::from_secs(60);
+// alarms change whether or not the pipeline does, so they're looked at on their own schedule
+const ALARM_INTERVAL: Duration = Duration::from_secs(60);
+
+// what the commands need to talk to the outside world
+struct Context {
+    clients: Clients,
+    config: Config,
+    // depends on the pipeline's source actions, so it's rebuilt whenever a pipeline is opened
+    resolver: Option<CommitResolver>,
+    // opened the first time something's copied, and kept around since on X11 the copy goes when it does
+    clipboard: Option<Clipboard>,
+    cache: Cache,
+    // turned off in the config, or we couldn't open it, in which case the statistics make do with recent runs
+    history: Option<History>,
+    // polls the open pipeline in the background, and on the fleet view every listed one, every update comes
+    // back through it
+    poller: Supervisor,
+    // the open pipeline's alarms, if it has any
+    alarm_source: Option<AlarmSource>,
+    audit: AuditLog,
+    notifier: Notifier,
+    // asked for by a command, but it has to wait for the main loop, which has the terminal
+    reauthenticate: bool,
+}
+
+// how the wait went as the exit code, or ERROR_EXIT_CODE with the error
+fn exit_waiting(outcome: Result<wait::Outcome, Box<dyn Error + Send + Sync>>) -> ! {
+    match outcome {
+        Ok(outcome) => std::process::exit(outcome.exit_code()),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(wait::ERROR_EXIT_CODE)
+        }
+    }
+}
 
 #[tokio::main]
 // dyn Error: anything that has the Error trait
 // Box<dyn Error>: accept anything with the Error trait and put it on the heap instead of the stack
-async fn main() -> Result<(), Box<dyn Error>> {
+// + Send + Sync: the error can be handed between threads, which the tokio side of things needs
+async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     // RUST_LOG=info would make all our dependencies spit out their logs
     // we don't need to see our imported dependencies' logs, so here we configure our logger to use a custom environment variable instead of RUST_LOG
     set_var("LOCAL_LOGGING", "info");
-    pretty_env_logger::try_init_timed_custom_env("LOCAL_LOGGING")?;
-
-    // access credentials through a hardcoded AWS profile named "cdk"
-    let credentials_dir = var("HOME")? + "/.aws/credentials";
-    let profile_provider = ProfileProvider::with_configuration(credentials_dir, "cdk");
-    let http_client = HttpClient::new()?;
-    let codepipeline_client =
-        CodePipelineClient::new_with(http_client, profile_provider, Region::UsWest2);
-
-    info!("Getting pipelines list...");
-    let pipelines_list_res = codepipeline_client
-        .list_pipelines(ListPipelinesInput { next_token: None }) // we shouldn't have so many pipelines that a token is necessary
-        .await?;
-    info!("Successfully listed pipelines.");
-
-    // find the appropriate pipeline by picking the first one with a correct-looking name for now
-    let pipelines_list = pipelines_list_res.pipelines.ok_or("No pipelines!")?;
-    let dpbuilder_pipeline = pipelines_list
-        .into_iter()
-        .find(|pipeline| match &pipeline.name {
-            Some(name) => name.find("DavidTestStack").is_some(),
-            None => false,
-        })
-        .ok_or_else(|| "Couldn't find the DavidTestStack pipeline!")?;
+    // like pretty_env_logger's own, but it keeps every line for the log view too
+    logging::init("LOCAL_LOGGING")?;
 
-    let pipeline_name = dpbuilder_pipeline.name.unwrap();
-    let get_pipeline_input = GetPipelineStateInput {
-        name: pipeline_name.clone(),
+    let opt = Opt::from_args();
+    // nothing to do with AWS, or with whatever the config says
+    match &opt.command {
+        Some(Subcommand::SelfUpdate { check }) => return update::self_update(*check).await,
+        Some(Subcommand::Config(ConfigCommand::Export { file })) => {
+            return Config::export(file.as_deref())
+        }
+        Some(Subcommand::Config(ConfigCommand::Import { file })) => return Config::import(file),
+        _ => {}
+    }
+    let mut config = Config::load()?;
+    let seed = opt.seed.unwrap_or_else(demo::random_seed);
+    let clients = if opt.demo {
+        info!("Demo data from --seed {}.", seed);
+        // made-up runs have no business in the real history or audit log
+        config.history.enabled = false;
+        config.audit.enabled = false;
+        // nor in anyone's Slack
+        config.notifications.rules.clear();
+        Clients::local(demo::serve(seed, opt.region.name()).await?)?
+    } else {
+        Clients::new(&opt.profile, opt.region.clone(), config.endpoints)?
     };
+    let mut audit = AuditLog::new(&config.audit);
+    // a typo in it should stop us before anything's listed, not leave the dashboard empty
+    let watchlist = opt.watchlist.as_deref().map(Watchlist::load).transpose()?;
 
-    info!("Getting info for pipeline {}...", pipeline_name);
-    let dpbuilder_pipeline_info = codepipeline_client
-        .get_pipeline_state(get_pipeline_input)
-        .await?;
-    info!("Successfully got info for pipeline {}.", pipeline_name);
+    // the subcommands are for scripts, there's no dashboard to set up unless trigger ends up on it
+    let mut pipeline = opt.pipeline.clone();
+    let mut execution_id = opt.execution_id.clone();
+    // the subcommands name their pipeline themselves
+    if let (Some(stack), None) = (&opt.stack, &opt.command) {
+        let name = aws::pipeline_for_stack(&clients, stack).await?;
+        info!("Stack {} has pipeline {}.", stack, name);
+        pipeline = Some(name);
+    }
+    // run bare inside a service's repository, it's that service's pipeline we're after
+    // a watchlist is asking for the pipeline list
+    if opt.command.is_none()
+        && pipeline.is_none()
+        && !opt.no_infer
+        && !opt.demo
+        && watchlist.is_none()
+    {
+        pipeline = infer::infer_pipeline(&clients).await;
+        if let Some(name) = &pipeline {
+            info!("Guessed pipeline {} from the repository.", name);
+        }
+    }
+    if opt.command.is_none() && pipeline.is_none() {
+        if opt.execution_id.is_some() {
+            return Err("--execution-id needs --pipeline or --stack".into());
+        }
+        if opt.linear {
+            return Err("--linear needs --pipeline or --stack".into());
+        }
+    }
+    let read_only = opt.read_only || config.read_only;
+    if let (true, Some(command)) = (read_only, &opt.command) {
+        let name = match command {
+            Subcommand::Trigger { .. } => Some("trigger"),
+            Subcommand::Approve { .. } => Some("approve"),
+            Subcommand::Reject { .. } => Some("reject"),
+            Subcommand::Retry { .. } => Some("retry"),
+            Subcommand::Stop { .. } => Some("stop"),
+            Subcommand::Wait { .. }
+            | Subcommand::List { .. }
+            | Subcommand::SelfUpdate { .. }
+            | Subcommand::Config(_) => None,
+        };
+        if let Some(name) = name {
+            return Err(format!("{} changes the pipeline, which read-only turns off", name).into());
+        }
+    }
+    match &opt.command {
+        Some(Subcommand::Wait {
+            pipeline,
+            execution_id,
+            timeout,
+        }) => {
+            exit_waiting(wait::wait(&clients, pipeline, execution_id.clone(), *timeout).await);
+        }
+        Some(Subcommand::Trigger {
+            pipeline: name,
+            variables,
+            wait,
+            timeout,
+        }) => {
+            confirm(&config, &opt, name, "start", name)?;
+            let start = aws::start_execution(&clients, name, variables);
+            let result = guarded(&clients, &config.guardrails, name, "start", start).await;
+            let action = Action {
+                action: "start",
+                pipeline: name.clone(),
+                execution_id: result.as_ref().ok().cloned(),
+                detail: describe_variables(variables),
+                ..Action::default()
+            };
+            audit.record(&clients, action, failure(&result)).await;
+            let id = match result {
+                Ok(id) => id,
+                // not started is as good as not knowing how it went, for a script waiting on it
+                Err(e) if *wait => exit_waiting(Err(e)),
+                Err(e) => return Err(e),
+            };
+            println!("Started {} execution {}", name, id);
+            if *wait {
+                exit_waiting(wait::wait(&clients, name, Some(id), *timeout).await);
+            }
+            // pinned, so a newer execution starting while we watch doesn't take over
+            pipeline = Some(name.clone());
+            execution_id = Some(id);
+        }
+        Some(Subcommand::List { output }) => {
+            return list::list(&clients, &opt.tags, watchlist.as_ref(), *output).await;
+        }
+        Some(Subcommand::Approve {
+            pipeline,
+            stage,
+            action,
+            comment,
+        }) => {
+            let comment = comment
+                .as_deref()
+                .unwrap_or("Approved from codepipeline-status");
+            confirm(&config, &opt, pipeline, "approve", stage)?;
+            let approve = aws::submit_approval(&clients, pipeline, stage, action, true, comment);
+            let result = guarded(&clients, &config.guardrails, pipeline, "approve", approve).await;
+            let entry = Action {
+                action: "approve",
+                pipeline: pipeline.clone(),
+                stage: Some(stage.clone()),
+                execution_id: result.as_ref().ok().cloned().flatten(),
+                detail: Some(format!("{}: {}", action, comment)),
+            };
+            audit.record(&clients, entry, failure(&result)).await;
+            result?;
+            println!("Approved {} in {} {}", action, pipeline, stage);
+            return Ok(());
+        }
+        Some(Subcommand::Reject {
+            pipeline,
+            stage,
+            action,
+            comment,
+        }) => {
+            let comment = comment
+                .as_deref()
+                .unwrap_or("Rejected from codepipeline-status");
+            confirm(&config, &opt, pipeline, "reject", stage)?;
+            let reject = aws::submit_approval(&clients, pipeline, stage, action, false, comment);
+            let result = guarded(&clients, &config.guardrails, pipeline, "reject", reject).await;
+            let entry = Action {
+                action: "reject",
+                pipeline: pipeline.clone(),
+                stage: Some(stage.clone()),
+                execution_id: result.as_ref().ok().cloned().flatten(),
+                detail: Some(format!("{}: {}", action, comment)),
+            };
+            audit.record(&clients, entry, failure(&result)).await;
+            result?;
+            println!("Rejected {} in {} {}", action, pipeline, stage);
+            return Ok(());
+        }
+        Some(Subcommand::Retry {
+            pipeline,
+            stage,
+            all_actions,
+        }) => {
+            confirm(&config, &opt, pipeline, "retry", stage)?;
+            let retry = aws::retry_stage(&clients, pipeline, stage, *all_actions);
+            let result = guarded(&clients, &config.guardrails, pipeline, "retry", retry).await;
+            let action = Action {
+                action: "retry",
+                pipeline: pipeline.clone(),
+                stage: Some(stage.clone()),
+                execution_id: result.as_ref().ok().cloned(),
+                detail: Some(
+                    if *all_actions {
+                        "all actions"
+                    } else {
+                        "failed actions"
+                    }
+                    .to_owned(),
+                ),
+            };
+            audit.record(&clients, action, failure(&result)).await;
+            let id = result?;
+            println!("Retrying {} in {} execution {}", stage, pipeline, id);
+            return Ok(());
+        }
+        Some(Subcommand::Stop {
+            pipeline,
+            execution_id,
+            abandon,
+            reason,
+        }) => {
+            let id = match execution_id {
+                Some(id) => id.clone(),
+                None => aws::fetch_executions(&clients, pipeline)
+                    .await?
+                    .into_iter()
+                    .find(|execution| execution.status.as_deref() == Some("InProgress"))
+                    .and_then(|execution| execution.pipeline_execution_id)
+                    .ok_or_else(|| format!("{} has nothing running", pipeline))?,
+            };
+            let kind = if *abandon { "abandon" } else { "stop" };
+            confirm(&config, &opt, pipeline, kind, pipeline)?;
+            let stop = aws::stop_execution(&clients, pipeline, &id, *abandon, reason.clone());
+            let result = guarded(&clients, &config.guardrails, pipeline, kind, stop).await;
+            let action = Action {
+                action: kind,
+                pipeline: pipeline.clone(),
+                execution_id: Some(id.clone()),
+                detail: reason.clone(),
+                ..Action::default()
+            };
+            audit.record(&clients, action, failure(&result)).await;
+            result?;
+            let how = if *abandon { "Abandoned" } else { "Stopping" };
+            println!("{} {} execution {}", how, pipeline, id);
+            return Ok(());
+        }
+        // taken care of before anything else
+        Some(Subcommand::SelfUpdate { .. }) | Some(Subcommand::Config(_)) | None => {}
+    }
 
-    let stage_states = dpbuilder_pipeline_info.stage_states.unwrap();
-
-    // Make a local clone here so we can inspect and log the states with impunity
-    stage_states
+    let mut app = App::new(String::new(), opt.region.name().to_owned(), vec![]);
+    app.tag_filter = opt.tags.clone();
+    app.watchlist = watchlist;
+    app.read_only = read_only;
+    if opt.demo {
+        app.toast(ToastLevel::Info, format!("Demo data from --seed {}", seed));
+    }
+    app.confirm = config.confirm.clone();
+    app.sla = config.sla.clone();
+    app.stage_filter = config.stages.clone();
+    app.reauth = config
+        .credentials
+        .reauth
         .clone()
-        .into_iter()
-        .for_each(|elem| match elem {
-            StageState {
-                latest_execution: Some(execution),
-                stage_name: Some(name),
-                ..
-            } => info!("Stage: {} has status: {}", name, execution.status),
-            _ => error!("Could not inspect stage: {:?}", elem),
-        });
-
-    let stdout = io::stdout();
+        .unwrap_or_else(|| format!("aws sso login --profile {}", opt.profile));
+    app.stage_filter
+        .hide
+        .extend(opt.hide_stages.iter().cloned());
+    if !opt.only_stages.is_empty() {
+        app.stage_filter.only = opt.only_stages.clone();
+    }
+    let (macros, problems) = macros::parse(&config.macros);
+    for problem in problems {
+        warn!("{}", problem);
+    }
+    app.macros = macros;
+    app.calls = clients.calls.clone();
+    app.toast_duration = Duration::from_secs(config.toasts.seconds);
+    app.layout = config.layout.panes.clone();
+    app.themes
+        .extend(config.theme.custom.iter().map(CustomTheme::theme));
+    let theme_name = if opt.high_contrast {
+        "high-contrast"
+    } else {
+        config.theme.name.as_str()
+    };
+    match app.themes.iter().position(|theme| theme.name == theme_name) {
+        Some(index) => app.selected_theme = index,
+        None => warn!("There's no theme called {}, using dark", theme_name),
+    }
+    let mut context = Context {
+        poller: Supervisor::new(clients.clone(), config.polling.clone()),
+        notifier: Notifier::new(
+            config.notifications.clone(),
+            clients.http.clone(),
+            opt.region.name().to_owned(),
+        ),
+        clients,
+        config,
+        resolver: None,
+        clipboard: None,
+        cache: Cache::default(),
+        history: None,
+        alarm_source: None,
+        audit,
+        reauthenticate: false,
+    };
+    if context.config.history.enabled {
+        match History::open(context.config.history.days) {
+            Ok(history) => {
+                app.stats_days = Some(context.config.history.days);
+                context.history = Some(history);
+            }
+            Err(e) => warn!("Couldn't open the history database: {}", e),
+        }
+    }
+    // for the history view's stars
+    load_bookmarks(&context, &mut app);
+
+    // --linear has nothing to show in the meantime, so it gets everything first, and a bad name is worth
+    // stopping for before printing anything
+    if let (true, Some(pipeline_name)) = (opt.linear, &pipeline) {
+        info!("Getting info for pipeline {}...", pipeline_name);
+        open_pipeline(&mut context, &mut app, pipeline_name).await?;
+        app.pinned_execution = execution_id.clone();
+        after_open(&mut context, &mut app).await;
+        info!("Successfully got info for pipeline {}.", pipeline_name);
+
+        // Make a local clone here so we can inspect and log the states with impunity
+        app.pipeline
+            .stage_states
+            .clone()
+            .into_iter()
+            .for_each(|elem| match elem.state {
+                StageState {
+                    latest_execution: Some(execution),
+                    stage_name: Some(name),
+                    ..
+                } => info!("Stage: {} has status: {}", name, execution.status),
+                _ => error!("Could not inspect stage: {:?}", elem),
+            });
+        return run_linear(&mut context, app).await;
+    }
+
+    // everything else waits for the first frame, so a slow sign-in (SSO, say) isn't seconds of blank terminal
+    // with --pipeline the selector is skipped, and the pipeline comes before the rest of the selector
+    let mut startup = VecDeque::from(vec![Command::LoadPipelines]);
+    match (&pipeline, &execution_id) {
+        (Some(pipeline), Some(execution_id)) => startup.push_back(Command::Follow {
+            pipeline: pipeline.clone(),
+            execution_id: execution_id.clone(),
+        }),
+        (Some(pipeline), None) => startup.push_back(Command::OpenPipeline(pipeline.clone())),
+        _ => {}
+    }
+    startup.push_back(Command::LoadListings);
+    app.loading = true;
+    if opt.fleet {
+        app.view = View::Fleet;
+    }
+
+    // raw mode hands us every keypress, the alternate screen keeps the user's scrollback intact
+    // from here on the log only goes to the log view, stderr would end up all over the screen
+    logging::set_stderr(false);
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
-    terminal.draw(|f| {
-        let titles = ["Stages", "Commits"];
-        let sections = titles
-            .iter()
-            .zip(
-                // "zip" to match each title with a Rect
-                Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(1)
-                    .constraints(
-                        // generate a constraint for each title
-                        // they all have the same constraint in this case (they take up 1/titles.len() of the available space)
-                        (0..titles.len())
-                            .map(|_| Constraint::Ratio(1, titles.len() as u32))
-                            .collect::<Vec<_>>()
-                            .as_ref(),
-                    )
-                    // the available space for this layout is the full area of the terminal `f`
-                    .split(f.size()),
-            )
-            // do an effectful "inspect" here to render each chunk of the layout
-            .inspect(|(title, chunk)| {
-                f.render_widget(
-                    Block::default()
-                        .title(Span {
-                            content: title.to_string().into(),
-                            style: Style::default().add_modifier(Modifier::BOLD),
-                        })
-                        .border_type(BorderType::Thick)
-                        .border_style(Style::default().fg(Color::Rgb(255, 178, 102)))
-                        .borders(Borders::ALL),
-                    *chunk,
-                )
-            })
-            // we don't need the titles anymore, so discard them
-            .map(|(_, chunk)| chunk)
-            .collect::<Vec<_>>();
+    let result = run(&mut terminal, &mut context, app, startup).await;
 
-        stage_states
-            .iter()
-            .zip(
-                // each stage will get a Rect
-                Layout::default()
-                    // fill up the space from left to right
-                    .direction(Direction::Horizontal)
-                    .margin(1)
-                    .constraints(
-                        // as above, each Rect will take up a fraction of the space equal to 1/len
-                        (0..stage_states.len())
-                            .map(|_| Constraint::Ratio(1, stage_states.len() as u32))
-                            .collect::<Vec<_>>()
-                            .as_ref(),
-                    )
-                    // the space we're filling up is the first section (the "Stages" chunk) instead of the entire terminal window
-                    .split(*sections.get(0).unwrap()),
-            )
-            // render each stage
-            .for_each(|(state, chunk)| {
-                f.render_widget(
-                    Block::default()
-                        .title(Span {
-                            content: state.clone().stage_name.unwrap().into(),
-                            style: Style::default().add_modifier(Modifier::BOLD),
+    // put the terminal back the way we found it, even if the loop blew up
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    logging::set_stderr(true);
+
+    result
+}
+
+async fn run(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    context: &mut Context,
+    mut app: App,
+    mut startup: VecDeque<Command>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // a full redraw every tick flickers over a slow SSH link, so only draw once something has actually changed,
+    // or once a second while there are timers on screen
+    let mut dirty = true;
+    let mut last_draw = Instant::now();
+    let mut animating = false;
+    let mut calls_seen = 0;
+    let paused = Arc::new(AtomicBool::new(false));
+    let mut input = input::spawn(paused.clone());
+    loop {
+        if let Some(resolver) = &context.resolver {
+            let resolved = app.commits.len();
+            resolve_commits(resolver, &mut app).await;
+            dirty |= app.commits.len() != resolved;
+        }
+        let new_lines = logging::lines_since(app.app_log.len());
+        dirty |= !new_lines.is_empty();
+        app.app_log.extend(new_lines);
+        dirty |= check_slas(context, &mut app);
+        dirty |= app.check_credentials();
+        if context.reauthenticate {
+            context.reauthenticate = false;
+            reauthenticate(terminal, context, &mut app, &paused).await?;
+            dirty = true;
+        }
+        context.notifier.tick();
+        // the inspector moves on with every call, whichever part of us made it
+        let calls = app.calls.total();
+        dirty |= app.view == View::Inspector && calls != calls_seen;
+        calls_seen = calls;
+        app.update_log_view();
+
+        // whatever was animating last frame gets one more, so an expired toast doesn't linger
+        if dirty || (animating && last_draw.elapsed() >= ANIMATION_INTERVAL) {
+            terminal.draw(|f| ui::draw(f, &app))?;
+            app.fleet_columns = ui::fleet_columns(terminal.size()?.width);
+            dirty = false;
+            last_draw = Instant::now();
+            animating = app.animating();
+        }
+
+        // the startup steps go one at a time with a frame after each, any keys wait until they're done
+        if let Some(command) = startup.pop_front() {
+            run_command(context, &mut app, command).await;
+            dirty = true;
+            continue;
+        }
+        // a macro's steps too, each one's command finishing before the next step, so "pipeline X" then "failed"
+        // picks from X's stages
+        if !app.macro_steps.is_empty() {
+            let error = app.last_error.clone();
+            if let Some(command) = app.next_macro_step() {
+                run_command(context, &mut app, command).await;
+            }
+            // the steps after one that went wrong would only be pressing keys on the wrong thing
+            if app.last_error.is_some() && app.last_error != error {
+                app.stop_macro("a step went wrong");
+            }
+            if app.should_quit {
+                return Ok(());
+            }
+            dirty = true;
+            continue;
+        }
+        // the pipelines themselves are kept up to date by their own tasks, this only finds those that have
+        // come or gone, in the background like the polls
+        if app.view == View::Fleet
+            && app
+                .listed_at
+                .is_some_and(|at| at.elapsed() >= FLEET_INTERVAL)
+        {
+            run_command(context, &mut app, Command::RefreshFleet).await;
+        }
+        if context.alarm_source.is_some()
+            && app
+                .alarms_at
+                .is_none_or(|at| at.elapsed() >= ALARM_INTERVAL)
+        {
+            refresh_alarms(context, &mut app).await;
+            dirty = true;
+            continue;
+        }
+
+        // sleep until there's a key, an update or a frame of animation due, whichever comes first, none of
+        // them waits on the others
+        let mut wait = IDLE_WAIT;
+        if animating {
+            wait = wait.min(
+                ANIMATION_INTERVAL
+                    .checked_sub(last_draw.elapsed())
+                    .unwrap_or_default(),
+            );
+        }
+        let wakeup = tokio::select! {
+            event = input.recv() => Wakeup::Input(event.ok_or("Lost the terminal")?),
+            update = context.poller.recv() => Wakeup::Updates(update.into_iter().collect()),
+            _ = delay_for(wait) => Wakeup::Tick,
+        };
+
+        let mut updates = vec![];
+        match wakeup {
+            Wakeup::Input(event) => {
+                let mut events = vec![event];
+                while let Ok(event) = input.try_recv() {
+                    events.push(event);
+                }
+                let batch = input::coalesce(events);
+                for key in batch.keys {
+                    dirty = true;
+                    if let Some(command) = app.on_key(key) {
+                        run_command(context, &mut app, command).await;
+                    }
+                    if app.should_quit {
+                        return Ok(());
+                    }
+                }
+                // the commits pane may have come back into view since the last poll
+                load_execution_variables(context, &mut app).await;
+                // lay everything out again for the new size and draw it straight away, rather than leaving
+                // a half-redrawn frame up until the next keypress or tick
+                if let Some((width, height)) = batch.resize {
+                    terminal.resize(Rect::new(0, 0, width, height))?;
+                    dirty = true;
+                }
+            }
+            Wakeup::Updates(first) => updates = first,
+            Wakeup::Tick => {}
+        }
+        if let Some(command) = app.stage_actions_command() {
+            run_command(context, &mut app, command).await;
+            dirty = true;
+        }
+
+        context
+            .poller
+            .set_executions(&app.pipeline.name, app.executions_needed());
+        // the fleet's pipelines are polled like the open one, each at its own [polling] interval within the
+        // supervisor's cap on requests, a new one starting as soon as it's listed
+        if app.view == View::Fleet {
+            for listing in &app.pipelines {
+                context.poller.watch_listing(listing);
+            }
+        }
+        while let Some(listed) = context.poller.try_listed() {
+            take_listed(context, &mut app, listed);
+            dirty = true;
+        }
+        updates.extend(iter::from_fn(|| context.poller.try_recv()));
+        for update in updates {
+            // a fleet cell may have changed even when the open pipeline hasn't
+            dirty = true;
+            if !apply(context, &mut app, update).await {
+                continue;
+            }
+            // the running actions' durations have moved on, so fetch them again next time round
+            app.stage_actions = None;
+            // keep whatever detail view is open up to date too
+            let command = match app.view {
+                View::Action => app.detail_command(),
+                View::Stats => Some(Command::LoadStats),
+                // pick up whatever the build has written since
+                View::BuildLog => app.build_log_command(),
+                _ => None,
+            };
+            if let Some(command) = command {
+                run_command(context, &mut app, command).await;
+            }
+        }
+    }
+}
+
+// what woke the main loop up
+enum Wakeup {
+    Input(Event),
+    Updates(Vec<Update>),
+    Tick,
+}
+
+// one line per change rather than a screen that redraws in place, which is hopeless with a screen reader
+async fn run_linear(
+    context: &mut Context,
+    mut app: App,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut stdout = io::stdout();
+    if let Some(execution) = app.pipeline.executions.first() {
+        writeln!(
+            stdout,
+            "Pipeline {}: execution {} {}",
+            app.pipeline.name,
+            execution
+                .pipeline_execution_id
+                .as_deref()
+                .map_or("?", aws::short_id),
+            execution.status.as_deref().unwrap_or("Unknown")
+        )?;
+    }
+    for stage in &app.pipeline.stage_states {
+        writeln!(
+            stdout,
+            "Stage {}: {}",
+            stage.state.stage_name.as_deref().unwrap_or("?"),
+            stage
+                .state
+                .latest_execution
+                .as_ref()
+                .map_or("not run", |execution| execution.status.as_str())
+        )?;
+    }
+
+    // everything refresh notices goes into the notifications, so print those as they turn up
+    let mut printed = app.toasts.len();
+    while let Some(update) = context.poller.recv().await {
+        apply(context, &mut app, update).await;
+        for toast in &app.toasts[printed..] {
+            writeln!(stdout, "{} at {}", toast.message, toast.at.format("%H:%M"))?;
+        }
+        printed = app.toasts.len();
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+// take in what the poller found, false when it isn't the open pipeline, whose listing is all it changes
+async fn apply(context: &mut Context, app: &mut App, update: Update) -> bool {
+    if let Ok(pipeline) = &update.result {
+        follow_listing(context, app, pipeline);
+    }
+    if update.pipeline != app.pipeline.name {
+        if let Err(error) = &update.result {
+            warn!("Couldn't refresh {}: {}", update.pipeline, error);
+        }
+        return false;
+    }
+    // a failed refresh shouldn't take the whole dashboard down, keep the old data and say what happened
+    match update.result {
+        Ok(pipeline) => {
+            announce_finished(app, &pipeline);
+            let before = app.pipeline.stage_states.clone();
+            let new_executions = pipeline.executions != app.pipeline.executions;
+            app.pipeline = pipeline;
+            // going by the stages, the execution list is only fetched when a view needs it
+            if let Some((id, status)) = app.pipeline.latest_run() {
+                context.notifier.observe(&app.pipeline.name, &id, &status);
+            }
+            if new_executions {
+                record_executions(context, app);
+            }
+            app.last_error = None;
+            // signed in again some other way
+            app.credentials_expired = false;
+            // after pinning, so we're comparing the same execution's view of each stage
+            after_refresh(context, app).await;
+            note_stage_changes(app, &before);
+        }
+        Err(error) => {
+            // it'll likely keep failing the same way every refresh, once is enough to pop up. Expired
+            // credentials get a popup of their own
+            if app.last_error.as_ref() != Some(&error) && !credentials::expired(&error) {
+                app.toast(ToastLevel::Error, format!("Refresh failed: {}", error));
+            }
+            app.last_error = Some(error);
+        }
+    }
+    true
+}
+
+fn follow_listing(context: &mut Context, app: &mut App, pipeline: &aws::Pipeline) {
+    let listing = match app
+        .pipelines
+        .iter_mut()
+        .find(|listing| listing.name == pipeline.name)
+    {
+        Some(listing) => listing,
+        None => return,
+    };
+    listing.follow(&pipeline.executions);
+    // the open pipeline's are looked at along with the rest of its update
+    if pipeline.name != app.pipeline.name {
+        if let (Some(id), Some(status)) = (&listing.latest_execution_id, &listing.latest_status) {
+            context.notifier.observe(&listing.name, id, status);
+        }
+    }
+}
+
+// the fleet as it's listed now: those that have gone dropped and no longer polled, those that are new added in
+// the order they're listed, if they carry the tags
+fn take_listed(context: &mut Context, app: &mut App, listed: Result<poller::Listed, String>) {
+    let listed = match listed {
+        Ok(listed) => listed,
+        Err(e) => {
+            app.last_error = Some(e);
+            return;
+        }
+    };
+    let selected = app
+        .pipelines
+        .get(app.selected_pipeline)
+        .map(|listing| listing.name.clone());
+    let mut known = mem::take(&mut app.pipelines)
+        .into_iter()
+        .map(|listing| (listing.name.clone(), listing))
+        .collect::<HashMap<_, _>>();
+    let mut new = listed
+        .new
+        .into_iter()
+        .filter(|listing| app.matches_tags(&listing.tags))
+        .map(|listing| (listing.name.clone(), listing))
+        .collect::<HashMap<_, _>>();
+    for listing in new.values() {
+        if let (Some(id), Some(status)) = (&listing.latest_execution_id, &listing.latest_status) {
+            context.notifier.observe(&listing.name, id, status);
+        }
+    }
+    app.pipelines = listed
+        .names
+        .iter()
+        .filter_map(|name| known.remove(name).or_else(|| new.remove(name)))
+        .collect();
+    for name in known.keys() {
+        if *name != app.pipeline.name {
+            context.poller.unwatch(name);
+        }
+    }
+    app.selected_pipeline = app
+        .pipelines
+        .iter()
+        .position(|listing| Some(&listing.name) == selected.as_ref())
+        .unwrap_or(0);
+}
+
+// the sign-in command run in the terminal with the dashboard put away, since SSO prints a code to go with the
+// browser it opens and MFA asks for one, then everything fetched again with whatever credentials it left behind
+async fn reauthenticate(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    context: &mut Context,
+    app: &mut App,
+    paused: &AtomicBool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // otherwise the input thread would take keys meant for the command
+    paused.store(true, Ordering::Relaxed);
+    delay_for(input::PAUSE_LATENCY * 2).await;
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    logging::set_stderr(true);
+    println!("Signing in again with {}", app.reauth);
+    let status = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(&app.reauth)
+        .status()
+        .await;
+    logging::set_stderr(false);
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.clear()?;
+    paused.store(false, Ordering::Relaxed);
+
+    match status {
+        Ok(status) if status.success() => {
+            if let Some(credentials) = &context.clients.credentials {
+                credentials.forget().await;
+            }
+            context.audit.forget_identity();
+            app.credentials_expired = false;
+            app.last_error = None;
+            app.toast(ToastLevel::Info, "Signed in again".to_owned());
+            // the poller's been backing off all this while, it gets to go straight away
+            context.cache.clear();
+            context.poller.wake(&app.pipeline.name);
+            if matches!(app.view, View::Pipelines | View::Fleet) {
+                // they may well have expired before the list was ever loaded
+                if app.pipelines.is_empty() {
+                    run_command(context, app, Command::LoadPipelines).await;
+                }
+                run_command(context, app, Command::LoadListings).await;
+            }
+        }
+        Ok(status) => app.last_error = Some(format!("{} exited with {}", app.reauth, status)),
+        Err(e) => app.last_error = Some(format!("Couldn't run {}: {}", app.reauth, e)),
+    }
+    Ok(())
+}
+
+// a stage going over its [sla] happens with the clock rather than with a refresh, so it's checked every time
+// round, and each stage run only alerted on the once
+fn check_slas(context: &mut Context, app: &mut App) -> bool {
+    let now = Local::now().timestamp() as f64;
+    let over = app.newly_over_sla(now);
+    for (stage, execution_id, elapsed, limit) in &over {
+        app.toast(
+            ToastLevel::Error,
+            format!(
+                "{} has been running {}m, over its {}m SLA",
+                stage,
+                elapsed / 60,
+                limit / 60
+            ),
+        );
+        let pipeline = app.pipeline.name.clone();
+        context
+            .notifier
+            .overdue(&pipeline, stage, execution_id, elapsed / 60, limit / 60);
+    }
+    !over.is_empty()
+}
+
+// every stage whose status moved on since the last refresh, for the notifications view
+fn note_stage_changes(app: &mut App, before: &[aws::StageStateV2]) {
+    let status = |stage: &aws::StageStateV2| {
+        stage
+            .state
+            .latest_execution
+            .as_ref()
+            .map(|execution| execution.status.clone())
+    };
+    let changes = app
+        .pipeline
+        .stage_states
+        .iter()
+        .filter_map(|after| {
+            let name = after.state.stage_name.clone()?;
+            let old = before
+                .iter()
+                .find(|stage| stage.state.stage_name.as_ref() == Some(&name))
+                .and_then(status);
+            let new = status(after)?;
+            if old.as_ref() == Some(&new) {
+                return None;
+            }
+            let level = match new.as_str() {
+                "Succeeded" => ToastLevel::Success,
+                "Failed" => ToastLevel::Error,
+                _ => ToastLevel::Info,
+            };
+            Some((
+                level,
+                format!(
+                    "Stage {}: {} -> {}",
+                    name,
+                    old.as_deref().unwrap_or("not run"),
+                    new
+                ),
+            ))
+        })
+        .collect::<Vec<_>>();
+    for (level, message) in changes {
+        app.note(level, message);
+    }
+}
+
+// say when the execution we were watching finishes, so nobody has to keep staring at it
+fn announce_finished(app: &mut App, pipeline: &aws::Pipeline) {
+    let (before, after) = match (app.pipeline.latest_run(), pipeline.latest_run()) {
+        (Some(before), Some(after)) => (before, after),
+        _ => return,
+    };
+    if before.0 != after.0 || before.1 != "InProgress" {
+        return;
+    }
+    let id = aws::short_id(&after.0);
+    let (level, message) = match after.1.as_str() {
+        "Succeeded" => (
+            ToastLevel::Success,
+            format!("{} is green again ({})", pipeline.name, id),
+        ),
+        "Failed" => (
+            ToastLevel::Error,
+            format!("{} failed ({})", pipeline.name, id),
+        ),
+        "Stopped" => (
+            ToastLevel::Info,
+            format!("{} {} ({})", pipeline.name, after.1.to_lowercase(), id),
+        ),
+        _ => return,
+    };
+    app.toast(level, message);
+}
+
+async fn after_open(context: &mut Context, app: &mut App) {
+    // the stage view needs the duration history to tell when a stage is overrunning
+    load_stats(context, app).await;
+    // before pinning, which would leave them nothing but the pinned execution's stages to start from
+    load_concurrent(context, app).await;
+    pin_execution(context, app).await;
+    load_stage_starts(context, app).await;
+    after_refresh(context, app).await;
+    find_alarms(context, app).await;
+    // only the pipeline on screen needs watching
+    context.poller.unwatch_all();
+    watch(context, app);
+}
+
+// with whichever execution the stages are pinned to, the poller pins every poll it makes to it as well
+fn watch(context: &mut Context, app: &App) {
+    context.poller.watch(
+        app.pipeline.clone(),
+        app.pinned_execution.clone(),
+        app.execution_mode,
+    );
+}
+
+// the poller keeps them up to date from here on, this is only for having them straight away
+async fn load_concurrent(context: &Context, app: &mut App) {
+    if app.execution_mode != ExecutionMode::Parallel {
+        return;
+    }
+    let pipeline = &app.pipeline;
+    app.pipeline.concurrent = aws::fetch_concurrent(
+        &context.clients,
+        &pipeline.name,
+        &pipeline.stage_states,
+        &pipeline.executions,
+        &pipeline.concurrent,
+        None,
+    )
+    .await;
+}
+
+// everything that depends on the pipeline state we just fetched, nothing of it another call but the variables
+// of a new execution while the commits pane is showing
+async fn after_refresh(context: &Context, app: &mut App) {
+    note_stage_starts(app);
+    load_execution_variables(context, app).await;
+}
+
+// which alarms the config and the pipeline's tags say belong to it, the main loop fetches them from there
+async fn find_alarms(context: &mut Context, app: &mut App) {
+    let config = &context.config.alarms;
+    let tags = if config.tag.is_empty() {
+        vec![]
+    } else {
+        if app.account.is_empty() {
+            match aws::account_id(&context.clients).await {
+                Ok(account) => app.account = account,
+                Err(e) => warn!("Couldn't work out the account: {}", e),
+            }
+        }
+        aws::fetch_tags(&context.clients, &app.account, &app.pipeline.name).await
+    };
+    context.alarm_source = AlarmSource::for_pipeline(config, &app.pipeline.name, &tags);
+    app.alarm_stage = context
+        .alarm_source
+        .as_ref()
+        .and_then(|source| source.stage.clone());
+}
+
+// any alarm that's gone off since last time gets a notification, the stage only shows how many there are
+async fn refresh_alarms(context: &Context, app: &mut App) {
+    let source = match &context.alarm_source {
+        Some(source) => source,
+        None => return,
+    };
+    // tried or not, it's another minute until the next try
+    app.alarms_at = Some(Instant::now());
+    match alarms::fetch_alarms(&context.clients, source).await {
+        Ok(alarms) => {
+            if let Some(previous) = &app.alarms {
+                let newly_alarming = alarms
+                    .iter()
+                    .filter(|alarm| alarm.state == "ALARM")
+                    .filter(|alarm| {
+                        !previous
+                            .iter()
+                            .any(|other| other.name == alarm.name && other.state == "ALARM")
+                    })
+                    .map(|alarm| match &alarm.reason {
+                        Some(reason) => format!("Alarm {} went off: {}", alarm.name, reason),
+                        None => format!("Alarm {} went off", alarm.name),
+                    })
+                    .collect::<Vec<_>>();
+                for message in newly_alarming {
+                    app.toast(ToastLevel::Error, message);
+                }
+            }
+            app.alarms = Some(alarms);
+        }
+        Err(e) => warn!("Couldn't get the alarms for {}: {}", app.pipeline.name, e),
+    }
+}
+
+async fn pin_execution(context: &Context, app: &mut App) {
+    let execution_id = match &app.pinned_execution {
+        Some(id) => id.clone(),
+        None => return,
+    };
+    match aws::fetch_execution_actions(&context.clients, &app.pipeline.name, &execution_id).await {
+        Ok(details) => app.pipeline.pin_to(&execution_id, &details),
+        Err(e) => app.last_error = Some(e.to_string()),
+    }
+}
+
+// look up any commits we haven't seen yet, so this only costs anything when a new revision shows up
+async fn resolve_commits(resolver: &CommitResolver, app: &mut App) {
+    for revision in app.unresolved_revisions() {
+        let info = resolver.resolve(&revision).await;
+        if let Some(id) = revision.revision_id {
+            app.commits.insert(id, info);
+        }
+    }
+}
+
+async fn load_stats(context: &Context, app: &mut App) {
+    // a stage run needs every one of its actions, so fetch plenty for the executions we cover
+    let actions = app
+        .definition
+        .stages
+        .iter()
+        .map(|stage| stage.actions.len())
+        .sum::<usize>();
+    match aws::list_action_executions(
+        &context.clients,
+        &app.pipeline.name,
+        stats::HISTORY_LENGTH * actions,
+    )
+    .await
+    {
+        Ok(details) => {
+            app.stats = Some(match &context.history {
+                Some(history) => {
+                    let observed = stats::observe(&app.definition, &details);
+                    history
+                        .record_runs(&app.pipeline.name, &observed)
+                        .and_then(|()| history.stats(&app.pipeline.name, &app.definition))
+                        .unwrap_or_else(|e| {
+                            warn!("Couldn't use the history database: {}", e);
+                            stats::compute(&app.definition, &details)
                         })
-                        .border_type(BorderType::Thick)
-                        .borders(Borders::ALL)
-                        .border_style(Style::default().fg(
-                            match state.to_owned().latest_execution {
-                                // if we can get a valid execution state, match on it
-                                Some(StageExecution { status, .. }) => match status.as_str() {
-                                    "InProgress" => Color::LightBlue,
-                                    "Failed" => Color::Red,
-                                    "Succeeded" => Color::Green,
-                                    _ => Color::LightYellow,
-                                },
-                                // default to red whenever we can't get the execution state
-                                _ => Color::Red,
-                            },
-                        )),
-                    chunk,
-                )
+                }
+                None => stats::compute(&app.definition, &details),
             });
+            record_executions(context, app);
+        }
+        Err(e) => app.last_error = Some(e.to_string()),
+    }
+}
+
+fn record_executions(context: &Context, app: &App) {
+    if let Some(history) = &context.history {
+        if let Err(e) = history.record_executions(&app.pipeline.name, &app.pipeline.executions) {
+            warn!("Couldn't record executions in the history database: {}", e);
+        }
+    }
+}
+
+// the latest execution's variable values, only looked up again once a newer execution comes along
+async fn load_execution_variables(context: &Context, app: &mut App) {
+    // pipelines that don't declare any variables never have values for them, and only the commits pane shows
+    // them
+    if app.variable_declarations.is_empty() || !app.shows_pane(Pane::Commits) {
+        return;
+    }
+    // the execution we're following, if there is one, otherwise the newest
+    let latest = match app
+        .pinned_execution
+        .clone()
+        .or_else(|| app.pipeline.latest_run().map(|(id, _)| id))
+    {
+        Some(id) => id,
+        None => return,
+    };
+    if app.execution_variables.as_ref().map(|(id, _)| id) == Some(&latest) {
+        return;
+    }
+    match aws::fetch_execution_variables(&context.clients, &app.pipeline.name, &latest).await {
+        Ok(variables) => app.execution_variables = Some((latest, variables)),
+        Err(e) => app.last_error = Some(e.to_string()),
+    }
+}
 
-        // do the same as above, but this is a structural layout that we'll use for organizing data rather than painting a diagram
-        // so no borders/fancy colors are needed
-        // also, we're putting it in a different section
-        stage_states
+// when each running stage started, so we can tell if it's taking far longer than usual, going by the state: an
+// action still running last changed when it started. Once the stage's first actions have finished that's later
+// than the stage really started, so the first one noted stands, and the stages already running when the
+// pipeline was opened were looked up properly by load_stage_starts
+fn note_stage_starts(app: &mut App) {
+    forget_stage_starts(app);
+    for stage in &app.pipeline.stage_states {
+        let (name, execution) = match (&stage.state.stage_name, &stage.state.latest_execution) {
+            (Some(name), Some(execution)) if execution.status == "InProgress" => (name, execution),
+            _ => continue,
+        };
+        let start = stage
+            .state
+            .action_states
             .iter()
-            .zip(
-                Layout::default()
-                    .direction(Direction::Horizontal)
-                    .margin(0)
-                    .constraints(
-                        (0..stage_states.len())
-                            .map(|_| Constraint::Ratio(1, stage_states.len() as u32))
-                            .collect::<Vec<_>>(),
-                    )
-                    .split(*sections.get(1).unwrap()),
-            )
-            .for_each(|(_, chunk)| f.render_widget(Block::default().borders(Borders::NONE), chunk));
-    })?;
+            .flatten()
+            .filter_map(|action| action.latest_execution.as_ref())
+            .filter(|action| action.status.as_deref() == Some("InProgress"))
+            .filter_map(|action| action.last_status_change)
+            .fold(None, |earliest: Option<f64>, start| {
+                Some(earliest.map_or(start, |earliest| earliest.min(start)))
+            });
+        if let Some(start) = start {
+            app.stage_starts
+                .entry((execution.pipeline_execution_id.clone(), name.clone()))
+                .or_insert(start);
+        }
+    }
+}
 
+// only the stages still running need a start
+fn forget_stage_starts(app: &mut App) {
+    let running = app.running_stages();
+    app.stage_starts.retain(|(execution_id, stage), _| {
+        running
+            .get(execution_id)
+            .is_some_and(|stages| stages.contains(stage))
+    });
+}
+
+// the stages already running when the pipeline's opened, from their executions' actions, a lookup per
+// execution. From then on note_stage_starts sees each one start
+async fn load_stage_starts(context: &Context, app: &mut App) {
+    forget_stage_starts(app);
+    let running = app.running_stages();
+    for (execution_id, stages) in running {
+        let stages = stages
+            .into_iter()
+            .filter(|stage| {
+                !app.stage_starts
+                    .contains_key(&(execution_id.clone(), stage.clone()))
+            })
+            .collect::<Vec<_>>();
+        if stages.is_empty() {
+            continue;
+        }
+        match aws::fetch_execution_actions(&context.clients, &app.pipeline.name, &execution_id)
+            .await
+        {
+            Ok(details) => {
+                for stage in stages {
+                    if let Some(start) = stats::stage_start(&details, &stage) {
+                        app.stage_starts
+                            .insert((execution_id.clone(), stage), start);
+                    }
+                }
+            }
+            Err(e) => app.last_error = Some(e.to_string()),
+        }
+    }
+}
+
+fn load_bookmarks(context: &Context, app: &mut App) {
+    if let Some(history) = &context.history {
+        match history.bookmarks() {
+            Ok(bookmarks) => {
+                app.selected_bookmark =
+                    app.selected_bookmark.min(bookmarks.len().saturating_sub(1));
+                app.bookmarks = bookmarks;
+            }
+            Err(e) => warn!("Couldn't read bookmarks from the history database: {}", e),
+        }
+    }
+}
+
+// everything we need to show a pipeline: its state, its definition, and a way to look up its commits
+async fn open_pipeline(
+    context: &mut Context,
+    app: &mut App,
+    name: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let pipeline = aws::fetch_pipeline(&context.clients, name).await?;
+    let definition = aws::fetch_definition(&context.clients, name).await?;
+    context.resolver =
+        Some(CommitResolver::new(&context.config, &context.clients, &definition.declaration).await);
+    app.open_pipeline(pipeline, definition);
+    if let Some(history) = &context.history {
+        match history.notes(name) {
+            Ok(notes) => app.notes = notes,
+            Err(e) => warn!("Couldn't read notes from the history database: {}", e),
+        }
+    }
+    Ok(())
+}
+
+// CodeBuild only says where the log is once the build has started, so ask about the build each time
+async fn load_build_log(
+    clients: &Clients,
+    limits: &LogConfig,
+    app: &mut App,
+    build_id: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // a different build means starting over
+    if app.build_log.as_ref().map(|log| log.build_id.as_str()) != Some(build_id) {
+        app.build_log = Some(BuildLog::new(build_id.to_owned(), limits.clone()));
+    }
+    let build = aws::fetch_build(clients, build_id).await?;
+    let (group, stream) = match build
+        .logs
+        .as_ref()
+        .and_then(|logs| Some((logs.group_name.clone()?, logs.stream_name.clone()?)))
+    {
+        Some(location) => location,
+        None => return Ok(()),
+    };
+    if let Some(log) = &mut app.build_log {
+        let (lines, next_token) =
+            aws::fetch_log_events(clients, &group, &stream, log.next_token.clone()).await?;
+        log.extend(lines);
+        log.next_token = next_token;
+    }
     Ok(())
 }
+
+fn copy(context: &mut Context, text: &str) -> Result<(), arboard::Error> {
+    if context.clipboard.is_none() {
+        context.clipboard = Some(Clipboard::new()?);
+    }
+    match &mut context.clipboard {
+        Some(clipboard) => clipboard.set_text(text.to_owned()),
+        None => Ok(()),
+    }
+}
+
+// the cached answer if it's recent enough, otherwise whatever the fetch comes back with, kept for next time
+async fn cached<T, F>(
+    cache: &mut Cache,
+    api: Api,
+    resource: String,
+    fetch: F,
+) -> Result<T, Box<dyn Error + Send + Sync>>
+where
+    T: Clone + Send + Sync + 'static,
+    F: Future<Output = Result<T, Box<dyn Error + Send + Sync>>>,
+{
+    if let Some(value) = cache.get(api, &resource) {
+        return Ok(value);
+    }
+    let value = fetch.await?;
+    cache.insert(api, resource, value.clone());
+    Ok(value)
+}
+
+// builds and stacks are only unique within a region, and cross-region actions look them up elsewhere
+fn regional_key(clients: &Clients, resource: &str) -> String {
+    format!("{}/{}", clients.region.name(), resource)
+}
+
+// the subcommands' version of the dashboard's typed confirmation: --confirm for a script, otherwise asked for on
+// the terminal, and without either a production pipeline is left alone
+fn confirm(
+    config: &Config,
+    opt: &Opt,
+    pipeline: &str,
+    action: &str,
+    expected: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if !config.confirm.needs_typing(pipeline, action) {
+        return Ok(());
+    }
+    let typed = match &opt.confirm {
+        Some(typed) => typed.clone(),
+        None if io::stdin().is_tty() => {
+            eprint!(
+                "{} is a production pipeline, type {} to {}: ",
+                pipeline, expected, action
+            );
+            io::stderr().flush()?;
+            let mut line = String::new();
+            io::stdin().read_line(&mut line)?;
+            line.trim().to_owned()
+        }
+        None => {
+            return Err(format!(
+                "{} is a production pipeline, pass --confirm {} to {} it",
+                pipeline, expected, action
+            )
+            .into())
+        }
+    };
+    if typed == expected {
+        Ok(())
+    } else {
+        Err(format!("That isn't {}, not going ahead", expected).into())
+    }
+}
+
+// a start's variables as they'd be typed on the command line, for the audit log
+fn describe_variables(variables: &[(String, String)]) -> Option<String> {
+    if variables.is_empty() {
+        return None;
+    }
+    let pairs = variables
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>();
+    Some(pairs.join(" "))
+}
+
+// approve and reject are the same call, only the answer differs
+async fn answer_approval(
+    context: &mut Context,
+    app: &mut App,
+    stage: &str,
+    action: &str,
+    approved: bool,
+    comment: &str,
+) {
+    let clients = &context.clients;
+    let kind = if approved { "approve" } else { "reject" };
+    let answer = aws::submit_approval(
+        clients,
+        &app.pipeline.name,
+        stage,
+        action,
+        approved,
+        comment,
+    );
+    let result = guarded(
+        clients,
+        &context.config.guardrails,
+        &app.pipeline.name,
+        kind,
+        answer,
+    )
+    .await;
+    let entry = Action {
+        action: kind,
+        pipeline: app.pipeline.name.clone(),
+        stage: Some(stage.to_owned()),
+        execution_id: result.as_ref().ok().cloned().flatten(),
+        detail: Some(format!("{}: {}", action, comment)),
+    };
+    context.audit.record(clients, entry, failure(&result)).await;
+    match result {
+        Ok(_) => {
+            let how = if approved { "Approved" } else { "Rejected" };
+            app.toast(ToastLevel::Info, format!("{} {} in {}", how, action, stage));
+            context.poller.wake(&app.pipeline.name)
+        }
+        Err(e) => app.last_error = Some(e.to_string()),
+    }
+}
+
+async fn run_command(context: &mut Context, app: &mut App, command: Command) {
+    let clients = &context.clients;
+    let guardrails = &context.config.guardrails;
+    // a cross-region action's build, stack or deployment only exists in the action's own region
+    let regional;
+    let action_clients = match app.selected_region().map(partition::parse_region) {
+        Some(Ok(region)) => {
+            regional = clients.in_region(region);
+            &regional
+        }
+        _ => clients,
+    };
+    // the keys are already off, this is in case anything else ever asks
+    if app.read_only && command.mutates() {
+        warn!("Read-only, not running {:?}", command);
+        return;
+    }
+    match command {
+        Command::LoadPipelines => {
+            info!("Getting pipelines list...");
+            let names = async {
+                let account = aws::account_id(clients).await?;
+                let names = aws::list_pipeline_names(clients).await?;
+                Ok::<_, Box<dyn Error + Send + Sync>>((account, names))
+            };
+            match names.await {
+                Ok((account, names)) => {
+                    info!("Successfully listed {} pipelines.", names.len());
+                    app.account = account;
+                    // left out before their listings are fetched, a watchlist is often a few of hundreds
+                    app.pipelines = names
+                        .into_iter()
+                        .filter(|name| app.watching(name))
+                        .map(PipelineListing::new)
+                        .collect();
+                }
+                Err(e) => {
+                    app.loading = false;
+                    app.last_error = Some(e.to_string());
+                }
+            }
+        }
+        Command::LoadListings => {
+            let names = app
+                .pipelines
+                .iter()
+                .map(|listing| listing.name.clone())
+                .collect::<Vec<_>>();
+            let listings =
+                aws::fetch_listings(clients, &app.account, names, context.poller.permits()).await;
+            // hang on to the selection, it may have moved while these were loading
+            let selected = app
+                .pipelines
+                .get(app.selected_pipeline)
+                .map(|listing| listing.name.clone());
+            app.pipelines = listings
+                .into_iter()
+                .filter(|listing| app.matches_tags(&listing.tags))
+                .collect();
+            app.selected_pipeline = app
+                .pipelines
+                .iter()
+                .position(|listing| Some(&listing.name) == selected.as_ref())
+                .unwrap_or(0);
+            for listing in &app.pipelines {
+                if let (Some(id), Some(status)) =
+                    (&listing.latest_execution_id, &listing.latest_status)
+                {
+                    context.notifier.observe(&listing.name, id, status);
+                }
+            }
+            app.loading = false;
+            app.listed_at = Some(Instant::now());
+            if app.pipelines.is_empty() && app.last_error.is_none() {
+                app.last_error = Some("No pipelines matched!".to_owned());
+            }
+        }
+        Command::RefreshFleet => {
+            let known = app
+                .pipelines
+                .iter()
+                .map(|listing| listing.name.clone())
+                .collect();
+            context
+                .poller
+                .relist(&app.account, known, app.watchlist.clone());
+            context.poller.wake_all();
+            app.listed_at = Some(Instant::now());
+        }
+        Command::Follow {
+            pipeline,
+            execution_id,
+        } => match open_pipeline(context, app, &pipeline).await {
+            Ok(()) => {
+                app.last_error = None;
+                app.pinned_execution = Some(execution_id);
+                after_open(context, app).await;
+            }
+            Err(e) => app.last_error = Some(e.to_string()),
+        },
+        Command::OpenPipeline(name) => match open_pipeline(context, app, &name).await {
+            Ok(()) => {
+                app.last_error = None;
+                after_open(context, app).await;
+            }
+            Err(e) => app.last_error = Some(e.to_string()),
+        },
+        Command::StartExecution(variables) => {
+            let start = aws::start_execution(clients, &app.pipeline.name, &variables);
+            let result = guarded(clients, guardrails, &app.pipeline.name, "start", start).await;
+            let action = Action {
+                action: "start",
+                pipeline: app.pipeline.name.clone(),
+                execution_id: result.as_ref().ok().cloned(),
+                detail: describe_variables(&variables),
+                ..Action::default()
+            };
+            context
+                .audit
+                .record(clients, action, failure(&result))
+                .await;
+            match result {
+                // show the new execution straight away rather than at the next poll
+                Ok(_) => {
+                    app.toast(ToastLevel::Info, format!("Started {}", app.pipeline.name));
+                    context.poller.wake(&app.pipeline.name)
+                }
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::PlanRollback(stage) => {
+            // the stage's own latest execution is no use as a target, even when it succeeded
+            let current = app
+                .pipeline
+                .stage_states
+                .iter()
+                .find(|state| state.state.stage_name.as_ref() == Some(&stage))
+                .and_then(|state| state.state.latest_execution.as_ref())
+                .map(|execution| execution.pipeline_execution_id.clone());
+            match aws::fetch_succeeded_in_stage(clients, &app.pipeline.name, &stage).await {
+                Ok(executions) => {
+                    match executions
+                        .into_iter()
+                        .find(|execution| execution.pipeline_execution_id != current)
+                    {
+                        Some(target) => {
+                            app.modal = Some(Modal::Rollback(RollbackPlan { stage, target }))
+                        }
+                        None => {
+                            app.last_error = Some(format!(
+                                "No earlier successful execution of {} to roll back to",
+                                stage
+                            ))
+                        }
+                    }
+                }
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::Rollback { stage, target } => {
+            let rollback = aws::rollback_stage(clients, &app.pipeline.name, &stage, &target);
+            let result = guarded(
+                clients,
+                guardrails,
+                &app.pipeline.name,
+                "rollback",
+                rollback,
+            )
+            .await;
+            let action = Action {
+                action: "rollback",
+                pipeline: app.pipeline.name.clone(),
+                stage: Some(stage.clone()),
+                execution_id: result.as_ref().ok().cloned(),
+                detail: Some(format!("back to {}", target)),
+            };
+            context
+                .audit
+                .record(clients, action, failure(&result))
+                .await;
+            match result {
+                Ok(_) => {
+                    app.toast(
+                        ToastLevel::Info,
+                        format!("Rolling {} back to {}", stage, aws::short_id(&target)),
+                    );
+                    context.poller.wake(&app.pipeline.name)
+                }
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::EnableTransition(stage) => {
+            let enable = aws::enable_transition(clients, &app.pipeline.name, &stage);
+            let result = guarded(
+                clients,
+                guardrails,
+                &app.pipeline.name,
+                "enable-transition",
+                enable,
+            )
+            .await;
+            let action = Action {
+                action: "enable-transition",
+                pipeline: app.pipeline.name.clone(),
+                stage: Some(stage.clone()),
+                ..Action::default()
+            };
+            context
+                .audit
+                .record(clients, action, failure(&result))
+                .await;
+            match result {
+                Ok(()) => {
+                    app.toast(
+                        ToastLevel::Info,
+                        format!("Enabled the transition into {}", stage),
+                    );
+                    context.poller.wake(&app.pipeline.name)
+                }
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::DisableTransition { stage, reason } => {
+            let disable = aws::disable_transition(clients, &app.pipeline.name, &stage, &reason);
+            let result = guarded(
+                clients,
+                guardrails,
+                &app.pipeline.name,
+                "disable-transition",
+                disable,
+            )
+            .await;
+            let action = Action {
+                action: "disable-transition",
+                pipeline: app.pipeline.name.clone(),
+                stage: Some(stage.clone()),
+                detail: Some(reason.clone()),
+                ..Action::default()
+            };
+            context
+                .audit
+                .record(clients, action, failure(&result))
+                .await;
+            match result {
+                Ok(()) => {
+                    app.toast(
+                        ToastLevel::Info,
+                        format!("Disabled the transition into {}", stage),
+                    );
+                    context.poller.wake(&app.pipeline.name)
+                }
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::Approve {
+            stage,
+            action,
+            comment,
+        } => answer_approval(context, app, &stage, &action, true, &comment).await,
+        Command::Reject {
+            stage,
+            action,
+            comment,
+        } => answer_approval(context, app, &stage, &action, false, &comment).await,
+        Command::RetryStage(stage) => {
+            let retry = aws::retry_stage(clients, &app.pipeline.name, &stage, false);
+            let result = guarded(clients, guardrails, &app.pipeline.name, "retry", retry).await;
+            let action = Action {
+                action: "retry",
+                pipeline: app.pipeline.name.clone(),
+                stage: Some(stage.clone()),
+                execution_id: result.as_ref().ok().cloned(),
+                detail: Some("failed actions".to_owned()),
+            };
+            context
+                .audit
+                .record(clients, action, failure(&result))
+                .await;
+            match result {
+                Ok(_) => {
+                    app.toast(ToastLevel::Info, format!("Retrying {}", stage));
+                    context.poller.wake(&app.pipeline.name)
+                }
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::StopExecution {
+            execution_id,
+            reason,
+        } => {
+            let stop = aws::stop_execution(
+                clients,
+                &app.pipeline.name,
+                &execution_id,
+                false,
+                reason.clone(),
+            );
+            let result = guarded(clients, guardrails, &app.pipeline.name, "stop", stop).await;
+            let action = Action {
+                action: "stop",
+                pipeline: app.pipeline.name.clone(),
+                execution_id: Some(execution_id.clone()),
+                detail: reason,
+                ..Action::default()
+            };
+            context
+                .audit
+                .record(clients, action, failure(&result))
+                .await;
+            match result {
+                Ok(()) => {
+                    app.toast(
+                        ToastLevel::Info,
+                        format!("Stopping execution {}", aws::short_id(&execution_id)),
+                    );
+                    context.poller.wake(&app.pipeline.name)
+                }
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::LoadStats => load_stats(context, app).await,
+        Command::LoadProvenance(execution_id) => {
+            match aws::fetch_execution_actions(clients, &app.pipeline.name, &execution_id).await {
+                Ok(details) => {
+                    if let Some(execution) = app.pipeline.executions.iter().find(|execution| {
+                        execution.pipeline_execution_id.as_ref() == Some(&execution_id)
+                    }) {
+                        app.provenance =
+                            Some(provenance::trace(&app.definition, execution, &details));
+                    }
+                }
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::LoadTimeline(execution_id) => {
+            match aws::fetch_execution_actions(clients, &app.pipeline.name, &execution_id).await {
+                Ok(details) => app.timeline = Some(details),
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::LoadBookmarks => load_bookmarks(context, app),
+        Command::Bookmark {
+            pipeline,
+            execution_id,
+            bookmarked,
+        } => {
+            let history = match &context.history {
+                Some(history) => history,
+                None => {
+                    app.last_error = Some(
+                        "Bookmarks are kept in the history database, which is turned off"
+                            .to_owned(),
+                    );
+                    return;
+                }
+            };
+            match history.set_bookmark(&pipeline, &execution_id, bookmarked) {
+                Ok(()) => {
+                    let what = if bookmarked {
+                        "Bookmarked"
+                    } else {
+                        "Took the bookmark off"
+                    };
+                    app.toast(
+                        ToastLevel::Info,
+                        format!("{} {}", what, aws::short_id(&execution_id)),
+                    );
+                    load_bookmarks(context, app);
+                }
+                Err(e) => app.last_error = Some(format!("Couldn't save the bookmark: {}", e)),
+            }
+        }
+        Command::Annotate { execution_id, note } => {
+            let history = match &context.history {
+                Some(history) => history,
+                None => {
+                    app.last_error = Some(
+                        "Notes are kept in the history database, which is turned off".to_owned(),
+                    );
+                    return;
+                }
+            };
+            match history.set_note(&app.pipeline.name, &execution_id, &note) {
+                Ok(()) if note.trim().is_empty() => {
+                    app.notes.remove(&execution_id);
+                    app.toast(
+                        ToastLevel::Info,
+                        format!("Took the note off {}", aws::short_id(&execution_id)),
+                    );
+                }
+                Ok(()) => {
+                    app.notes
+                        .insert(execution_id.clone(), note.trim().to_owned());
+                    app.toast(
+                        ToastLevel::Info,
+                        format!("Noted on {}", aws::short_id(&execution_id)),
+                    );
+                }
+                Err(e) => app.last_error = Some(format!("Couldn't save the note: {}", e)),
+            }
+        }
+        // the open view reloads along with the update once the poll comes back
+        Command::Refresh => {
+            context.cache.clear();
+            context.poller.wake(&app.pipeline.name);
+        }
+        Command::Focus(execution_id) => {
+            app.pinned_execution = execution_id;
+            // the poll already has the execution's own stages, the latest state needs asking for again
+            let stage_states = match &app.pinned_execution {
+                Some(id) => app
+                    .pipeline
+                    .concurrent_execution(id)
+                    .map(|concurrent| Ok(concurrent.stage_states.clone())),
+                None => Some(aws::fetch_state(clients, &app.pipeline.name).await),
+            };
+            match stage_states {
+                Some(Ok(stage_states)) => app.pipeline.stage_states = stage_states,
+                Some(Err(e)) => app.last_error = Some(e.to_string()),
+                None => pin_execution(context, app).await,
+            }
+            after_refresh(context, app).await;
+            watch(context, app);
+        }
+        Command::Reauthenticate => context.reauthenticate = true,
+        Command::LoadStageActions(execution_id) => {
+            let fetch = aws::fetch_execution_actions(clients, &app.pipeline.name, &execution_id);
+            match cached(
+                &mut context.cache,
+                Api::StageActions,
+                execution_id.clone(),
+                fetch,
+            )
+            .await
+            {
+                Ok(details) => app.stage_actions = Some((execution_id, details)),
+                // remember we tried, or we'd be asking again on every tick until the next refresh
+                Err(e) => {
+                    app.stage_actions = Some((execution_id, vec![]));
+                    app.last_error = Some(e.to_string());
+                }
+            }
+        }
+        Command::LoadBuildLog(build_id) => {
+            if let Err(e) =
+                load_build_log(action_clients, &context.config.logs, app, &build_id).await
+            {
+                app.last_error = Some(e.to_string());
+            }
+        }
+        Command::Copy(text) => match copy(context, &text) {
+            Ok(()) => app.toast(ToastLevel::Info, format!("Copied {}", text)),
+            Err(e) => app.last_error = Some(format!("Couldn't copy to the clipboard: {}", e)),
+        },
+        Command::OpenUrl(url) => match console::open(&url) {
+            Ok(()) => app.toast(ToastLevel::Info, format!("Opened {}", url)),
+            Err(e) => app.last_error = Some(format!("Couldn't open {}: {}", url, e)),
+        },
+        Command::Compare { base, head } => {
+            // the stages straight from each execution's actions, the changes in its sources from the resolver
+            let pipeline = app.pipeline.name.clone();
+            let stages = futures::try_join!(
+                aws::fetch_execution_actions(&context.clients, &pipeline, &base),
+                aws::fetch_execution_actions(&context.clients, &pipeline, &head),
+            );
+            // a failure's kept for the pane too, which would otherwise be loading forever
+            app.stage_comparison = Some(match stages {
+                Ok((base, head)) => Ok(stats::compare_stages(&app.definition, &base, &head)),
+                Err(e) => {
+                    app.last_error = Some(e.to_string());
+                    Err(format!("Couldn't fetch the executions' stages: {}", e))
+                }
+            });
+            let find = |id: &str| {
+                app.pipeline
+                    .executions
+                    .iter()
+                    .find(|execution| execution.pipeline_execution_id.as_deref() == Some(id))
+                    .cloned()
+            };
+            // said in the pane, which would otherwise be loading forever
+            app.comparison = Some(match (&context.resolver, find(&base), find(&head)) {
+                (None, _, _) => Err("No git resolver configured".to_owned()),
+                (Some(resolver), Some(base), Some(head)) => {
+                    Ok(resolver.compare(&base, &head).await)
+                }
+                _ => Err("Execution not found in the recent history".to_owned()),
+            });
+        }
+        Command::Load(Detail::Build(build_id)) => {
+            let resource = regional_key(action_clients, &build_id);
+            let fetch = aws::fetch_build(action_clients, &build_id);
+            match cached(&mut context.cache, Api::Build, resource, fetch).await {
+                Ok(build) => app.build = Some(build),
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::Load(Detail::StackEvents(stack_name)) => {
+            let resource = regional_key(action_clients, &stack_name);
+            let fetch = aws::fetch_stack_events(action_clients, &stack_name);
+            match cached(&mut context.cache, Api::StackEvents, resource, fetch).await {
+                Ok(events) => app.stack_events = Some(events),
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::Load(Detail::Deployment(source)) => {
+            let resource = regional_key(action_clients, &format!("{:?}", source));
+            let fetch = deploy::fetch_deployment(action_clients, &source);
+            match cached(&mut context.cache, Api::Deployment, resource, fetch).await {
+                Ok(deployment) => app.deployment = Some(deployment),
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        Command::Load(Detail::Approvals { stage, action }) => {
+            let resource = format!("{}/{}/{}", app.pipeline.name, stage, action);
+            let fetch = aws::fetch_action_executions(
+                clients,
+                &app.pipeline.name,
+                &stage,
+                &action,
+                ui::PREVIOUS_APPROVALS,
+            );
+            match cached(&mut context.cache, Api::Approvals, resource, fetch).await {
+                Ok(approvals) => app.approvals = Some(approvals),
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::aws::Clients;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use rusoto_core::Region;
+use serde_json::{json, Value};
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+// a stand-in for CodePipeline on localhost, speaking just enough of its JSON protocol (the operation in
+// X-Amz-Target, the input and output as JSON, errors as a __type and a message) to hand back canned responses
+// and remember what it was asked, so the tests can go through the real clients and the real calls
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    // per operation, answered in order, with the last one repeated for as long as it keeps being asked
+    responses: HashMap<String, VecDeque<Canned>>,
+    // answered whenever the input has all these fields, ahead of the ones in order, for calls made at once
+    matching: Vec<(String, Value, Canned)>,
+    requests: Vec<(String, Value)>,
+}
+
+#[derive(Clone)]
+struct Canned {
+    status: u16,
+    body: Value,
+}
+
+impl MockServer {
+    // listening on a port of its own, on whichever tokio runtime the test is running in
+    pub async fn start() -> MockServer {
+        let state = Arc::new(Mutex::new(State::default()));
+        let shared = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = shared.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| handle(state.clone(), request))) }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                error!("The mock server stopped: {}", e);
+            }
+        });
+        MockServer { addr, state }
+    }
+
+    // everything pointed at us
+    pub fn clients(&self) -> Clients {
+        Clients::local(self.region()).expect("Couldn't set up an HTTP client")
+    }
+
+    pub fn region(&self) -> Region {
+        Region::Custom {
+            name: "us-west-2".to_owned(),
+            endpoint: format!("http://{}", self.addr),
+        }
+    }
+
+    // what the operation answers with next
+    pub fn respond(&self, operation: &str, body: Value) {
+        self.queue(operation, Canned { status: 200, body });
+    }
+
+    // what the operation answers with whenever it's asked with these fields in its input, e.g. one execution's
+    // ListActionExecutions however the calls happen to arrive
+    pub fn respond_to(&self, operation: &str, input: Value, body: Value) {
+        self.state.lock().unwrap().matching.push((
+            operation.to_owned(),
+            input,
+            Canned { status: 200, body },
+        ));
+    }
+
+    // an error in the shape the SDKs expect, e.g. 400 and PipelineNotFoundException
+    pub fn fail(&self, operation: &str, status: u16, error_type: &str, message: &str) {
+        self.queue(
+            operation,
+            Canned {
+                status,
+                body: json!({ "__type": error_type, "message": message }),
+            },
+        );
+    }
+
+    // the input of every call made to the operation so far, oldest first
+    pub fn requests(&self, operation: &str) -> Vec<Value> {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|(name, _)| name == operation)
+            .map(|(_, input)| input.clone())
+            .collect()
+    }
+
+    fn queue(&self, operation: &str, canned: Canned) {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .entry(operation.to_owned())
+            .or_default()
+            .push_back(canned);
+    }
+}
+
+async fn handle(
+    state: Arc<Mutex<State>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, hyper::Error> {
+    // CodePipeline_20150709.ListPipelines
+    let operation = request
+        .headers()
+        .get("x-amz-target")
+        .and_then(|target| target.to_str().ok())
+        .and_then(|target| target.rsplit('.').next())
+        .unwrap_or_default()
+        .to_owned();
+    let body = hyper::body::to_bytes(request.into_body()).await?;
+    let input = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let canned = {
+        let mut state = state.lock().unwrap();
+        let matched = state
+            .matching
+            .iter()
+            .find(|(name, wanted, _)| *name == operation && contains(&input, wanted))
+            .map(|(_, _, canned)| canned.clone());
+        state.requests.push((operation.clone(), input));
+        let queue = state.responses.get_mut(&operation);
+        match queue {
+            _ if matched.is_some() => matched,
+            Some(queue) if queue.len() > 1 => queue.pop_front(),
+            Some(queue) => queue.front().cloned(),
+            None => None,
+        }
+    };
+    // a test that forgot to set something up should fail saying so, rather than on some parse error
+    let canned = canned.unwrap_or_else(|| Canned {
+        status: 400,
+        body: json!({
+            "__type": "UnknownOperationException",
+            "message": format!("The mock server has nothing for {}", operation),
+        }),
+    });
+    let response = Response::builder()
+        .status(canned.status)
+        .header("content-type", "application/x-amz-json-1.1")
+        .body(Body::from(canned.body.to_string()))
+        .expect("Couldn't build the mock response");
+    Ok(response)
+}
+
+// every field of wanted is in input with the same value, going into objects, so a filter can be matched on one
+// field of it
+fn contains(input: &Value, wanted: &Value) -> bool {
+    match (input, wanted) {
+        (Value::Object(input), Value::Object(wanted)) => wanted
+            .iter()
+            .all(|(key, wanted)| input.get(key).is_some_and(|input| contains(input, wanted))),
+        _ => input == wanted,
+    }
+}
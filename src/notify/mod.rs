@@ -0,0 +1,394 @@
+use crate::aws::short_id;
+use crate::config::{Channel, NotificationsConfig};
+use crate::console;
+use chrono::Local;
+use rules::Change;
+use serde_json::json;
+use throttle::FailureThrottle;
+use tokio::process::Command;
+
+use std::collections::HashMap;
+use std::env::consts;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+pub mod rules;
+pub mod throttle;
+
+// sends pipelines' executions moving on wherever the [notifications] rules say, on top of the toasts
+pub struct Notifier {
+    config: NotificationsConfig,
+    http: reqwest::Client,
+    region: String,
+    // per pipeline, the latest execution we've seen, so each change is only sent once
+    seen: HashMap<String, Seen>,
+    throttle: FailureThrottle,
+    // what came up during quiet hours, for sending once they're over, along with where each was going
+    held: Vec<(Vec<Channel>, Notification)>,
+}
+
+struct Seen {
+    execution_id: String,
+    status: String,
+    // how the execution before it finished, which is what makes a success a recovery
+    previous: Option<String>,
+}
+
+// everything the channels are given about a change
+#[derive(Clone)]
+struct Notification {
+    pipeline: String,
+    execution_id: String,
+    // only for an Overdue, the stage that's gone over
+    stage: Option<String>,
+    status: String,
+    previous: Option<String>,
+    recovered: bool,
+    // for a "still failing", how many failures it stands for that weren't sent
+    repeated: u32,
+    message: String,
+    url: String,
+}
+
+impl Notifier {
+    pub fn new(config: NotificationsConfig, http: reqwest::Client, region: String) -> Notifier {
+        for problem in rules::problems(&config) {
+            warn!("{}", problem);
+        }
+        Notifier {
+            config,
+            http,
+            region,
+            seen: HashMap::new(),
+            throttle: FailureThrottle::default(),
+            held: vec![],
+        }
+    }
+
+    // called every time round the main loop, for whatever's waiting on the clock: the summaries of failures held
+    // back, and everything held over quiet hours once they end
+    pub fn tick(&mut self) {
+        for still in self.throttle.expired(self.failure_window(), Instant::now()) {
+            let change = Change {
+                pipeline: &still.pipeline,
+                status: "Failed",
+                previous: None,
+            };
+            let channels = rules::channels(&self.config, &change);
+            let notification = Notification {
+                pipeline: still.pipeline.clone(),
+                execution_id: still.execution_id.clone(),
+                stage: None,
+                status: "Failed".to_owned(),
+                previous: None,
+                recovered: false,
+                repeated: still.held,
+                message: format!(
+                    "{} is still failing, {} more times since {}",
+                    still.pipeline,
+                    still.held,
+                    still.since.format("%H:%M")
+                ),
+                url: console::execution_url(&self.region, &still.pipeline, &still.execution_id),
+            };
+            self.deliver(channels, notification);
+        }
+        if self.held.is_empty() || self.quiet() {
+            return;
+        }
+        // one go per channel with everything that was meant for it
+        let held = std::mem::take(&mut self.held);
+        for channel in [
+            Channel::Desktop,
+            Channel::Slack,
+            Channel::Webhook,
+            Channel::Sound,
+        ] {
+            let batch = held
+                .iter()
+                .filter(|(channels, _)| channels.contains(&channel))
+                .map(|(_, notification)| notification.clone())
+                .collect::<Vec<_>>();
+            if !batch.is_empty() {
+                self.dispatch(vec![channel], batch);
+            }
+        }
+    }
+
+    // the pipeline's latest execution as of the latest refresh, from the open pipeline or the fleet's listings.
+    // The first time a pipeline's seen only tells us where it stands, anything after that is a change
+    pub fn observe(&mut self, pipeline: &str, execution_id: &str, status: &str) {
+        let previous = match self.seen.get(pipeline) {
+            None => None,
+            Some(seen) if seen.execution_id == execution_id && seen.status == status => return,
+            Some(seen) if seen.execution_id == execution_id => seen.previous.clone(),
+            // a newer execution, the one we knew about having finished, or been overtaken before we saw it finish
+            Some(seen) if finished(&seen.status) => Some(seen.status.clone()),
+            Some(seen) => seen.previous.clone(),
+        };
+        let first = !self.seen.contains_key(pipeline);
+        self.seen.insert(
+            pipeline.to_owned(),
+            Seen {
+                execution_id: execution_id.to_owned(),
+                status: status.to_owned(),
+                previous: previous.clone(),
+            },
+        );
+        if status == "Succeeded" {
+            self.throttle.recovered(pipeline);
+        }
+        if first {
+            return;
+        }
+
+        let change = Change {
+            pipeline,
+            status,
+            previous: previous.as_deref(),
+        };
+        let channels = rules::channels(&self.config, &change);
+        if channels.is_empty() {
+            return;
+        }
+        let notification = Notification {
+            pipeline: pipeline.to_owned(),
+            execution_id: execution_id.to_owned(),
+            stage: None,
+            status: status.to_owned(),
+            previous: previous.clone(),
+            recovered: change.recovered(),
+            repeated: 0,
+            message: message(&change, execution_id),
+            url: console::execution_url(&self.region, pipeline, execution_id),
+        };
+        if status == "Failed"
+            && !self.throttle.allow(
+                pipeline,
+                execution_id,
+                self.failure_window(),
+                Instant::now(),
+            )
+        {
+            return;
+        }
+        self.deliver(channels, notification);
+    }
+
+    // a stage of the open pipeline running past its [sla], once per stage run, to wherever the rules on Overdue
+    // say
+    pub fn overdue(
+        &mut self,
+        pipeline: &str,
+        stage: &str,
+        execution_id: &str,
+        minutes: u64,
+        allowed: u64,
+    ) {
+        let change = Change {
+            pipeline,
+            status: "Overdue",
+            previous: None,
+        };
+        let channels = rules::channels(&self.config, &change);
+        let notification = Notification {
+            pipeline: pipeline.to_owned(),
+            execution_id: execution_id.to_owned(),
+            stage: Some(stage.to_owned()),
+            status: "Overdue".to_owned(),
+            previous: None,
+            recovered: false,
+            repeated: 0,
+            message: format!(
+                "{}'s {} has been running {}m, over its {}m SLA ({})",
+                pipeline,
+                stage,
+                minutes,
+                allowed,
+                short_id(execution_id)
+            ),
+            url: console::execution_url(&self.region, pipeline, execution_id),
+        };
+        self.deliver(channels, notification);
+    }
+
+    fn failure_window(&self) -> Duration {
+        Duration::from_secs(self.config.failure_minutes * 60)
+    }
+
+    fn quiet(&self) -> bool {
+        self.config
+            .quiet_hours
+            .as_ref()
+            .is_some_and(|quiet| throttle::is_quiet(quiet, Local::now().time()))
+    }
+
+    fn deliver(&mut self, channels: Vec<Channel>, notification: Notification) {
+        if channels.is_empty() {
+            return;
+        }
+        if self.quiet() {
+            if self
+                .config
+                .quiet_hours
+                .as_ref()
+                .is_some_and(|quiet| quiet.batch)
+            {
+                self.held.push((channels, notification));
+            } else {
+                info!("Not sending {} during quiet hours", notification.message);
+            }
+            return;
+        }
+        self.dispatch(channels, vec![notification]);
+    }
+
+    // in the background, a slow webhook shouldn't hold up the dashboard
+    fn dispatch(&self, channels: Vec<Channel>, notifications: Vec<Notification>) {
+        let http = self.http.clone();
+        let config = self.config.clone();
+        tokio::spawn(async move {
+            for channel in channels {
+                if let Err(e) = send(&http, &config, channel, &notifications).await {
+                    warn!("Couldn't send notifications to {:?}: {}", channel, e);
+                }
+            }
+        });
+    }
+}
+
+fn finished(status: &str) -> bool {
+    !matches!(status, "InProgress" | "Stopping")
+}
+
+fn message(change: &Change, execution_id: &str) -> String {
+    let id = short_id(execution_id);
+    match change.status {
+        _ if change.recovered() => format!("{} is green again ({})", change.pipeline, id),
+        "InProgress" => format!("{} started ({})", change.pipeline, id),
+        status => format!("{} {} ({})", change.pipeline, status.to_lowercase(), id),
+    }
+}
+
+// several at once (after quiet hours) go as one popup or Slack message, a webhook still gets them one by one
+async fn send(
+    http: &reqwest::Client,
+    config: &NotificationsConfig,
+    channel: Channel,
+    notifications: &[Notification],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match channel {
+        Channel::Desktop => {
+            let messages = notifications
+                .iter()
+                .map(|notification| notification.message.as_str())
+                .collect::<Vec<_>>();
+            desktop(&messages.join("\n")).await
+        }
+        Channel::Slack => {
+            let url = config.slack_webhook.as_deref().ok_or("no slack_webhook")?;
+            let mut lines = notifications
+                .iter()
+                .map(|notification| format!("{} <{}|open>", notification.message, notification.url))
+                .collect::<Vec<_>>();
+            if lines.len() > 1 {
+                lines.insert(0, "While it was quiet:".to_owned());
+            }
+            post(http, url, json!({ "text": lines.join("\n") })).await
+        }
+        Channel::Webhook => {
+            let url = config.webhook.as_deref().ok_or("no webhook")?;
+            for notification in notifications {
+                let body = json!({
+                    "pipeline": notification.pipeline,
+                    "execution_id": notification.execution_id,
+                    "stage": notification.stage,
+                    "status": notification.status,
+                    "previous_status": notification.previous,
+                    "recovered": notification.recovered,
+                    "repeated": notification.repeated,
+                    "message": notification.message,
+                    "url": notification.url,
+                });
+                post(http, url, body).await?;
+            }
+            Ok(())
+        }
+        // once, however many it's for
+        Channel::Sound => sound(config.sound.as_deref()).await,
+    }
+}
+
+async fn post(
+    http: &reqwest::Client,
+    url: &str,
+    body: serde_json::Value,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    http.post(url)
+        .json(&body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+// the sound file through whatever plays one here, or the terminal's bell, which rings (or flashes) even over
+// ssh
+async fn sound(file: Option<&Path>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let file = match file {
+        Some(file) => file,
+        None => {
+            let mut stdout = io::stdout();
+            stdout.write_all(b"\x07")?;
+            stdout.flush()?;
+            return Ok(());
+        }
+    };
+    let players: &[&str] = if consts::OS == "macos" {
+        &["afplay"]
+    } else {
+        // PulseAudio (or PipeWire's stand-in for it), then plain ALSA
+        &["paplay", "aplay"]
+    };
+    let mut last = None;
+    for player in players {
+        // anything they print would land in the middle of the dashboard
+        let status = Command::new(player)
+            .arg(file)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await;
+        match status {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => last = Some(format!("{} exited with {}", player, status)),
+            Err(e) => last = Some(format!("Couldn't run {}: {}", player, e)),
+        }
+    }
+    Err(last.unwrap_or_default().into())
+}
+
+// whatever the desktop has for popping something up: Notification Center on a Mac, notify-send (libnotify)
+// everywhere else
+async fn desktop(message: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut command = if consts::OS == "macos" {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {:?} with title \"codepipeline-status\"",
+            message
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg("codepipeline-status").arg(message);
+        command
+    };
+    let status = command.status().await?;
+    if !status.success() {
+        return Err(format!("{:?} exited with {}", command, status).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use crate::config::{wildcard_match, Channel, NotificationsConfig};
+use crate::notify::throttle::parse_time;
+
+// what a rule's `on` can name
+pub const EVENTS: &[&str] = &[
+    "InProgress",
+    "Succeeded",
+    "Recovered",
+    "Failed",
+    "Stopped",
+    "Superseded",
+    "Overdue",
+];
+
+// a pipeline's latest execution having moved on to another status
+pub struct Change<'a> {
+    pub pipeline: &'a str,
+    // or Overdue, for a stage that's gone over its [sla]
+    pub status: &'a str,
+    // how the execution before it finished, if we saw it finish
+    pub previous: Option<&'a str>,
+}
+
+impl Change<'_> {
+    // a success straight after a failure is the one worth telling everyone about
+    pub fn recovered(&self) -> bool {
+        self.status == "Succeeded" && self.previous == Some("Failed")
+    }
+
+    fn is(&self, event: &str) -> bool {
+        event == self.status || (event == "Recovered" && self.recovered())
+    }
+}
+
+// where the change has to be sent, nowhere if the first rule for the pipeline isn't interested in it or there's
+// no rule for it at all
+pub fn channels(config: &NotificationsConfig, change: &Change) -> Vec<Channel> {
+    let rule = config
+        .rules
+        .iter()
+        .find(|rule| wildcard_match(&rule.pipeline, change.pipeline));
+    match rule {
+        Some(rule) if rule.on.iter().any(|event| change.is(event)) => rule.channels.clone(),
+        _ => vec![],
+    }
+}
+
+// anything in the config that can't ever send a notification, for warning about once at startup
+pub fn problems(config: &NotificationsConfig) -> Vec<String> {
+    let mut problems = vec![];
+    if let Some(quiet) = &config.quiet_hours {
+        for time in [&quiet.from, &quiet.to] {
+            if parse_time(time).is_none() {
+                problems.push(format!(
+                    "Quiet hours need times like 22:00, not {}, so there aren't any",
+                    time
+                ));
+            }
+        }
+    }
+    for rule in &config.rules {
+        for event in &rule.on {
+            if !EVENTS.contains(&event.as_str()) {
+                problems.push(format!(
+                    "The notification rule for {} is on {}, which isn't one of {}",
+                    rule.pipeline,
+                    event,
+                    EVENTS.join(", ")
+                ));
+            }
+        }
+        if rule.channels.contains(&Channel::Slack) && config.slack_webhook.is_none() {
+            problems.push(format!(
+                "The notification rule for {} sends to slack, but there's no slack_webhook",
+                rule.pipeline
+            ));
+        }
+        if let (true, Some(sound)) = (rule.channels.contains(&Channel::Sound), &config.sound) {
+            if !sound.is_file() {
+                problems.push(format!(
+                    "The notification rule for {} plays {}, which isn't there",
+                    rule.pipeline,
+                    sound.display()
+                ));
+            }
+        }
+        if rule.channels.contains(&Channel::Webhook) && config.webhook.is_none() {
+            problems.push(format!(
+                "The notification rule for {} sends to webhook, but there's no webhook",
+                rule.pipeline
+            ));
+        }
+    }
+    problems
+}
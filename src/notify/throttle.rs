@@ -0,0 +1,113 @@
+use crate::config::QuietHours;
+use chrono::{DateTime, Local, NaiveTime};
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// "22:00"
+pub fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M").ok()
+}
+
+// whether it's quiet at `now`, the hours running past midnight if they end before they start. Times that don't
+// parse are never quiet, they're complained about at startup
+pub fn is_quiet(quiet: &QuietHours, now: NaiveTime) -> bool {
+    let (from, to) = match (parse_time(&quiet.from), parse_time(&quiet.to)) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return false,
+    };
+    if from <= to {
+        from <= now && now < to
+    } else {
+        now >= from || now < to
+    }
+}
+
+// a failure from each pipeline per window, the ones after it are only counted, so one that keeps flapping
+// doesn't bury everything else
+#[derive(Default)]
+pub struct FailureThrottle {
+    windows: HashMap<String, Window>,
+}
+
+struct Window {
+    ends: Instant,
+    since: DateTime<Local>,
+    held: u32,
+    // the latest of the failures held back
+    execution_id: String,
+}
+
+// what to say about a window's held back failures once it's over
+pub struct StillFailing {
+    pub pipeline: String,
+    pub held: u32,
+    pub since: DateTime<Local>,
+    pub execution_id: String,
+}
+
+impl FailureThrottle {
+    // whether the failure can be sent now, otherwise it's counted towards the window's summary
+    pub fn allow(
+        &mut self,
+        pipeline: &str,
+        execution_id: &str,
+        window: Duration,
+        now: Instant,
+    ) -> bool {
+        if window == Duration::default() {
+            return true;
+        }
+        match self.windows.get_mut(pipeline) {
+            Some(open) if now < open.ends => {
+                open.held += 1;
+                open.execution_id = execution_id.to_owned();
+                false
+            }
+            _ => {
+                self.windows.insert(
+                    pipeline.to_owned(),
+                    Window {
+                        ends: now + window,
+                        since: Local::now(),
+                        held: 0,
+                        execution_id: execution_id.to_owned(),
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    // the pipeline's passing again, so its held back failures are old news rather than something to say it's
+    // still doing, and the next failure goes straight out
+    pub fn recovered(&mut self, pipeline: &str) {
+        self.windows.remove(pipeline);
+    }
+
+    // the windows that are over with failures held back in them. Sending the summary opens a new window, so a
+    // pipeline that carries on failing is still only heard from once per window
+    pub fn expired(&mut self, window: Duration, now: Instant) -> Vec<StillFailing> {
+        let mut summaries = vec![];
+        self.windows.retain(|pipeline, open| {
+            if now < open.ends {
+                return true;
+            }
+            if open.held == 0 {
+                return false;
+            }
+            summaries.push(StillFailing {
+                pipeline: pipeline.clone(),
+                held: open.held,
+                since: open.since,
+                execution_id: open.execution_id.clone(),
+            });
+            open.ends = now + window;
+            open.since = Local::now();
+            open.held = 0;
+            true
+        });
+        summaries.sort_by(|a, b| a.pipeline.cmp(&b.pipeline));
+        summaries
+    }
+}
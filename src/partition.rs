@@ -0,0 +1,85 @@
+use crate::config::EndpointConfig;
+use rusoto_core::Region;
+
+// which AWS partition a region is in, each has its own ARNs, endpoint domain and console
+pub struct Partition {
+    // what goes after "arn:"
+    pub name: &'static str,
+    pub dns_suffix: &'static str,
+    // the dual-stack (IPv4 and IPv6) endpoints live under a domain of their own
+    pub dual_stack_suffix: &'static str,
+    pub console_host: &'static str,
+}
+
+pub fn for_region(region: &str) -> Partition {
+    if region.starts_with("cn-") {
+        Partition {
+            name: "aws-cn",
+            dns_suffix: "amazonaws.com.cn",
+            dual_stack_suffix: "api.amazonwebservices.com.cn",
+            console_host: "console.amazonaws.cn",
+        }
+    } else if region.starts_with("us-gov-") {
+        Partition {
+            name: "aws-us-gov",
+            dns_suffix: "amazonaws.com",
+            dual_stack_suffix: "api.aws",
+            console_host: "console.amazonaws-us-gov.com",
+        }
+    } else {
+        Partition {
+            name: "aws",
+            dns_suffix: "amazonaws.com",
+            dual_stack_suffix: "api.aws",
+            console_host: "console.aws.amazon.com",
+        }
+    }
+}
+
+// rusoto only knows the regions there were when it was released, anything newer is accepted as long as it looks
+// like a region, and gets its endpoints worked out by `endpoint`
+pub fn parse_region(region: &str) -> Result<Region, String> {
+    if let Ok(known) = region.parse::<Region>() {
+        return Ok(known);
+    }
+    let looks_like_a_region = region.contains('-')
+        && region
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !looks_like_a_region {
+        return Err(format!("\"{}\" isn't a region", region));
+    }
+    Ok(Region::Custom {
+        name: region.to_owned(),
+        endpoint: String::new(),
+    })
+}
+
+// the region to hand one service's client, which for a region rusoto doesn't know, or FIPS or dual-stack
+// endpoints, means spelling out that service's endpoint, since a custom region's one endpoint would otherwise be
+// used for every service
+pub fn endpoint(region: &Region, service: &str, options: EndpointConfig) -> Region {
+    match region {
+        // an endpoint given some other way (a local mock, say) is left alone
+        Region::Custom { endpoint, .. } if !endpoint.is_empty() => return region.clone(),
+        Region::Custom { .. } => {}
+        _ if !options.fips && !options.dual_stack => return region.clone(),
+        _ => {}
+    }
+    let name = region.name();
+    let partition = for_region(name);
+    Region::Custom {
+        name: name.to_owned(),
+        endpoint: format!(
+            "https://{}{}.{}.{}",
+            service,
+            if options.fips { "-fips" } else { "" },
+            name,
+            if options.dual_stack {
+                partition.dual_stack_suffix
+            } else {
+                partition.dns_suffix
+            }
+        ),
+    }
+}
@@ -0,0 +1,409 @@
+use crate::aws::{self, Clients, ExecutionMode, Pipeline, PipelineListing};
+use crate::config::{wildcard_match, PollingConfig};
+use crate::watchlist::Watchlist;
+use tokio::sync::{mpsc, Notify, Semaphore};
+use tokio::time::delay_for;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// how often a pipeline is polled, often while something's running and hardly at all otherwise, unless [polling]
+// says otherwise
+const ACTIVE_INTERVAL: Duration = Duration::from_secs(5);
+const IDLE_INTERVAL: Duration = Duration::from_secs(90);
+// a failing pipeline waits twice as long after every failure, up to this
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+// however many pipelines are being watched, only this many of them talk to AWS at once
+pub const MAX_CONCURRENT_REQUESTS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Intervals {
+    pub active: Duration,
+    pub idle: Duration,
+}
+
+// the first [polling] entry for the pipeline, or the defaults. Never quicker than a second, 0 would have a
+// task polling flat out
+pub fn intervals(config: &PollingConfig, pipeline: &str) -> Intervals {
+    match config
+        .pipelines
+        .iter()
+        .find(|polling| wildcard_match(&polling.pipeline, pipeline))
+    {
+        Some(polling) => Intervals {
+            active: Duration::from_secs(polling.seconds.max(1)),
+            idle: Duration::from_secs(polling.idle_seconds.unwrap_or(polling.seconds).max(1)),
+        },
+        None => Intervals {
+            active: ACTIVE_INTERVAL,
+            idle: IDLE_INTERVAL,
+        },
+    }
+}
+
+// what one poll of a pipeline came back with
+pub struct Update {
+    pub pipeline: String,
+    pub result: Result<Pipeline, String>,
+}
+
+// the fleet's watched pipelines listed again, with the listings of any that are new since. Those that have gone are
+// the known ones missing from names
+pub struct Listed {
+    pub names: Vec<String>,
+    pub new: Vec<PipelineListing>,
+}
+
+// when a poll fetches the execution list as well as the state, going by what's on screen. The stages pane
+// needs nothing but the state, so watching a pipeline is the one call a poll
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Executions {
+    Never,
+    // once a stage has moved on to an execution that isn't in the list, for the commits pane's revisions
+    New,
+    // once the state shows anything has moved on, for the fleet's cells and a parallel pipeline's executions
+    Changes,
+    // every poll, while one of the views about executions is open
+    Always,
+}
+
+// how the supervisor reaches a pipeline's task while it's running
+struct Watch {
+    stop: Arc<AtomicBool>,
+    executions: Arc<Mutex<Executions>>,
+    wake: Arc<Notify>,
+}
+
+// one task per watched pipeline, each with its own interval and backoff, so a slow or failing pipeline only
+// holds itself up, and a shared cap on how many are fetching at once
+pub struct Supervisor {
+    clients: Clients,
+    polling: PollingConfig,
+    permits: Arc<Semaphore>,
+    sender: mpsc::UnboundedSender<Update>,
+    receiver: mpsc::UnboundedReceiver<Update>,
+    watches: HashMap<String, Watch>,
+    listed_sender: mpsc::UnboundedSender<Result<Listed, String>>,
+    listed_receiver: mpsc::UnboundedReceiver<Result<Listed, String>>,
+    // one relisting at a time, a slow one shouldn't have another piling in behind it
+    relisting: Arc<AtomicBool>,
+}
+
+impl Supervisor {
+    pub fn new(clients: Clients, polling: PollingConfig) -> Supervisor {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (listed_sender, listed_receiver) = mpsc::unbounded_channel();
+        Supervisor {
+            clients,
+            polling,
+            permits: Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS)),
+            sender,
+            receiver,
+            watches: HashMap::new(),
+            listed_sender,
+            listed_receiver,
+            relisting: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // the same cap for anything else that fetches a lot at once
+    pub fn permits(&self) -> &Semaphore {
+        &self.permits
+    }
+
+    // list the pipelines again in the background, fetching listings only for those that are watched and aren't
+    // known yet, the known ones are kept up to date by their own tasks. The result turns up in try_listed
+    pub fn relist(&self, account: &str, known: Vec<String>, watchlist: Option<Watchlist>) {
+        if self.relisting.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let clients = self.clients.clone();
+        let permits = self.permits.clone();
+        let sender = self.listed_sender.clone();
+        let relisting = self.relisting.clone();
+        let account = account.to_owned();
+        tokio::spawn(async move {
+            let result = async {
+                let names = {
+                    let _permit = permits.acquire().await;
+                    aws::list_pipeline_names(&clients).await?
+                };
+                let names = names
+                    .into_iter()
+                    .filter(|name| {
+                        watchlist
+                            .as_ref()
+                            .is_none_or(|watchlist| watchlist.matches(name))
+                    })
+                    .collect::<Vec<_>>();
+                let new = names
+                    .iter()
+                    .filter(|name| !known.contains(name))
+                    .cloned()
+                    .collect();
+                let new = aws::fetch_listings(&clients, &account, new, &permits).await;
+                Ok::<_, Box<dyn Error + Send + Sync>>(Listed { names, new })
+            };
+            let result = result.await.map_err(|e| e.to_string());
+            relisting.store(false, Ordering::Relaxed);
+            let _ = sender.send(result);
+        });
+    }
+
+    pub fn try_listed(&mut self) -> Option<Result<Listed, String>> {
+        self.listed_receiver.try_recv().ok()
+    }
+
+    // starting from what we already have, so the first poll can tell what's changed since
+    pub fn watch(&mut self, pipeline: Pipeline, pinned: Option<String>, mode: ExecutionMode) {
+        self.spawn(pipeline, pinned, mode, Executions::Never, false);
+    }
+
+    // one of the fleet view's pipelines, unless it's already watched as the open one. There's only its listing
+    // to go on until the first poll, which says whether to start on the quick interval
+    pub fn watch_listing(&mut self, listing: &PipelineListing) {
+        if self.watches.contains_key(&listing.name) {
+            return;
+        }
+        let pipeline = Pipeline {
+            name: listing.name.clone(),
+            ..Pipeline::default()
+        };
+        let running = listing.running_since.is_some();
+        self.spawn(
+            pipeline,
+            None,
+            ExecutionMode::default(),
+            Executions::Changes,
+            running,
+        );
+    }
+
+    fn spawn(
+        &mut self,
+        pipeline: Pipeline,
+        pinned: Option<String>,
+        mode: ExecutionMode,
+        executions: Executions,
+        running: bool,
+    ) {
+        self.unwatch(&pipeline.name);
+        let watch = Watch {
+            stop: Arc::new(AtomicBool::new(false)),
+            executions: Arc::new(Mutex::new(executions)),
+            wake: Arc::new(Notify::new()),
+        };
+        let task = Task {
+            clients: self.clients.clone(),
+            permits: self.permits.clone(),
+            sender: self.sender.clone(),
+            stop: watch.stop.clone(),
+            executions: watch.executions.clone(),
+            wake: watch.wake.clone(),
+            pinned,
+            mode,
+            running,
+            intervals: intervals(&self.polling, &pipeline.name),
+        };
+        self.watches.insert(pipeline.name.clone(), watch);
+        tokio::spawn(task.run(pipeline));
+    }
+
+    // the task finishes at its next wakeup, anything it sends before then is for a pipeline nobody's looking at
+    pub fn unwatch(&mut self, name: &str) {
+        if let Some(watch) = self.watches.remove(name) {
+            watch.stop.store(true, Ordering::Relaxed);
+            watch.wake.notify();
+        }
+    }
+
+    pub fn unwatch_all(&mut self) {
+        let names = self.watches.keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            self.unwatch(&name);
+        }
+    }
+
+    // poll now rather than waiting out the interval, after starting something or when asked to refresh
+    pub fn wake(&self, name: &str) {
+        if let Some(watch) = self.watches.get(name) {
+            watch.wake.notify();
+        }
+    }
+
+    pub fn wake_all(&self) {
+        for watch in self.watches.values() {
+            watch.wake.notify();
+        }
+    }
+
+    // a view wanting more than the task's been fetching has it polled straight away, rather than going without
+    // until the next poll
+    pub fn set_executions(&self, name: &str, executions: Executions) {
+        if let Some(watch) = self.watches.get(name) {
+            let mut current = watch.executions.lock().unwrap();
+            if executions > *current {
+                watch.wake.notify();
+            }
+            *current = executions;
+        }
+    }
+
+    // whatever's come in since we last looked, without waiting
+    pub fn try_recv(&mut self) -> Option<Update> {
+        self.receiver.try_recv().ok()
+    }
+
+    pub async fn recv(&mut self) -> Option<Update> {
+        self.receiver.recv().await
+    }
+}
+
+struct Task {
+    clients: Clients,
+    permits: Arc<Semaphore>,
+    sender: mpsc::UnboundedSender<Update>,
+    stop: Arc<AtomicBool>,
+    executions: Arc<Mutex<Executions>>,
+    wake: Arc<Notify>,
+    pinned: Option<String>,
+    mode: ExecutionMode,
+    // what the listing said before there's been a poll to go by
+    running: bool,
+    intervals: Intervals,
+}
+
+impl Task {
+    async fn run(self, mut pipeline: Pipeline) {
+        let mut failures = 0;
+        let mut running = self.running;
+        loop {
+            // the interval follows whatever the last poll found, so a new execution turning up on an idle
+            // pipeline switches straight over to the quick one
+            let wait = if failures > 0 {
+                // a pipeline that's only polled every few minutes anyway can wait longer than the cap
+                (self.intervals.active * 2u32.pow(failures.min(6)))
+                    .min(MAX_BACKOFF.max(self.intervals.active))
+            } else if running || pipeline.active() {
+                self.intervals.active
+            } else {
+                self.intervals.idle
+            };
+            tokio::select! {
+                _ = delay_for(wait) => {}
+                _ = self.wake.notified() => {}
+            }
+            if self.stop.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let result = {
+                let _permit = self.permits.acquire().await;
+                self.fetch_changes(&pipeline).await
+            };
+            let result = match result {
+                Ok(next) => {
+                    failures = 0;
+                    running = false;
+                    pipeline = next.clone();
+                    Ok(next)
+                }
+                Err(e) => {
+                    failures += 1;
+                    Err(e.to_string())
+                }
+            };
+            let update = Update {
+                pipeline: pipeline.name.clone(),
+                result,
+            };
+            // nobody's listening any more
+            if self.sender.send(update).is_err() {
+                return;
+            }
+        }
+    }
+
+    // watching a pipeline is the one call a poll. The execution list is only fetched as well when what's on
+    // screen needs it, see Executions, a pinned execution's actions are fetched every poll to pin to, and a
+    // parallel pipeline costs another call for each execution still running but the pinned one
+    async fn fetch_changes(
+        &self,
+        previous: &Pipeline,
+    ) -> Result<Pipeline, Box<dyn Error + Send + Sync>> {
+        let clients = &self.clients;
+        let name = &previous.name;
+        let mut pipeline = Pipeline {
+            name: name.clone(),
+            stage_states: aws::fetch_state(clients, name).await?,
+            executions: previous.executions.clone(),
+            concurrent: vec![],
+        };
+        // what the parallel executions' own stages start from, before pinning swaps in one execution's view
+        let latest = pipeline.stage_states.clone();
+        // pinned before comparing, so we're comparing like with like
+        let mut pinned = None;
+        if let Some(id) = &self.pinned {
+            let details = aws::fetch_execution_actions(clients, name, id).await?;
+            pipeline.pin_to(id, &details);
+            pinned = Some((id.as_str(), details));
+        }
+        let executions = *self.executions.lock().unwrap();
+        let fetch = match executions {
+            Executions::Never => false,
+            Executions::New => moved_on(previous, &pipeline),
+            // a fleet pipeline's first poll has nothing to compare with
+            Executions::Changes => {
+                pipeline.stage_states != previous.stage_states || previous.stage_states.is_empty()
+            }
+            Executions::Always => true,
+        };
+        if fetch {
+            pipeline.executions = aws::fetch_executions(clients, name).await?;
+        }
+        if self.mode == ExecutionMode::Parallel {
+            let pinned = pinned
+                .as_ref()
+                .map(|(id, details)| (*id, details.as_slice()));
+            pipeline.concurrent = aws::fetch_concurrent(
+                clients,
+                name,
+                &latest,
+                &pipeline.executions,
+                &previous.concurrent,
+                pinned,
+            )
+            .await;
+        }
+        Ok(pipeline)
+    }
+}
+
+// a stage showing an execution it wasn't showing last poll, and that isn't in the list. One that was already
+// too old to be in the list would otherwise be fetched for on every poll
+fn moved_on(previous: &Pipeline, pipeline: &Pipeline) -> bool {
+    let shown = |pipeline: &Pipeline, id: &str| {
+        pipeline.stage_states.iter().any(|stage| {
+            stage
+                .state
+                .latest_execution
+                .as_ref()
+                .is_some_and(|latest| latest.pipeline_execution_id == id)
+        })
+    };
+    pipeline
+        .stage_states
+        .iter()
+        .filter_map(|stage| stage.state.latest_execution.as_ref())
+        .map(|latest| latest.pipeline_execution_id.as_str())
+        .any(|id| {
+            !shown(previous, id)
+                && !pipeline
+                    .executions
+                    .iter()
+                    .any(|execution| execution.pipeline_execution_id.as_deref() == Some(id))
+        })
+}
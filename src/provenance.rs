@@ -0,0 +1,104 @@
+use crate::aws::ActionExecutionDetailV2;
+use rusoto_codepipeline::{ActionDeclaration, PipelineDeclaration, PipelineExecutionSummary};
+
+// an action in the chain, along with what it produced and what went on to consume that
+#[derive(Clone, Debug)]
+pub struct ActionNode {
+    pub stage: String,
+    pub action: String,
+    pub provider: String,
+    pub status: Option<String>,
+    // the revision for source actions, the external execution ID (build ID, deployment ID, ...) for everything else
+    pub reference: Option<String>,
+    pub outputs: Vec<ArtifactNode>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ArtifactNode {
+    pub name: String,
+    pub consumers: Vec<ActionNode>,
+}
+
+// follow every artifact of an execution from the action that starts it (usually a source) to the actions that use it
+pub fn trace(
+    definition: &PipelineDeclaration,
+    execution: &PipelineExecutionSummary,
+    details: &[ActionExecutionDetailV2],
+) -> Vec<ActionNode> {
+    let actions = definition
+        .stages
+        .iter()
+        .flat_map(|stage| {
+            stage
+                .actions
+                .iter()
+                .map(move |action| (stage.name.as_str(), action))
+        })
+        .collect::<Vec<_>>();
+    actions
+        .iter()
+        .filter(|(_, action)| action.input_artifacts.iter().flatten().next().is_none())
+        .map(|(stage, action)| node(&actions, execution, details, stage, action, actions.len()))
+        .collect()
+}
+
+fn node(
+    actions: &[(&str, &ActionDeclaration)],
+    execution: &PipelineExecutionSummary,
+    details: &[ActionExecutionDetailV2],
+    stage: &str,
+    action: &ActionDeclaration,
+    // a pipeline's artifacts can't loop, but a chain can't be longer than the pipeline either
+    depth: usize,
+) -> ActionNode {
+    // details are newest first, so a retried action's latest attempt wins
+    let detail = details.iter().find(|detail| {
+        detail.detail.stage_name.as_deref() == Some(stage)
+            && detail.detail.action_name.as_deref() == Some(action.name.as_str())
+    });
+    let revision = execution
+        .source_revisions
+        .iter()
+        .flatten()
+        .find(|revision| revision.action_name == action.name)
+        .and_then(|revision| revision.revision_id.clone());
+    let external_id = detail
+        .and_then(|detail| detail.detail.output.as_ref())
+        .and_then(|output| output.execution_result.as_ref())
+        .and_then(|result| result.external_execution_id.clone());
+
+    let outputs = if depth == 0 {
+        vec![]
+    } else {
+        action
+            .output_artifacts
+            .iter()
+            .flatten()
+            .map(|artifact| ArtifactNode {
+                name: artifact.name.clone(),
+                consumers: actions
+                    .iter()
+                    .filter(|(_, consumer)| {
+                        consumer
+                            .input_artifacts
+                            .iter()
+                            .flatten()
+                            .any(|input| input.name == artifact.name)
+                    })
+                    .map(|(stage, consumer)| {
+                        node(actions, execution, details, stage, consumer, depth - 1)
+                    })
+                    .collect(),
+            })
+            .collect()
+    };
+
+    ActionNode {
+        stage: stage.to_owned(),
+        action: action.name.clone(),
+        provider: action.action_type_id.provider.clone(),
+        status: detail.and_then(|detail| detail.detail.status.clone()),
+        reference: revision.or(external_id),
+        outputs,
+    }
+}
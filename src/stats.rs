@@ -0,0 +1,246 @@
+use crate::aws::ActionExecutionDetailV2;
+use rusoto_codepipeline::{PipelineDeclaration, PipelineExecutionSummary};
+
+use std::collections::HashMap;
+
+// how many of each stage's most recent finished runs the statistics cover
+pub const HISTORY_LENGTH: usize = 20;
+
+// a running stage that has taken this many times its median is probably stuck
+pub const ANOMALY_FACTOR: f64 = 1.5;
+
+// one finished run of a stage, pieced together from the executions of its actions
+#[derive(Clone, Debug)]
+pub struct StageRun {
+    pub succeeded: bool,
+    // from the first action starting to the last one finishing
+    pub seconds: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct StageStats {
+    pub stage: String,
+    // oldest first
+    pub runs: Vec<StageRun>,
+}
+
+impl StageStats {
+    pub fn success_rate(&self) -> Option<f64> {
+        if self.runs.is_empty() {
+            return None;
+        }
+        let succeeded = self.runs.iter().filter(|run| run.succeeded).count();
+        Some(succeeded as f64 / self.runs.len() as f64)
+    }
+
+    pub fn average(&self) -> Option<u64> {
+        average(&self.runs)
+    }
+
+    // a handful of runs isn't enough to say what's normal
+    pub fn median(&self) -> Option<u64> {
+        if self.runs.len() < 3 {
+            return None;
+        }
+        let mut seconds = self.runs.iter().map(|run| run.seconds).collect::<Vec<_>>();
+        seconds.sort_unstable();
+        Some(seconds[seconds.len() / 2])
+    }
+
+    // how much slower (positive) or faster (negative) the newer half of the runs are than the older half,
+    // as a fraction of the older half's average
+    pub fn trend(&self) -> Option<f64> {
+        if self.runs.len() < 4 {
+            return None;
+        }
+        let (older, newer) = self.runs.split_at(self.runs.len() / 2);
+        let older = average(older)?;
+        let newer = average(newer)?;
+        if older == 0 {
+            return None;
+        }
+        Some((newer as f64 - older as f64) / older as f64)
+    }
+}
+
+fn average(runs: &[StageRun]) -> Option<u64> {
+    if runs.is_empty() {
+        return None;
+    }
+    Some(runs.iter().map(|run| run.seconds).sum::<u64>() / runs.len() as u64)
+}
+
+// how long each finished execution took, oldest first, given executions newest first the way CodePipeline lists them
+pub fn execution_durations(executions: &[PipelineExecutionSummary]) -> Vec<u64> {
+    executions
+        .iter()
+        .rev()
+        .filter(|execution| execution.status.as_deref() != Some("InProgress"))
+        .filter_map(
+            |execution| match (execution.start_time, execution.last_update_time) {
+                (Some(start), Some(end)) => Some((end - start).max(0.0) as u64),
+                _ => None,
+            },
+        )
+        .collect()
+}
+
+// one stage's run in each of two executions being compared, either missing if it didn't run or hasn't finished
+#[derive(Clone, Debug)]
+pub struct StageComparison {
+    pub stage: String,
+    pub base: Option<StageRun>,
+    pub head: Option<StageRun>,
+}
+
+impl StageComparison {
+    // how much longer (positive) or shorter (negative) the head's run took than the base's
+    pub fn delta(&self) -> Option<i64> {
+        match (&self.base, &self.head) {
+            (Some(base), Some(head)) => Some(head.seconds as i64 - base.seconds as i64),
+            _ => None,
+        }
+    }
+}
+
+// every stage of the pipeline side by side in the two executions, given each execution's action executions
+pub fn compare_stages(
+    definition: &PipelineDeclaration,
+    base: &[ActionExecutionDetailV2],
+    head: &[ActionExecutionDetailV2],
+) -> Vec<StageComparison> {
+    let base = observe(definition, base);
+    let head = observe(definition, head);
+    let run = |observed: &[ObservedRun], stage: &str| {
+        observed
+            .iter()
+            .find(|observed| observed.stage == stage)
+            .map(|observed| observed.run.clone())
+    };
+    definition
+        .stages
+        .iter()
+        .map(|stage| StageComparison {
+            stage: stage.name.clone(),
+            base: run(&base, &stage.name),
+            head: run(&head, &stage.name),
+        })
+        .collect()
+}
+
+// a stage run along with which execution it was and when it started, what the history database keeps
+#[derive(Clone, Debug)]
+pub struct ObservedRun {
+    pub stage: String,
+    pub execution_id: String,
+    pub started: f64,
+    pub run: StageRun,
+}
+
+// work out every stage's recent runs from its actions' executions, in the order the pipeline declares the stages
+pub fn compute(
+    definition: &PipelineDeclaration,
+    details: &[ActionExecutionDetailV2],
+) -> Vec<StageStats> {
+    let observed = observe(definition, details);
+    definition
+        .stages
+        .iter()
+        .map(|stage| {
+            let runs = observed
+                .iter()
+                .filter(|observed| observed.stage == stage.name)
+                .collect::<Vec<_>>();
+            let skip = runs.len().saturating_sub(HISTORY_LENGTH);
+            StageStats {
+                stage: stage.name.clone(),
+                runs: runs
+                    .into_iter()
+                    .skip(skip)
+                    .map(|observed| observed.run.clone())
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+// every finished stage run in these action executions, oldest first
+pub fn observe(
+    definition: &PipelineDeclaration,
+    details: &[ActionExecutionDetailV2],
+) -> Vec<ObservedRun> {
+    let mut observed = vec![];
+    for stage in &definition.stages {
+        // the actions of one run of a stage all share the pipeline execution ID
+        let mut executions: HashMap<&str, Vec<&ActionExecutionDetailV2>> = HashMap::new();
+        for detail in details {
+            if detail.detail.stage_name.as_deref() != Some(stage.name.as_str()) {
+                continue;
+            }
+            if let Some(id) = &detail.detail.pipeline_execution_id {
+                executions.entry(id).or_default().push(detail);
+            }
+        }
+        observed.extend(executions.into_iter().filter_map(|(id, actions)| {
+            let (started, run) = stage_run(&actions)?;
+            Some(ObservedRun {
+                stage: stage.name.clone(),
+                execution_id: id.to_owned(),
+                started,
+                run,
+            })
+        }));
+    }
+    observed.sort_by(|a, b| {
+        a.started
+            .partial_cmp(&b.started)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    observed
+}
+
+// when the given stage's run in these executions started, going by its earliest action
+pub fn stage_start(details: &[ActionExecutionDetailV2], stage: &str) -> Option<f64> {
+    details
+        .iter()
+        .filter(|detail| detail.detail.stage_name.as_deref() == Some(stage))
+        .filter_map(|detail| detail.detail.start_time)
+        .fold(None, |earliest: Option<f64>, start| {
+            Some(earliest.map_or(start, |earliest| earliest.min(start)))
+        })
+}
+
+// the run along with when it started, or nothing if it hasn't finished or was abandoned part way through
+fn stage_run(actions: &[&ActionExecutionDetailV2]) -> Option<(f64, StageRun)> {
+    let statuses = actions
+        .iter()
+        .map(|action| action.detail.status.as_deref())
+        .collect::<Vec<_>>();
+    let succeeded = if statuses.contains(&Some("Failed")) {
+        false
+    } else if statuses.iter().all(|status| *status == Some("Succeeded")) {
+        true
+    } else {
+        return None;
+    };
+
+    let start = actions
+        .iter()
+        .filter_map(|action| action.detail.start_time)
+        .fold(f64::INFINITY, f64::min);
+    let end = actions
+        .iter()
+        .filter_map(|action| action.detail.last_update_time)
+        .fold(f64::NEG_INFINITY, f64::max);
+    if !start.is_finite() || !end.is_finite() {
+        return None;
+    }
+
+    Some((
+        start,
+        StageRun {
+            succeeded,
+            seconds: (end - start).max(0.0) as u64,
+        },
+    ))
+}
@@ -0,0 +1,190 @@
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+use tui::style::Color;
+
+use std::cell::Cell;
+
+// every color the UI uses, by what it means rather than what it looks like
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub colors: Colors,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Colors {
+    // box borders
+    pub accent: Color,
+    // the pane tab has picked out
+    pub focused: Color,
+    // hints, timestamps and anything else that shouldn't draw the eye
+    pub muted: Color,
+    // running things, sparklines and artifacts
+    pub info: Color,
+    pub succeeded: Color,
+    pub failed: Color,
+    // stopped on purpose
+    pub stopped: Color,
+    // overrunning stages and the like
+    pub warning: Color,
+    // any status we don't have a color for
+    pub pending: Color,
+    // everything in bold with heavier status symbols, for low vision and projectors
+    pub bold: bool,
+}
+
+impl Theme {
+    pub fn dark() -> Theme {
+        Theme {
+            name: "dark".to_owned(),
+            colors: Colors {
+                accent: Color::Rgb(255, 178, 102),
+                focused: Color::White,
+                muted: Color::DarkGray,
+                info: Color::LightBlue,
+                succeeded: Color::Green,
+                failed: Color::Red,
+                stopped: Color::Magenta,
+                warning: Color::Yellow,
+                pending: Color::LightYellow,
+                bold: false,
+            },
+        }
+    }
+
+    // only the brightest colors, nothing dim or gray, and all of it bold
+    pub fn high_contrast() -> Theme {
+        Theme {
+            name: "high-contrast".to_owned(),
+            colors: Colors {
+                accent: Color::White,
+                focused: Color::Yellow,
+                muted: Color::White,
+                info: Color::LightCyan,
+                succeeded: Color::LightGreen,
+                failed: Color::LightRed,
+                stopped: Color::LightMagenta,
+                warning: Color::Yellow,
+                pending: Color::LightYellow,
+                bold: true,
+            },
+        }
+    }
+
+    // the dark theme's yellows and light blues all but vanish on a white background
+    pub fn light() -> Theme {
+        Theme {
+            name: "light".to_owned(),
+            colors: Colors {
+                accent: Color::Rgb(166, 86, 0),
+                focused: Color::Black,
+                muted: Color::Gray,
+                info: Color::Blue,
+                succeeded: Color::Rgb(0, 120, 0),
+                failed: Color::Rgb(190, 0, 0),
+                stopped: Color::Magenta,
+                warning: Color::Rgb(180, 120, 0),
+                pending: Color::Rgb(150, 110, 0),
+                bold: false,
+            },
+        }
+    }
+}
+
+// a theme from the config, anything it leaves out comes from the dark theme
+#[derive(Clone, Debug, Deserialize)]
+pub struct CustomTheme {
+    pub name: String,
+    #[serde(default, deserialize_with = "color")]
+    pub accent: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub focused: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub muted: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub info: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub succeeded: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub failed: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub stopped: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub warning: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub pending: Option<Color>,
+    #[serde(default)]
+    pub bold: bool,
+}
+
+impl CustomTheme {
+    pub fn theme(&self) -> Theme {
+        let base = Theme::dark().colors;
+        Theme {
+            name: self.name.clone(),
+            colors: Colors {
+                accent: self.accent.unwrap_or(base.accent),
+                focused: self.focused.unwrap_or(base.focused),
+                muted: self.muted.unwrap_or(base.muted),
+                info: self.info.unwrap_or(base.info),
+                succeeded: self.succeeded.unwrap_or(base.succeeded),
+                failed: self.failed.unwrap_or(base.failed),
+                stopped: self.stopped.unwrap_or(base.stopped),
+                warning: self.warning.unwrap_or(base.warning),
+                pending: self.pending.unwrap_or(base.pending),
+                bold: self.bold,
+            },
+        }
+    }
+}
+
+// "#rrggbb" or one of the terminal's own colors by name, e.g. "lightblue"
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Color>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse_color(&value)
+        .map(Some)
+        .ok_or_else(|| de::Error::custom(format!("{} isn't a color", value)))
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |range| u8::from_str_radix(hex.get(range)?, 16).ok();
+        return Some(Color::Rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?));
+    }
+    Some(match value.to_ascii_lowercase().as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    })
+}
+
+// the drawing code reaches for colors all over the place, so rather than hand the theme down through every
+// helper, `ui::draw` sets it here at the start of each frame
+thread_local! {
+    static CURRENT: Cell<Colors> = Cell::new(Theme::dark().colors);
+}
+
+pub fn set(colors: Colors) {
+    CURRENT.with(|current| current.set(colors))
+}
+
+pub fn colors() -> Colors {
+    CURRENT.with(Cell::get)
+}
@@ -0,0 +1,2825 @@
+use crate::app::{
+    App, Form, FormPurpose, Modal, RollbackPlan, ToastLevel, TypedConfirmation, View,
+    ACTION_COLUMNS,
+};
+use crate::aws::{short_id, ActionExecutionDetailV2, ActionRuns, ExecutionMode, StageStateV2};
+use crate::config::Pane;
+use crate::deploy::DeploymentProgress;
+use crate::git::CommitInfo;
+use crate::logview::LogView;
+use crate::provenance::ActionNode;
+use crate::stats::{execution_durations, StageRun};
+use crate::theme::{self, colors};
+use chrono::{Local, TimeZone};
+use rusoto_cloudformation::StackEvent;
+use rusoto_codebuild::{Build, BuildPhase};
+use rusoto_codepipeline::{
+    ActionState, ExecutionTrigger, PipelineExecutionSummary, StageExecution, StageState,
+};
+use tui::backend::Backend;
+use tui::buffer::Buffer;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Row, Table, Wrap};
+use tui::widgets::{BorderType, Widget};
+use tui::Frame;
+
+use std::collections::HashMap;
+
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &App) {
+    theme::set(app.theme().colors);
+
+    // leave a line at the bottom for key hints and errors, and one at the top of the detail views for where we are
+    let header = if matches!(app.view, View::Pipelines | View::Fleet) {
+        0
+    } else {
+        1
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(header),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+    let body = chunks[1];
+
+    draw_breadcrumbs(f, chunks[0], app);
+
+    match app.view {
+        View::Pipelines => draw_pipelines(f, body, app),
+        View::Fleet => draw_fleet(f, body, app),
+        View::Stages => draw_stages(f, body, app),
+        View::History => draw_history(f, body, app, false),
+        View::Stats => draw_stats(f, body, app),
+        View::Compare => draw_comparison(f, body, app),
+        View::Provenance => draw_provenance(f, body, app),
+        View::Timeline => draw_timeline(f, body, app),
+        View::Action => draw_action(f, body, app),
+        View::BuildLog => match &app.build_log {
+            Some(log) => draw_log(
+                f,
+                body,
+                // so it's clear the top of the log isn't the start of the build
+                &match log.dropped {
+                    0 => format!("Build {}", log.build_id),
+                    dropped => {
+                        format!("Build {} ({} earlier lines dropped)", log.build_id, dropped)
+                    }
+                },
+                &log.lines,
+                &app.log_view,
+                &|_| None,
+            ),
+            None => f.render_widget(
+                Paragraph::new("Loading...").block(pane_block("Build", false)),
+                body,
+            ),
+        },
+        View::AppLog => draw_log(f, body, "Log", &app.app_log, &app.log_view, &|_| None),
+        View::Inspector => {
+            let (lines, _) = app.calls.lines();
+            // the calls that didn't go through, so they stand out among the bodies
+            let failed = |index: usize| {
+                lines
+                    .get(index)
+                    .filter(|line| !line.starts_with(' ') && line.contains(" failed "))
+                    .map(|_| colors().failed)
+            };
+            draw_log(f, body, "API calls", &lines, &app.log_view, &failed)
+        }
+        View::Bookmarks => draw_bookmarks(f, body, app),
+        View::Notifications => draw_log(
+            f,
+            body,
+            "Notifications",
+            &app.notification_lines(),
+            &app.log_view,
+            &|index| app.toasts.get(index).map(|toast| toast_color(toast.level)),
+        ),
+    }
+    draw_footer(f, chunks[2], app);
+
+    draw_toasts(f, body, app);
+    if app.debug {
+        draw_debug(f, body, app);
+    }
+
+    // drawn last so it sits on top of whatever view is underneath
+    if let Some(modal) = &app.modal {
+        draw_modal(f, f.size(), app, modal);
+    }
+    if colors().bold {
+        f.render_widget(Embolden, f.size());
+    }
+}
+
+// goes over everything that's already been drawn and makes it bold
+struct Embolden;
+
+impl Widget for Embolden {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                cell.modifier.insert(Modifier::BOLD);
+            }
+        }
+    }
+}
+
+// a box in the middle of `area`, for popups
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+// recent notifications stacked in the top right corner, newest at the bottom
+fn draw_toasts<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let mut y = area.y + 1;
+    for toast in app.visible_toasts() {
+        let width = (toast.message.chars().count() as u16 + 4)
+            .min(area.width / 2)
+            .max(10);
+        if y + 3 > area.y + area.height || width + 1 > area.width {
+            break;
+        }
+        let color = toast_color(toast.level);
+        let rect = Rect::new(area.x + area.width - width - 1, y, width, 3);
+        f.render_widget(Clear, rect);
+        f.render_widget(
+            Paragraph::new(toast.message.as_str()).block(
+                Block::default()
+                    .border_type(BorderType::Thick)
+                    .border_style(Style::default().fg(color))
+                    .borders(Borders::ALL),
+            ),
+            rect,
+        );
+        y += 3;
+    }
+}
+
+// F12: every AWS API called in the last minute, how long it took and how often it's had to be retried, for
+// working out why refreshes are slow or being throttled
+fn draw_debug<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let apis = app.calls.summary();
+    let rows = apis.iter().map(|api| {
+        let cells = vec![
+            api.api.clone(),
+            api.calls.to_string(),
+            format!("{}ms", api.latency.as_millis()),
+            format!("{}ms", api.slowest.as_millis()),
+            api.retries.to_string(),
+        ];
+        // still not going through
+        if api.retries > 0 || api.failed > 0 {
+            Row::StyledData(cells.into_iter(), Style::default().fg(colors().failed))
+        } else {
+            Row::Data(cells.into_iter())
+        }
+    });
+    // the borders, the header and the gap under it, and room for a row even before anything's been called
+    let height = apis.len().max(1) as u16 + 4;
+    let popup = centered_rect(area.width.saturating_sub(4).min(80), height, area);
+    // the API gets whatever the numbers and the spaces between the columns leave, spelled out since a Min
+    // leaves the layout free to split any spare room differently from one frame to the next
+    let widths = [
+        Constraint::Length(popup.width.saturating_sub(2 + 5 + 8 + 8 + 7 + 4 * 2)),
+        Constraint::Length(5),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(7),
+    ];
+    let title = format!(
+        "AWS calls: {} in the last minute (F12 to close)",
+        app.calls.per_minute()
+    );
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Table::new(
+            ["API", "Calls", "Latest", "Slowest", "Retries"].iter(),
+            rows,
+        )
+        .header_style(Style::default().add_modifier(Modifier::BOLD))
+        .widths(&widths)
+        .column_spacing(2)
+        .block(
+            Block::default()
+                .title(Span {
+                    content: title.into(),
+                    style: Style::default().add_modifier(Modifier::BOLD),
+                })
+                .border_type(BorderType::Thick)
+                .border_style(Style::default().fg(colors().accent))
+                .borders(Borders::ALL),
+        ),
+        popup,
+    );
+}
+
+fn toast_color(level: ToastLevel) -> Color {
+    match level {
+        ToastLevel::Info => colors().info,
+        ToastLevel::Success => colors().succeeded,
+        ToastLevel::Error => colors().failed,
+    }
+}
+
+// every popup is the same bordered box cleared over the view, only what's inside differs
+fn draw_modal<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App, modal: &Modal) {
+    let (title, color, lines) = match modal {
+        Modal::Form(form) => {
+            let (title, lines) = form_lines(app, form);
+            (title.to_owned(), colors().accent, lines)
+        }
+        // going backwards is the riskier thing to confirm
+        Modal::Rollback(plan) => (
+            "Rollback".to_owned(),
+            colors().failed,
+            rollback_lines(app, plan),
+        ),
+        Modal::Message { title, text } => {
+            let mut lines = text
+                .lines()
+                .map(|line| Spans::from(line.to_owned()))
+                .collect::<Vec<_>>();
+            lines.push(Spans::from(""));
+            lines.push(Spans::from(Span::styled(
+                "esc: close",
+                Style::default().fg(colors().muted),
+            )));
+            (title.clone(), colors().failed, lines)
+        }
+        Modal::Typed(typed) => (
+            format!("Confirm {}", typed.action),
+            colors().failed,
+            typed_lines(app, typed),
+        ),
+        Modal::Reauth => (
+            "Credentials expired".to_owned(),
+            colors().warning,
+            vec![
+                Spans::from("The AWS credentials have expired, so nothing can be refreshed."),
+                Spans::from(""),
+                Spans::from(vec![
+                    Span::raw("Sign in again with "),
+                    Span::styled(
+                        app.reauth.clone(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw("? The dashboard comes back once it's done."),
+                ]),
+                Spans::from(""),
+                Spans::from(Span::styled(
+                    "y/enter: sign in  n/esc: not now (e brings this back)",
+                    Style::default().fg(colors().muted),
+                )),
+            ],
+        ),
+    };
+
+    // long lines wrap, so leave room for them
+    let width = area.width.saturating_sub(4).clamp(20, 70);
+    let inner = width.saturating_sub(2).max(1) as usize;
+    let height = lines
+        .iter()
+        .map(|line| line.width().div_ceil(inner).max(1) as u16)
+        .sum::<u16>();
+    let popup = centered_rect(width, height + 2, area);
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .title(Span {
+                    content: title.into(),
+                    style: Style::default().add_modifier(Modifier::BOLD),
+                })
+                .border_type(BorderType::Thick)
+                .border_style(Style::default().fg(color))
+                .borders(Borders::ALL),
+        ),
+        popup,
+    );
+}
+
+// a y/n is too easy to give without looking, so production makes you type the name
+fn typed_lines(app: &App, typed: &TypedConfirmation) -> Vec<Spans<'static>> {
+    let color = if typed.typed == typed.expected {
+        colors().succeeded
+    } else {
+        colors().failed
+    };
+    vec![
+        Spans::from(format!("{} is a production pipeline.", app.pipeline.name)),
+        Spans::from(vec![
+            Span::raw("Type "),
+            Span::styled(
+                typed.expected.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(" to {}:", typed.action.replace('-', " "))),
+        ]),
+        Spans::from(""),
+        Spans::from(vec![
+            Span::raw("> "),
+            Span::styled(typed.typed.clone(), Style::default().fg(color)),
+            Span::raw("_"),
+        ]),
+        Spans::from(""),
+        Spans::from(Span::styled(
+            "enter: confirm  esc: cancel",
+            Style::default().fg(colors().muted),
+        )),
+    ]
+}
+
+// say exactly what the stage will go back to before anything happens
+fn rollback_lines(app: &App, plan: &RollbackPlan) -> Vec<Spans<'static>> {
+    let target = &plan.target;
+    let mut lines = vec![
+        Spans::from(format!("Roll {} back to execution", plan.stage)),
+        Spans::from(vec![
+            Span::styled(
+                target
+                    .pipeline_execution_id
+                    .as_deref()
+                    .map_or("?", short_id)
+                    .to_owned(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                " from {}?",
+                target.start_time.map_or("-".to_owned(), format_timestamp)
+            )),
+        ]),
+        Spans::from(""),
+        Spans::from("This will restore:"),
+    ];
+    for revision in target.source_revisions.iter().flatten() {
+        let id = revision
+            .revision_id
+            .as_deref()
+            .unwrap_or("unknown revision");
+        lines.push(Spans::from(format!(
+            "  {} @ {}",
+            revision.action_name,
+            id.get(..7).unwrap_or(id)
+        )));
+        if let Some(commit) = app.commits.get(id) {
+            lines.push(Spans::from(Span::styled(
+                format!("    {}", commit.message),
+                Style::default().fg(colors().muted),
+            )));
+        }
+    }
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(Span::styled(
+        "y: roll back  n: cancel",
+        Style::default().fg(colors().muted),
+    )));
+
+    lines
+}
+
+// the form's title and lines
+fn form_lines(app: &App, form: &Form) -> (&'static str, Vec<Spans<'static>>) {
+    let (title, prompt, hint) = match &form.purpose {
+        FormPurpose::StartExecution => (
+            "Start execution",
+            format!("Start a new execution of {}?", app.pipeline.name),
+            if form.fields.is_empty() {
+                "enter: start  esc: cancel"
+            } else {
+                "enter: start  esc: cancel  tab: next variable"
+            },
+        ),
+        FormPurpose::DisableTransition(stage) => (
+            "Disable transition",
+            format!("Stop executions from moving into {}?", stage),
+            "enter: disable  esc: cancel",
+        ),
+        FormPurpose::Annotate(execution_id) => (
+            "Note",
+            format!("A note on execution {}", short_id(execution_id)),
+            "enter: save  esc: cancel",
+        ),
+        FormPurpose::Approve { stage, action } => (
+            "Approve",
+            format!("Approve {} in {}?", action, stage),
+            "enter: approve  esc: cancel",
+        ),
+        FormPurpose::Reject { stage, action } => (
+            "Reject",
+            format!("Reject {} in {}?", action, stage),
+            "enter: reject  esc: cancel",
+        ),
+        FormPurpose::StopExecution(execution_id) => (
+            "Stop execution",
+            format!(
+                "Stop execution {} of {}?",
+                short_id(execution_id),
+                app.pipeline.name
+            ),
+            "enter: stop  esc: cancel",
+        ),
+    };
+    let mut lines = vec![Spans::from(prompt), Spans::from("")];
+    for (index, field) in form.fields.iter().enumerate() {
+        let focused = index == form.focused;
+        lines.push(Spans::from(vec![
+            Span::styled(
+                format!("{}: ", field.name),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                // a cursor so it's clear where typing goes
+                if focused {
+                    format!("{}_", field.value)
+                } else {
+                    field.value.clone()
+                },
+                if focused {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                },
+            ),
+        ]));
+        if let Some(description) = &field.description {
+            lines.push(Spans::from(Span::styled(
+                format!("  {}", description),
+                Style::default().fg(colors().muted),
+            )));
+        }
+    }
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(Span::styled(
+        hint,
+        Style::default().fg(colors().muted),
+    )));
+
+    (title, lines)
+}
+
+fn draw_stages<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let executions = &app.pipeline.executions;
+    let now = Local::now().timestamp() as f64;
+
+    let mut stages_title = match &app.pinned_execution {
+        Some(id) => format!("Stages (execution {})", short_id(id)),
+        None => "Stages".to_owned(),
+    };
+    // recent execution durations, so a pipeline getting slower is obvious at a glance
+    let durations = execution_durations(executions);
+    if !durations.is_empty() {
+        stages_title.push_str(&format!(" {}", sparkline(&durations)));
+    }
+    // so a stage the filter hides isn't forgotten about altogether
+    let hidden = app.pipeline.stage_states.len() - app.filtered_stages().len();
+    if hidden > 0 {
+        stages_title.push_str(&format!(" ({} hidden)", hidden));
+    }
+    // superseding is what everyone expects, anything else changes what the stages mean
+    if app.execution_mode != ExecutionMode::Superseded {
+        stages_title.push_str(&format!(" [{}]", app.execution_mode.label()));
+    }
+    // zoomed in, the focused pane gets the lot and the rest aren't drawn at all
+    let panes = match app.layout.get(app.focused_pane) {
+        Some(pane) if app.zoomed => vec![(app.focused_pane, pane)],
+        _ => app.layout.iter().enumerate().collect::<Vec<_>>(),
+    };
+    // the panes get their share of the space in the order the config lists them
+    let total = panes.iter().map(|(_, pane)| pane.size).sum::<u32>().max(1);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            panes
+                .iter()
+                .map(|(_, pane)| Constraint::Ratio(pane.size, total))
+                .collect::<Vec<_>>(),
+        )
+        .split(area);
+    for ((index, pane), chunk) in panes.into_iter().zip(chunks) {
+        // only worth picking out the focused one when there's more than one to choose from
+        let focused = index == app.focused_pane && app.layout.len() > 1;
+        match pane.pane {
+            Pane::Stages => draw_stage_boxes(f, chunk, app, &stages_title, focused, now),
+            Pane::Commits => draw_commits(f, chunk, app, focused),
+            Pane::History => draw_history(f, chunk, app, focused),
+            Pane::Notifications => draw_notifications(f, chunk, app, focused),
+        }
+    }
+}
+
+// a bordered, titled box around one of the panes, white rather than orange when tab has picked it
+fn pane_block(title: &str, focused: bool) -> Block<'static> {
+    Block::default()
+        .title(Span {
+            content: title.to_owned().into(),
+            style: Style::default().add_modifier(Modifier::BOLD),
+        })
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().fg(if focused {
+            colors().focused
+        } else {
+            colors().accent
+        }))
+        .borders(Borders::ALL)
+}
+
+fn draw_stage_boxes<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    app: &App,
+    title: &str,
+    focused: bool,
+    now: f64,
+) {
+    let visible = app.visible_stages();
+    let stage_states = visible
+        .iter()
+        .map(|index| &app.pipeline.stage_states[*index])
+        .collect::<Vec<_>>();
+    f.render_widget(pane_block(title, focused), area);
+    // a parallel pipeline's running executions get a row each above the stages, since the stages themselves can only
+    // say which of them got to each one last
+    let area = if app.execution_mode == ExecutionMode::Parallel {
+        let rows = running_execution_rows(app, area.height.saturating_sub(2) / 3);
+        f.render_widget(
+            Paragraph::new(rows.clone()),
+            Rect::new(
+                area.x + 1,
+                area.y + 1,
+                area.width.saturating_sub(2),
+                rows.len() as u16,
+            ),
+        );
+        Rect {
+            y: area.y + rows.len() as u16,
+            height: area.height.saturating_sub(rows.len() as u16),
+            ..area
+        }
+    } else {
+        area
+    };
+    if stage_states.is_empty() {
+        let message = if app.filtered_stages().is_empty() {
+            "Every stage is hidden by the stage filter"
+        } else {
+            "Every stage is green (! shows them again)"
+        };
+        f.render_widget(
+            Paragraph::new(message),
+            Layout::default()
+                .margin(1)
+                .constraints([Constraint::Min(0)])
+                .split(area)[0],
+        );
+        return;
+    }
+
+    stage_states
+        .iter()
+        .zip(
+            // each stage will get a Rect
+            Layout::default()
+                // fill up the space from left to right
+                .direction(Direction::Horizontal)
+                .margin(1)
+                .constraints(
+                    // as above, each Rect will take up a fraction of the space equal to 1/len
+                    (0..stage_states.len())
+                        .map(|_| Constraint::Ratio(1, stage_states.len() as u32))
+                        .collect::<Vec<_>>(),
+                )
+                // the space we're filling up is the first section (the "Stages" chunk) instead of the entire terminal window
+                .split(area),
+        )
+        // render each stage
+        .for_each(|(stage, chunk)| {
+            let StageStateV2 { state, .. } = stage;
+            f.render_widget(
+                Block::default()
+                    .title(Span {
+                        content: state.clone().stage_name.unwrap().into(),
+                        style: Style::default().add_modifier(Modifier::BOLD),
+                    })
+                    .border_type(BorderType::Thick)
+                    .borders(Borders::ALL)
+                    .border_style(
+                        Style::default().fg(match state.to_owned().latest_execution {
+                            // past what the [sla] allows is as bad as failing, it's likely hung
+                            Some(_) if app.over_sla(stage, now).is_some() => colors().failed,
+                            // a stage taking far longer than usual is worth a warning before it times out
+                            Some(_) if app.overrunning(stage, now).is_some() => colors().warning,
+                            // deployed fine, but the service it deployed isn't happy
+                            Some(_) if alarming(app, stage) => colors().warning,
+                            // nothing's wrong with the stage itself, a gate is keeping the execution out
+                            Some(_) if stage.blocked_by_conditions().is_some() => colors().warning,
+                            // if we can get a valid execution state, match on it
+                            Some(StageExecution { status, .. }) => status_color(&status),
+                            // default to red whenever we can't get the execution state
+                            _ => colors().failed,
+                        }),
+                    ),
+                chunk,
+            )
+        });
+
+    // over the borders between each pair of stages, show whether executions can move from one into the next
+    stage_states
+        .iter()
+        .zip(
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .margin(1)
+                .constraints(
+                    (0..stage_states.len())
+                        .map(|_| Constraint::Ratio(1, stage_states.len() as u32))
+                        .collect::<Vec<_>>(),
+                )
+                .split(area),
+        )
+        // the first stage has nothing coming into it
+        .skip(1)
+        .for_each(|(stage, chunk)| {
+            let (symbol, color) = if transition_enabled(&stage.state) {
+                ("▶▶", colors().succeeded)
+            } else {
+                ("🔒", colors().failed)
+            };
+            f.render_widget(
+                Paragraph::new(Span::styled(
+                    symbol,
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                )),
+                Rect::new(chunk.x.saturating_sub(1), chunk.y + chunk.height / 2, 2, 1),
+            )
+        });
+
+    // write out what each stage is running and what's queued up behind it inside the stage boxes
+    stage_states
+        .iter()
+        .zip(
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .margin(2)
+                .constraints(
+                    (0..stage_states.len())
+                        .map(|_| Constraint::Ratio(1, stage_states.len() as u32))
+                        .collect::<Vec<_>>(),
+                )
+                .split(area),
+        )
+        .enumerate()
+        .for_each(|(index, (stage, chunk))| {
+            let selected = if visible[index] == app.selected_stage {
+                Some(app.selected_action)
+            } else {
+                None
+            };
+            f.render_widget(
+                Paragraph::new(stage_execution_lines(
+                    app,
+                    stage,
+                    selected,
+                    app.overrunning(stage, now),
+                    app.over_sla(stage, now),
+                )),
+                // stay inside the stage's border
+                Layout::default()
+                    .margin(1)
+                    .constraints([Constraint::Min(0)])
+                    .split(chunk)[0],
+            )
+        });
+}
+
+// "▶ 1a2b3c4d InProgress  running 12m04s  @ 4f2a9c1  Source ✓ Build ● Prod ·", one for each execution still
+// going, as many as fit, with ▶ on the one ] and [ have the stages showing
+fn running_execution_rows(app: &App, fit: u16) -> Vec<Spans<'static>> {
+    let running = app.pipeline.running_executions();
+    if running.is_empty() {
+        return vec![];
+    }
+    let fit = (fit as usize).max(1);
+    let shown = if running.len() > fit {
+        fit - 1
+    } else {
+        running.len()
+    };
+    let mut rows = running
+        .iter()
+        .take(shown)
+        .map(|execution| {
+            let status = execution.status.as_deref().unwrap_or("Unknown");
+            let id = execution.pipeline_execution_id.as_deref().unwrap_or("?");
+            let focused = app.pinned_execution.as_deref() == Some(id);
+            let mut id_style = Style::default();
+            if focused {
+                id_style = id_style.add_modifier(Modifier::REVERSED);
+            }
+            let mut spans = vec![
+                Span::raw(if focused { "▶ " } else { "  " }),
+                Span::styled(short_id(id).to_owned(), id_style),
+                Span::raw(" "),
+                Span::styled(
+                    format!("{:<13} ", status_label(status)),
+                    Style::default().fg(status_color(status)),
+                ),
+                Span::raw(format!(
+                    "running {:>8}  {}  ",
+                    execution_duration(execution),
+                    revision_label(execution)
+                )),
+            ];
+            // the poll after it started fills these in
+            if let Some(concurrent) = app.pipeline.concurrent_execution(id) {
+                for index in app.visible_stages() {
+                    // edited since, and the poll hasn't caught up
+                    let stage = match concurrent.stage_states.get(index) {
+                        Some(stage) => &stage.state,
+                        None => continue,
+                    };
+                    let status = stage
+                        .latest_execution
+                        .as_ref()
+                        .map_or("", |execution| execution.status.as_str());
+                    spans.push(Span::raw(format!(
+                        " {} ",
+                        stage.stage_name.as_deref().unwrap_or("?")
+                    )));
+                    spans.push(Span::styled(
+                        status_symbol(status),
+                        Style::default().fg(status_color(status)),
+                    ));
+                }
+            }
+            Spans::from(spans)
+        })
+        .collect::<Vec<_>>();
+    if shown < running.len() {
+        rows.push(Spans::from(Span::styled(
+            format!(
+                "  and {} more running (h has them all)",
+                running.len() - shown
+            ),
+            Style::default().fg(colors().muted),
+        )));
+    }
+    rows
+}
+
+fn draw_commits<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App, focused: bool) {
+    let stage_states = app
+        .visible_stages()
+        .into_iter()
+        .map(|index| &app.pipeline.stage_states[index])
+        .collect::<Vec<_>>();
+    let executions = &app.pipeline.executions;
+    f.render_widget(pane_block("Commits", focused), area);
+
+    // the latest execution's variables go across the top of the commits section, since they're part of what it's running
+    let mut commits_area = Layout::default()
+        .margin(1)
+        .constraints([Constraint::Min(0)])
+        .split(area)[0];
+    if let Some((_, variables)) = &app.execution_variables {
+        if !variables.is_empty() {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(commits_area);
+            let mut spans = vec![Span::styled(
+                "Variables: ",
+                Style::default().add_modifier(Modifier::BOLD),
+            )];
+            spans.extend(variables.iter().map(|variable| {
+                Span::raw(format!("{}={}  ", variable.name, variable.resolved_value))
+            }));
+            f.render_widget(Paragraph::new(Spans::from(spans)), rows[0]);
+            commits_area = rows[1];
+        }
+    }
+
+    // do the same as above, but this is a structural layout that we'll use for organizing data rather than painting a diagram
+    // so no borders/fancy colors are needed
+    // also, we're putting it in a different section
+    stage_states
+        .iter()
+        .zip(
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .margin(0)
+                .constraints(
+                    (0..stage_states.len())
+                        .map(|_| Constraint::Ratio(1, stage_states.len() as u32))
+                        .collect::<Vec<_>>(),
+                )
+                .split(commits_area),
+        )
+        .for_each(|(stage, chunk)| {
+            f.render_widget(
+                Paragraph::new(stage_revision_lines(stage, executions, &app.commits))
+                    .block(Block::default().borders(Borders::NONE)),
+                chunk,
+            )
+        });
+}
+
+// a scrolled window onto `lines`, with the search's matches picked out and each line in `line_color` if it has one
+fn draw_log<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    title: &str,
+    lines: &[String],
+    view: &LogView,
+    line_color: &dyn Fn(usize) -> Option<Color>,
+) {
+    let mut height = area.height.saturating_sub(2) as usize;
+    // the search being typed takes the bottom line
+    if view.typing.is_some() {
+        height = height.saturating_sub(1);
+    }
+    let end = lines.len().saturating_sub(view.offset);
+    let start = end.saturating_sub(height);
+
+    let query = view.query.as_ref().map(|query| query.to_ascii_lowercase());
+    let mut text = lines[start..end]
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let current = view.current_match == Some(start + index);
+            let style = line_color(start + index)
+                .map_or(Style::default(), |color| Style::default().fg(color));
+            match &query {
+                Some(query) => highlight(line, query, current, style),
+                None => Spans::from(Span::styled(line.clone(), style)),
+            }
+        })
+        .collect::<Vec<_>>();
+    if let Some(typing) = &view.typing {
+        text.push(Spans::from(Span::styled(
+            format!("/{}_", typing),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+    }
+
+    let mut title = if lines.is_empty() {
+        format!("{} (nothing yet)", title)
+    } else {
+        format!("{} ({}-{} of {})", title, start + 1, end, lines.len())
+    };
+    title.push_str(if view.follow {
+        " [following, f to freeze]"
+    } else {
+        " [frozen, f to follow]"
+    });
+    if let Some(query) = &view.query {
+        let matches = view.matches(lines);
+        let position = view
+            .current_match
+            .and_then(|current| matches.iter().position(|line| *line == current))
+            .map_or("-".to_owned(), |position| (position + 1).to_string());
+        title.push_str(&format!(" /{} {} of {}", query, position, matches.len()));
+    }
+    f.render_widget(Paragraph::new(text).block(pane_block(&title, false)), area);
+}
+
+// every case-insensitive occurrence of `query` in black on yellow, the match we jumped to in bold too
+fn highlight(line: &str, query: &str, current: bool, style: Style) -> Spans<'static> {
+    let mut match_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+    if current {
+        match_style = match_style.add_modifier(Modifier::BOLD);
+    }
+    let lower = line.to_ascii_lowercase();
+    let mut spans = vec![];
+    let mut last = 0;
+    for (index, found) in lower.match_indices(query) {
+        spans.push(Span::styled(line[last..index].to_owned(), style));
+        spans.push(Span::styled(
+            line[index..index + found.len()].to_owned(),
+            match_style,
+        ));
+        last = index + found.len();
+    }
+    spans.push(Span::styled(line[last..].to_owned(), style));
+    Spans::from(spans)
+}
+
+// everything we've told the user about so far, newest first
+fn draw_notifications<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App, focused: bool) {
+    let items = app
+        .toasts
+        .iter()
+        .rev()
+        .map(|toast| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(
+                    format!("{}  ", toast.at.format("%H:%M:%S")),
+                    Style::default().fg(colors().muted),
+                ),
+                Span::styled(
+                    toast.message.clone(),
+                    Style::default().fg(toast_color(toast.level)),
+                ),
+            ]))
+        })
+        .collect::<Vec<_>>();
+    f.render_widget(
+        List::new(items).block(pane_block("Notifications", focused)),
+        area,
+    );
+}
+
+// every pipeline we can look at, with its tags alongside so you can tell whose is whose
+fn draw_pipelines<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let name_width = app
+        .pipelines
+        .iter()
+        .map(|listing| listing.name.len())
+        .max()
+        .unwrap_or(0);
+    let items = app
+        .visible_pipelines()
+        .into_iter()
+        .map(|index| {
+            let listing = &app.pipelines[index];
+            let status = match (&listing.latest_status, app.loading) {
+                (Some(status), _) => status.as_str(),
+                (None, true) => "Loading",
+                (None, false) => "Never run",
+            };
+            let style = if index == app.selected_pipeline {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Spans::from(vec![
+                Span::styled(
+                    format!("{:<width$}  ", listing.name, width = name_width),
+                    style,
+                ),
+                Span::styled(
+                    format!("{:<13} ", status_label(status)),
+                    Style::default().fg(status_color(status)),
+                ),
+                // padded so the tags line up whatever the pipeline's history
+                Span::styled(
+                    format!("{:<20}  ", sparkline(&listing.durations)),
+                    Style::default().fg(colors().info),
+                ),
+                Span::styled(
+                    listing
+                        .tags
+                        .iter()
+                        .map(|(key, value)| format!("{}={}", key, value))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    Style::default().fg(colors().muted),
+                ),
+            ]))
+        })
+        .collect::<Vec<_>>();
+
+    f.render_widget(
+        List::new(items).block(
+            Block::default()
+                .title(Span {
+                    content: match (app.problems_only, app.loading) {
+                        (_, true) => "Pipelines (loading…)".into(),
+                        (true, false) => "Pipelines (problems only)".into(),
+                        (false, false) => "Pipelines".into(),
+                    },
+                    style: Style::default().add_modifier(Modifier::BOLD),
+                })
+                .border_type(BorderType::Thick)
+                .border_style(Style::default().fg(colors().accent))
+                .borders(Borders::ALL),
+        ),
+        area,
+    );
+}
+
+// the fleet grid's cells, wide enough for most pipeline names and tall enough to read from across the room
+const FLEET_CELL_WIDTH: u16 = 24;
+const FLEET_CELL_HEIGHT: u16 = 3;
+
+// how many cells fit across a terminal this wide, inside the box and with a gap between each
+pub fn fleet_columns(width: u16) -> usize {
+    (width.saturating_sub(2) / (FLEET_CELL_WIDTH + 1)).max(1) as usize
+}
+
+// every pipeline as a cell colored by how its latest execution went, under a line counting them up
+fn draw_fleet<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let title = match (app.problems_only, app.loading) {
+        (_, true) => "Fleet (loading…)",
+        (true, false) => "Fleet (problems only)",
+        (false, false) => "Fleet",
+    };
+    let block = Block::default()
+        .title(Span {
+            content: format!("{}: {}", title, app.breadcrumbs().join(" ")).into(),
+            style: Style::default().add_modifier(Modifier::BOLD),
+        })
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().fg(colors().accent))
+        .borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    if inner.height < 2 {
+        return;
+    }
+
+    f.render_widget(
+        Paragraph::new(fleet_summary(app, Local::now().timestamp() as f64)),
+        Rect::new(inner.x, inner.y, inner.width, 1),
+    );
+
+    // scrolled down just far enough to keep the selected cell on screen
+    let grid = Rect::new(
+        inner.x,
+        inner.y + 2,
+        inner.width,
+        inner.height.saturating_sub(2),
+    );
+    let columns = fleet_columns(area.width);
+    let rows = (grid.height / (FLEET_CELL_HEIGHT + 1)).max(1) as usize;
+    let visible = app.visible_pipelines();
+    let selected = visible
+        .iter()
+        .position(|index| *index == app.selected_pipeline)
+        .unwrap_or(0);
+    let first_row = (selected / columns).saturating_sub(rows - 1);
+    for (position, index) in visible.iter().enumerate().skip(first_row * columns) {
+        let row = (position / columns - first_row) as u16;
+        let column = (position % columns) as u16;
+        let y = grid.y + row * (FLEET_CELL_HEIGHT + 1);
+        if y + FLEET_CELL_HEIGHT > grid.y + grid.height {
+            break;
+        }
+        // a terminal narrower than a cell still gets one column, cut off at the border rather than drawn past it
+        let x = grid.x + column * (FLEET_CELL_WIDTH + 1);
+        if x >= grid.right() {
+            continue;
+        }
+        let cell = Rect::new(
+            x,
+            y,
+            FLEET_CELL_WIDTH.min(grid.right() - x),
+            FLEET_CELL_HEIGHT,
+        );
+        let listing = &app.pipelines[*index];
+        let status = match (&listing.latest_status, app.loading) {
+            (Some(status), _) => status.as_str(),
+            (None, true) => "Loading",
+            (None, false) => "Never run",
+        };
+        let mut style = Style::default().fg(Color::Black).bg(status_color(status));
+        if *index == app.selected_pipeline {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        let width = FLEET_CELL_WIDTH as usize - 2;
+        let name = if listing.name.chars().count() > width {
+            format!(
+                "{}…",
+                listing.name.chars().take(width - 1).collect::<String>()
+            )
+        } else {
+            listing.name.clone()
+        };
+        f.render_widget(
+            Paragraph::new(vec![
+                Spans::from(Span::styled(
+                    format!(" {:<width$} ", name, width = width),
+                    style.add_modifier(Modifier::BOLD),
+                )),
+                Spans::from(Span::styled(
+                    format!(" {:<width$} ", status_label(status), width = width),
+                    style,
+                )),
+                Spans::from(Span::styled(" ".repeat(width + 2), style)),
+            ]),
+            cell,
+        );
+    }
+}
+
+// the counts go from what most needs looking at to what least does
+// the whole fleet in a line: how many are green, red and going, and which has been going the longest
+fn fleet_summary(app: &App, now: f64) -> Spans<'static> {
+    let count = |wanted: &str| {
+        app.pipelines
+            .iter()
+            .filter(|listing| listing.latest_status.as_deref() == Some(wanted))
+            .count()
+    };
+    let (green, red, running) = (count("Succeeded"), count("Failed"), count("InProgress"));
+    let mut summary = vec![
+        Span::styled(
+            format!("{} pipelines", app.pipelines.len()),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("   {} green", green),
+            Style::default().fg(status_color("Succeeded")),
+        ),
+        Span::styled(
+            format!("   {} red", red),
+            Style::default().fg(status_color("Failed")),
+        ),
+        Span::styled(
+            format!("   {} in progress", running),
+            Style::default().fg(status_color("InProgress")),
+        ),
+    ];
+    // stopped, superseded, never run...
+    let other = app.pipelines.len() - green - red - running;
+    if other > 0 {
+        summary.push(Span::styled(
+            format!("   {} other", other),
+            Style::default().fg(colors().muted),
+        ));
+    }
+    let oldest = app
+        .pipelines
+        .iter()
+        .filter_map(|listing| Some((listing, listing.running_since?)))
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    if let Some((listing, since)) = oldest {
+        summary.push(Span::raw(format!(
+            "   longest running: {} for {}",
+            listing.name,
+            format_duration((now - since).max(0.0) as u64)
+        )));
+    }
+    Spans::from(summary)
+}
+
+// one line per recent execution, newest first
+fn draw_history<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App, focused: bool) {
+    let items = app
+        .pipeline
+        .executions
+        .iter()
+        .enumerate()
+        .map(|(index, execution)| {
+            let status = execution.status.as_deref().unwrap_or("Unknown");
+            let mut id_style = Style::default();
+            if index == app.selected_execution {
+                id_style = id_style.add_modifier(Modifier::REVERSED);
+            }
+            ListItem::new(Spans::from(vec![
+                // the execution marked for comparing gets a star
+                Span::raw(if app.marked_execution == Some(index) {
+                    "* "
+                } else {
+                    "  "
+                }),
+                Span::styled(
+                    if execution
+                        .pipeline_execution_id
+                        .as_deref()
+                        .is_some_and(|id| app.is_bookmarked(id))
+                    {
+                        "★ "
+                    } else {
+                        "  "
+                    },
+                    Style::default().fg(colors().accent),
+                ),
+                Span::styled(
+                    short_id(execution.pipeline_execution_id.as_deref().unwrap_or("?")).to_owned(),
+                    id_style,
+                ),
+                Span::raw("  "),
+                Span::styled(
+                    format!("{:<13} ", status_label(status)),
+                    Style::default().fg(status_color(status)),
+                ),
+                Span::raw(format!(
+                    "{}  {:>8}  {}  ",
+                    execution
+                        .start_time
+                        .map(format_timestamp)
+                        .unwrap_or_else(|| "-".to_owned()),
+                    execution_duration(execution),
+                    revision_label(execution)
+                )),
+                Span::styled(
+                    execution
+                        .trigger
+                        .as_ref()
+                        .map(trigger_label)
+                        .unwrap_or_else(|| "unknown trigger".to_owned()),
+                    Style::default().fg(colors().muted),
+                ),
+                Span::styled(
+                    execution
+                        .pipeline_execution_id
+                        .as_ref()
+                        .and_then(|id| app.notes.get(id))
+                        .map_or(String::new(), |note| format!("  ✎ {}", note)),
+                    Style::default().fg(colors().accent),
+                ),
+            ]))
+        })
+        .collect::<Vec<_>>();
+
+    f.render_widget(
+        List::new(items).block(pane_block(
+            &format!("History: {}", app.pipeline.name),
+            focused,
+        )),
+        area,
+    );
+}
+
+// bookmarked executions from every pipeline, enter follows one
+fn draw_bookmarks<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let block = Block::default()
+        .title(Span {
+            content: "Bookmarks".into(),
+            style: Style::default().add_modifier(Modifier::BOLD),
+        })
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().fg(colors().accent))
+        .borders(Borders::ALL);
+    if app.bookmarks.is_empty() {
+        f.render_widget(
+            Paragraph::new("Nothing bookmarked yet, * in the history view bookmarks an execution")
+                .block(block),
+            area,
+        );
+        return;
+    }
+    let name_width = app
+        .bookmarks
+        .iter()
+        .map(|bookmark| bookmark.pipeline.len())
+        .max()
+        .unwrap_or(0);
+    let items = app
+        .bookmarks
+        .iter()
+        .enumerate()
+        .map(|(index, bookmark)| {
+            let status = bookmark.status.as_deref().unwrap_or("Unknown");
+            let mut id_style = Style::default();
+            if index == app.selected_bookmark {
+                id_style = id_style.add_modifier(Modifier::REVERSED);
+            }
+            ListItem::new(Spans::from(vec![
+                Span::raw(format!(
+                    "{:<width$}  ",
+                    bookmark.pipeline,
+                    width = name_width
+                )),
+                Span::styled(short_id(&bookmark.execution_id).to_owned(), id_style),
+                Span::raw("  "),
+                Span::styled(
+                    format!("{:<13} ", status_label(status)),
+                    Style::default().fg(status_color(status)),
+                ),
+                Span::raw(format!(
+                    "{:<16}  {:>8}",
+                    bookmark
+                        .started
+                        .map(format_timestamp)
+                        .unwrap_or_else(|| "-".to_owned()),
+                    bookmark.seconds.map_or("-".to_owned(), format_duration),
+                )),
+                Span::styled(
+                    bookmark
+                        .note
+                        .as_ref()
+                        .map_or(String::new(), |note| format!("  ✎ {}", note)),
+                    Style::default().fg(colors().accent),
+                ),
+            ]))
+        })
+        .collect::<Vec<_>>();
+    f.render_widget(List::new(items).block(block), area);
+}
+
+// the two executions' stages side by side, for "why was this deploy 20 minutes slower?", above "what exactly is
+// going out in this deploy?": every source's old and new revision, and the commits in between
+fn draw_comparison<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    // the header and the gap under it, the stages and the total
+    let stages_height = app.definition.stages.len() as u16 + 5;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(stages_height), Constraint::Min(0)])
+        .split(area);
+    draw_stage_comparison(f, chunks[0], app);
+
+    let mut lines = vec![];
+    match &app.comparison {
+        None => lines.push(Spans::from("Loading...")),
+        Some(Err(e)) => lines.push(Spans::from(Span::styled(
+            e.clone(),
+            Style::default().fg(colors().muted),
+        ))),
+        Some(Ok(changes)) if changes.is_empty() => {
+            lines.push(Spans::from("These executions have no source revisions"))
+        }
+        Some(Ok(changes)) => {
+            for change in changes {
+                let short = |id: &Option<String>| {
+                    id.as_deref()
+                        .map_or("?".to_owned(), |id| id.get(..7).unwrap_or(id).to_owned())
+                };
+                lines.push(Spans::from(vec![
+                    Span::styled(
+                        format!("{}: ", change.action_name),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    if change.changed() {
+                        Span::styled(
+                            format!("{} → {}", short(&change.base), short(&change.head)),
+                            Style::default().fg(colors().pending),
+                        )
+                    } else {
+                        Span::styled(
+                            format!("{} (unchanged)", short(&change.head)),
+                            Style::default().fg(colors().muted),
+                        )
+                    },
+                ]));
+                match &change.commits {
+                    Some(commits) => lines.extend(commits.iter().map(|(id, commit)| {
+                        Spans::from(vec![
+                            Span::raw(format!("  {}  ", id.get(..7).unwrap_or(id))),
+                            Span::raw(commit.message.clone()),
+                            Span::styled(
+                                commit
+                                    .author
+                                    .as_ref()
+                                    .map_or(String::new(), |author| format!("  by {}", author)),
+                                Style::default().fg(colors().muted),
+                            ),
+                        ])
+                    })),
+                    None if change.changed() => lines.push(Spans::from(Span::styled(
+                        "  (no commit list for this source, is a token configured?)",
+                        Style::default().fg(colors().muted),
+                    ))),
+                    None => {}
+                }
+                if change.truncated {
+                    lines.push(Spans::from(Span::styled(
+                        "  ... and older ones, the list stops before the base",
+                        Style::default().fg(colors().muted),
+                    )));
+                }
+                lines.push(Spans::from(""));
+            }
+        }
+    }
+
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title(Span {
+                    content: "Sources".into(),
+                    style: Style::default().add_modifier(Modifier::BOLD),
+                })
+                .border_type(BorderType::Thick)
+                .border_style(Style::default().fg(colors().accent))
+                .borders(Borders::ALL),
+        ),
+        chunks[1],
+    );
+}
+
+fn draw_stage_comparison<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let (base, head) = match &app.compared {
+        Some((base, head)) => (base.as_str(), head.as_str()),
+        None => ("", ""),
+    };
+    let block = Block::default()
+        .title(Span {
+            content: format!("Comparison {} → {}", short_id(base), short_id(head)).into(),
+            style: Style::default().add_modifier(Modifier::BOLD),
+        })
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().fg(colors().accent))
+        .borders(Borders::ALL);
+    let stages = match &app.stage_comparison {
+        Some(Ok(stages)) => stages,
+        Some(Err(e)) => {
+            let text = Span::styled(e.clone(), Style::default().fg(colors().muted));
+            f.render_widget(Paragraph::new(Spans::from(text)).block(block), area);
+            return;
+        }
+        None => {
+            f.render_widget(Paragraph::new("Loading...").block(block), area);
+            return;
+        }
+    };
+
+    let run_label = |run: &Option<StageRun>| match run {
+        Some(run) if run.succeeded => format_duration(run.seconds),
+        Some(run) => format!("{} failed", format_duration(run.seconds)),
+        None => "-".to_owned(),
+    };
+    let row = |cells: Vec<String>, delta: Option<i64>, base: u64| {
+        // minutes slower and then some is what's worth looking into, a few seconds either way is noise
+        match delta {
+            Some(delta) if delta >= 30 && delta as f64 > base as f64 * 0.1 => {
+                Row::StyledData(cells.into_iter(), Style::default().fg(colors().warning))
+            }
+            Some(delta) if delta <= -30 => {
+                Row::StyledData(cells.into_iter(), Style::default().fg(colors().succeeded))
+            }
+            _ => Row::Data(cells.into_iter()),
+        }
+    };
+    let mut rows = stages
+        .iter()
+        .map(|stage| {
+            row(
+                vec![
+                    stage.stage.clone(),
+                    run_label(&stage.base),
+                    run_label(&stage.head),
+                    stage.delta().map_or("-".to_owned(), delta_label),
+                ],
+                stage.delta(),
+                stage.base.as_ref().map_or(0, |run| run.seconds),
+            )
+        })
+        .collect::<Vec<_>>();
+    let took = |id: &str| {
+        let execution = app
+            .pipeline
+            .executions
+            .iter()
+            .find(|execution| execution.pipeline_execution_id.as_deref() == Some(id))?;
+        Some((execution.last_update_time? - execution.start_time?).max(0.0) as u64)
+    };
+    let (base_took, head_took) = (took(base), took(head));
+    let total = match (base_took, head_took) {
+        (Some(base), Some(head)) => Some(head as i64 - base as i64),
+        _ => None,
+    };
+    rows.push(row(
+        vec![
+            "Total".to_owned(),
+            base_took.map_or("-".to_owned(), format_duration),
+            head_took.map_or("-".to_owned(), format_duration),
+            total.map_or("-".to_owned(), delta_label),
+        ],
+        total,
+        base_took.unwrap_or_default(),
+    ));
+
+    let widths = [
+        Constraint::Percentage(30),
+        Constraint::Length(16),
+        Constraint::Length(16),
+        Constraint::Length(10),
+    ];
+    f.render_widget(
+        Table::new(
+            [
+                "Stage".to_owned(),
+                short_id(base).to_owned(),
+                short_id(head).to_owned(),
+                "Change".to_owned(),
+            ]
+            .iter(),
+            rows.into_iter(),
+        )
+        .header_style(Style::default().add_modifier(Modifier::BOLD))
+        .widths(&widths)
+        .column_spacing(2)
+        .block(block),
+        area,
+    );
+}
+
+// +12m30s for slower, -1m05s for faster
+fn delta_label(seconds: i64) -> String {
+    match seconds {
+        0 => "same".to_owned(),
+        seconds if seconds > 0 => format!("+{}", format_duration(seconds as u64)),
+        seconds => format!("-{}", format_duration(seconds.unsigned_abs())),
+    }
+}
+
+// a Gantt chart of the selected execution: one bar per action, placed by when it started and finished,
+// so actions running side by side and the one each stage was waiting on stand out
+fn draw_timeline<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let block = Block::default()
+        .title(Span {
+            content: "Timeline".into(),
+            style: Style::default().add_modifier(Modifier::BOLD),
+        })
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().fg(colors().accent))
+        .borders(Borders::ALL);
+    let details = match &app.timeline {
+        Some(details) => details,
+        None => {
+            f.render_widget(Paragraph::new("Loading...").block(block), area);
+            return;
+        }
+    };
+
+    let now = Local::now().timestamp() as f64;
+    // (label, start, end, status, whether its stage was waiting on it), in pipeline order
+    let mut rows = vec![];
+    for stage in &app.definition.stages {
+        let mut stage_rows = vec![];
+        for action in &stage.actions {
+            // details are newest first, so a retried action's latest attempt wins
+            let detail = details.iter().find(|detail| {
+                detail.detail.stage_name.as_deref() == Some(stage.name.as_str())
+                    && detail.detail.action_name.as_deref() == Some(action.name.as_str())
+            });
+            if let Some(detail) = detail {
+                let status = detail.detail.status.clone().unwrap_or_default();
+                let start = match detail.detail.start_time {
+                    Some(start) => start,
+                    None => continue,
+                };
+                let end = if status == "InProgress" {
+                    now
+                } else {
+                    detail.detail.last_update_time.unwrap_or(start)
+                };
+                stage_rows.push((
+                    format!("{} ▸ {}", stage.name, action.name),
+                    start,
+                    end,
+                    status,
+                ));
+            }
+        }
+        stage_rows.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        let last_end = stage_rows
+            .iter()
+            .map(|row| row.2)
+            .fold(f64::NEG_INFINITY, f64::max);
+        rows.extend(
+            stage_rows
+                .into_iter()
+                .map(|(label, start, end, status)| (label, start, end, status, end >= last_end)),
+        );
+    }
+    if rows.is_empty() {
+        f.render_widget(
+            Paragraph::new("This execution has no action runs").block(block),
+            area,
+        );
+        return;
+    }
+
+    let first = rows.iter().map(|row| row.1).fold(f64::INFINITY, f64::min);
+    let last = rows
+        .iter()
+        .map(|row| row.2)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let span = (last - first).max(1.0);
+    let label_width = rows
+        .iter()
+        .map(|row| row.0.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(40);
+    // borders, the label, a gap and the duration after the bar take up the rest
+    let bar_width = (area.width as usize)
+        .saturating_sub(label_width + 4 + 8)
+        .max(10);
+
+    let mut lines = rows
+        .iter()
+        .map(|(label, start, end, status, critical)| {
+            let offset = ((start - first) / span * bar_width as f64) as usize;
+            let length = (((end - start) / span * bar_width as f64).round() as usize).max(1);
+            let mut style = Style::default().fg(status_color(status));
+            if *critical {
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            Spans::from(vec![
+                Span::raw(format!(
+                    "{:<width$}  ",
+                    label.chars().take(label_width).collect::<String>(),
+                    width = label_width
+                )),
+                Span::raw(" ".repeat(offset.min(bar_width - 1))),
+                // the action its stage was waiting on gets a solid bar, the rest a lighter one
+                Span::styled(
+                    (if *critical { "█" } else { "▒" })
+                        .repeat(length.min(bar_width - offset.min(bar_width - 1))),
+                    style,
+                ),
+                Span::styled(
+                    format!(" {}", format_duration((end - start).max(0.0) as u64)),
+                    Style::default().fg(colors().muted),
+                ),
+            ])
+        })
+        .collect::<Vec<_>>();
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(Span::styled(
+        format!(
+            "{:<width$}  {} → {} ({})",
+            "",
+            format_timestamp(first),
+            format_timestamp(last),
+            format_duration(span as u64),
+            width = label_width
+        ),
+        Style::default().fg(colors().muted),
+    )));
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+// the selected execution's artifacts as a tree: each action, what it produced, and who picked that up
+fn draw_provenance<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let mut lines = vec![];
+    match &app.provenance {
+        None => lines.push(Spans::from("Loading...")),
+        Some(roots) => {
+            for root in roots {
+                provenance_lines(root, "", "", &mut lines);
+            }
+        }
+    }
+
+    let id = app
+        .pipeline
+        .executions
+        .get(app.selected_execution)
+        .and_then(|execution| execution.pipeline_execution_id.as_deref())
+        .map_or("?", short_id);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title(Span {
+                    content: format!("Artifacts of execution {}", id).into(),
+                    style: Style::default().add_modifier(Modifier::BOLD),
+                })
+                .border_type(BorderType::Thick)
+                .border_style(Style::default().fg(colors().accent))
+                .borders(Borders::ALL),
+        ),
+        area,
+    );
+}
+
+// `first` goes in front of the node's own line, `rest` in front of everything underneath it
+fn provenance_lines(node: &ActionNode, first: &str, rest: &str, lines: &mut Vec<Spans<'static>>) {
+    let status = node.status.as_deref().unwrap_or("NotRun");
+    let mut spans = vec![
+        Span::raw(first.to_owned()),
+        Span::styled(
+            format!("{} ▸ {}", node.stage, node.action),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(format!(" ({}) ", node.provider)),
+        Span::styled(status.to_owned(), Style::default().fg(status_color(status))),
+    ];
+    if let Some(reference) = &node.reference {
+        spans.push(Span::styled(
+            format!("  {}", reference),
+            Style::default().fg(colors().muted),
+        ));
+    }
+    lines.push(Spans::from(spans));
+
+    for (index, artifact) in node.outputs.iter().enumerate() {
+        let last = index == node.outputs.len() - 1;
+        let (branch, indent) = if last {
+            ("└─ ", "   ")
+        } else {
+            ("├─ ", "│  ")
+        };
+        lines.push(Spans::from(vec![
+            Span::raw(format!("{}{}", rest, branch)),
+            Span::styled(artifact.name.clone(), Style::default().fg(colors().info)),
+        ]));
+        let rest = format!("{}{}", rest, indent);
+        if artifact.consumers.is_empty() {
+            lines.push(Spans::from(Span::styled(
+                format!("{}└─ (not used)", rest),
+                Style::default().fg(colors().muted),
+            )));
+        }
+        for (index, consumer) in artifact.consumers.iter().enumerate() {
+            let last = index == artifact.consumers.len() - 1;
+            let (branch, indent) = if last {
+                ("└─ ", "   ")
+            } else {
+                ("├─ ", "│  ")
+            };
+            provenance_lines(
+                consumer,
+                &format!("{}{}", rest, branch),
+                &format!("{}{}", rest, indent),
+                lines,
+            );
+        }
+    }
+}
+
+// how each stage has been doing lately: how often it passes, how long it takes, and whether that's getting worse
+fn draw_stats<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let title = match app.stats_days {
+        Some(days) => format!("Runs per stage over the last {} days", days),
+        None => format!("Last {} runs per stage", crate::stats::HISTORY_LENGTH),
+    };
+    let block = Block::default()
+        .title(Span {
+            content: title.into(),
+            style: Style::default().add_modifier(Modifier::BOLD),
+        })
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().fg(colors().accent))
+        .borders(Borders::ALL);
+
+    let stats = match &app.stats {
+        Some(stats) => stats,
+        None => {
+            f.render_widget(Paragraph::new("Loading...").block(block), area);
+            return;
+        }
+    };
+
+    let rows = stats.iter().map(|stage| {
+        let cells = vec![
+            stage.stage.clone(),
+            stage.runs.len().to_string(),
+            stage
+                .success_rate()
+                .map_or("-".to_owned(), |rate| format!("{:.0}%", rate * 100.0)),
+            stage.average().map_or("-".to_owned(), format_duration),
+            stage.trend().map_or("-".to_owned(), trend_label),
+            // only as many as there's room for, the newest
+            sparkline(
+                &stage
+                    .runs
+                    .iter()
+                    .skip(
+                        stage
+                            .runs
+                            .len()
+                            .saturating_sub(crate::stats::HISTORY_LENGTH),
+                    )
+                    .map(|run| run.seconds)
+                    .collect::<Vec<_>>(),
+            ),
+        ];
+        // the latest run failing matters more than the overall rate
+        match stage.runs.last() {
+            Some(run) if !run.succeeded => {
+                Row::StyledData(cells.into_iter(), Style::default().fg(colors().failed))
+            }
+            _ => Row::Data(cells.into_iter()),
+        }
+    });
+    let widths = [
+        Constraint::Percentage(25),
+        Constraint::Length(5),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(crate::stats::HISTORY_LENGTH as u16),
+    ];
+    f.render_widget(
+        Table::new(
+            ["Stage", "Runs", "Success", "Average", "Trend", "Durations"].iter(),
+            rows,
+        )
+        .header_style(Style::default().add_modifier(Modifier::BOLD))
+        .widths(&widths)
+        .column_spacing(2)
+        .block(block),
+        area,
+    );
+}
+
+// an arrow for which way durations are heading, with how far
+fn trend_label(trend: f64) -> String {
+    let arrow = if trend > 0.1 {
+        "↑"
+    } else if trend < -0.1 {
+        "↓"
+    } else {
+        "→"
+    };
+    format!("{} {:+.0}%", arrow, trend * 100.0)
+}
+
+// one bar per run, oldest on the left, scaled to the slowest run
+fn sparkline(seconds: &[u64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let longest = seconds.iter().copied().max().unwrap_or(0).max(1);
+    seconds
+        .iter()
+        .map(|seconds| BARS[(seconds * (BARS.len() as u64 - 1) / longest) as usize])
+        .collect()
+}
+
+// everything we know about the selected action, plus the phase breakdown for CodeBuild actions
+fn draw_action<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let (stage, action) = match (app.selected_stage(), app.selected_action()) {
+        (Some(stage), Some(action)) => (stage, action),
+        _ => {
+            f.render_widget(Paragraph::new("No action selected"), area);
+            return;
+        }
+    };
+    let stage_name = stage.state.stage_name.as_deref().unwrap_or("?");
+    let action_name = action.action_name.as_deref().unwrap_or("?");
+    let provider = app.selected_provider().unwrap_or("unknown provider");
+
+    // every action in the stage up top, with the selected one's details underneath
+    let action_count = stage
+        .state
+        .action_states
+        .as_ref()
+        .map_or(0, |actions| actions.len());
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(action_count as u16 + 3),
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    draw_action_table(f, chunks[0], app, stage_name);
+    let area = chunks[1];
+
+    let mut lines = vec![];
+    if let Some(region) = app.selected_region() {
+        lines.push(Spans::from(format!("Region: {}", region)));
+    }
+    match &action.latest_execution {
+        Some(execution) => {
+            if let Some(summary) = &execution.summary {
+                lines.push(Spans::from(format!("Summary: {}", summary)));
+            }
+            if let Some(id) = &execution.external_execution_id {
+                lines.push(Spans::from(format!("External ID: {}", id)));
+            }
+            if let Some(url) = &execution.external_execution_url {
+                lines.push(Spans::from(format!("URL: {}", url)));
+            }
+            if let Some(error) = &execution.error_details {
+                lines.push(Spans::from(Span::styled(
+                    format!(
+                        "Error: {} {}",
+                        error.code.as_deref().unwrap_or_default(),
+                        error.message.as_deref().unwrap_or_default()
+                    ),
+                    Style::default().fg(colors().failed),
+                )));
+            }
+        }
+        None => lines.push(Spans::from("This action hasn't run yet")),
+    }
+
+    if provider == "CodeBuild" {
+        lines.push(Spans::from(""));
+        match &app.build {
+            Some(build) => lines.extend(build_phase_lines(build)),
+            None => lines.push(Spans::from("Loading build...")),
+        }
+    }
+    if let Some(events) = &app.stack_events {
+        lines.push(Spans::from(""));
+        lines.extend(stack_event_lines(events));
+    }
+    if provider == "Manual" {
+        lines.push(Spans::from(""));
+        lines.extend(approval_lines(app, action));
+    }
+    if let Some(deployment) = &app.deployment {
+        lines.push(Spans::from(""));
+        lines.extend(deployment_lines(deployment));
+    }
+
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title(Span {
+                    content: format!("{} ▸ {} ({})", stage_name, action_name, provider).into(),
+                    style: Style::default().add_modifier(Modifier::BOLD),
+                })
+                .border_type(BorderType::Thick)
+                .border_style(Style::default().fg(colors().accent))
+                .borders(Borders::ALL),
+        ),
+        area,
+    );
+}
+
+// the stage's actions with how they last went, sorted however the user picked with the number keys
+fn draw_action_table<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App, stage_name: &str) {
+    let actions = match app
+        .selected_stage()
+        .and_then(|stage| stage.state.action_states.as_ref())
+    {
+        Some(actions) => actions,
+        None => return,
+    };
+    let now = Local::now().timestamp() as f64;
+
+    let rows = app.sorted_actions(now).into_iter().map(|index| {
+        let action = &actions[index];
+        let name = action.action_name.as_deref().unwrap_or("?");
+        let execution = action.latest_execution.as_ref();
+        let cells = vec![
+            name.to_owned(),
+            app.action_declaration(stage_name, name)
+                .map_or("-".to_owned(), |declaration| {
+                    declaration.action_type_id.provider.clone()
+                }),
+            execution
+                .and_then(|execution| execution.status.clone())
+                .unwrap_or_else(|| "-".to_owned()),
+            app.action_duration(name, now)
+                .map_or("-".to_owned(), format_duration),
+            execution
+                .and_then(|execution| execution.last_status_change)
+                .map_or("-".to_owned(), format_timestamp),
+        ];
+        let status = execution
+            .and_then(|execution| execution.status.as_deref())
+            .unwrap_or_default();
+        let mut style = Style::default().fg(status_color(status));
+        if index == app.selected_action {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        Row::StyledData(cells.into_iter(), style)
+    });
+
+    // number the headers so it's obvious which key sorts by what, and point the sorted one the right way
+    let headers = ACTION_COLUMNS
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let arrow = match (*column == app.action_sort, app.action_sort_descending) {
+                (false, _) => "",
+                (true, false) => " ↑",
+                (true, true) => " ↓",
+            };
+            format!("{} {}{}", i + 1, column.title(), arrow)
+        })
+        .collect::<Vec<_>>();
+    let widths = [
+        Constraint::Percentage(30),
+        Constraint::Length(18),
+        Constraint::Length(14),
+        Constraint::Length(12),
+        Constraint::Length(18),
+    ];
+    f.render_widget(
+        Table::new(headers.iter(), rows)
+            .header_style(Style::default().add_modifier(Modifier::BOLD))
+            .widths(&widths)
+            .column_spacing(2)
+            .block(
+                Block::default()
+                    .title(Span {
+                        content: format!("{} actions", stage_name).into(),
+                        style: Style::default().add_modifier(Modifier::BOLD),
+                    })
+                    .border_type(BorderType::Thick)
+                    .border_style(Style::default().fg(colors().accent))
+                    .borders(Borders::ALL),
+            ),
+        area,
+    );
+}
+
+// one row per phase with its duration and a bar scaled against the longest phase,
+// so the phase that failed or took forever stands out
+fn build_phase_lines(build: &Build) -> Vec<Spans<'static>> {
+    let phases = build.phases.clone().unwrap_or_default();
+    let durations = phases.iter().map(phase_duration).collect::<Vec<_>>();
+    let longest = durations.iter().copied().max().unwrap_or(0).max(1);
+
+    let mut lines = vec![Spans::from(Span::styled(
+        "Build phases",
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    for (phase, duration) in phases.iter().zip(durations) {
+        let status = phase.phase_status.as_deref().unwrap_or("IN_PROGRESS");
+        let color = match status {
+            "SUCCEEDED" => colors().succeeded,
+            "IN_PROGRESS" => colors().info,
+            // FAILED, FAULT, TIMED_OUT, STOPPED
+            _ => colors().failed,
+        };
+        let is_longest = duration == longest && duration > 0;
+        lines.push(Spans::from(vec![
+            Span::raw(format!(
+                "{:<18} ",
+                phase.phase_type.as_deref().unwrap_or("?")
+            )),
+            Span::styled(format!("{:<12} ", status), Style::default().fg(color)),
+            Span::raw(format!("{:>7} ", format_duration(duration))),
+            Span::styled(
+                // up to 30 cells for the longest phase
+                "█".repeat((duration * 30 / longest) as usize),
+                Style::default().fg(if is_longest {
+                    colors().pending
+                } else {
+                    colors().muted
+                }),
+            ),
+        ]));
+        // CodeBuild explains failures in the phase contexts
+        if color == colors().failed {
+            lines.extend(phase.contexts.iter().flatten().map(|context| {
+                Spans::from(Span::styled(
+                    format!(
+                        "    {} {}",
+                        context.status_code.as_deref().unwrap_or_default(),
+                        context.message.as_deref().unwrap_or_default()
+                    ),
+                    Style::default().fg(colors().failed),
+                ))
+            }));
+        }
+    }
+    lines
+}
+
+// ideally the first failed resource is near the top, with its reason next to it
+fn stack_event_lines(events: &[StackEvent]) -> Vec<Spans<'static>> {
+    let mut lines = vec![Spans::from(Span::styled(
+        "Recent stack events",
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    lines.extend(events.iter().take(20).map(|event| {
+        let status = event.resource_status.as_deref().unwrap_or("?");
+        let color = if status.ends_with("FAILED") {
+            colors().failed
+        } else if status.ends_with("IN_PROGRESS") {
+            colors().info
+        } else if status.contains("ROLLBACK") {
+            colors().pending
+        } else {
+            colors().succeeded
+        };
+        Spans::from(vec![
+            // "2020-09-01T17:02:11.123Z", the time is the part worth the space
+            Span::raw(format!(
+                "{} ",
+                event.timestamp.get(11..19).unwrap_or(&event.timestamp)
+            )),
+            Span::raw(format!(
+                "{} ({}) ",
+                event.logical_resource_id.as_deref().unwrap_or("?"),
+                event.resource_type.as_deref().unwrap_or("?")
+            )),
+            Span::styled(format!("{} ", status), Style::default().fg(color)),
+            Span::styled(
+                event.resource_status_reason.clone().unwrap_or_default(),
+                Style::default().fg(colors().muted),
+            ),
+        ])
+    }));
+    lines
+}
+
+// how many of an approval's previous runs are listed
+pub const PREVIOUS_APPROVALS: usize = 10;
+
+// what an approver wants to know before hitting approve: what they're being asked, where to review it,
+// how long it's been waiting and who's approved it before
+fn approval_lines(app: &App, action: &ActionState) -> Vec<Spans<'static>> {
+    let configuration = app
+        .selected_declaration()
+        .and_then(|declaration| declaration.configuration.clone())
+        .unwrap_or_default();
+    let mut lines = vec![];
+    if let Some(message) = configuration.get("CustomData") {
+        lines.push(Spans::from(format!("Message: {}", message)));
+    }
+    if let Some(link) = configuration.get("ExternalEntityLink") {
+        lines.push(Spans::from(format!("Review: {}", link)));
+    }
+    if let Some(execution) = &action.latest_execution {
+        if let (Some("InProgress"), Some(since)) =
+            (execution.status.as_deref(), execution.last_status_change)
+        {
+            lines.push(Spans::from(Span::styled(
+                format!(
+                    "Waiting for approval for {}",
+                    format_duration((Local::now().timestamp() as f64 - since).max(0.0) as u64)
+                ),
+                Style::default().fg(colors().pending),
+            )));
+        }
+    }
+
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(Span::styled(
+        "Previous approvals",
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    match &app.approvals {
+        None => lines.push(Spans::from("Loading...")),
+        Some(approvals) => {
+            lines.extend(
+                approvals
+                    .runs
+                    .iter()
+                    .filter(|approval| approval.detail.status.as_deref() != Some("InProgress"))
+                    .take(PREVIOUS_APPROVALS)
+                    .map(approval_line),
+            );
+            if approvals.cut_off {
+                lines.push(Spans::from(Span::styled(
+                    format!(
+                        "Only the pipeline's latest {} action runs were looked through, there may be older ones",
+                        ActionRuns::LOOKED_THROUGH
+                    ),
+                    Style::default().fg(colors().muted),
+                )));
+            }
+        }
+    }
+    lines
+}
+
+// "2020-09-01 17:02  Succeeded  by assumed-role/Admin/dlewisn  "looks good""
+fn approval_line(approval: &ActionExecutionDetailV2) -> Spans<'static> {
+    let status = approval.detail.status.as_deref().unwrap_or("Unknown");
+    let comment = approval
+        .detail
+        .output
+        .as_ref()
+        .and_then(|output| output.execution_result.as_ref())
+        .and_then(|result| result.external_execution_summary.clone());
+    Spans::from(vec![
+        Span::raw(format!(
+            "{}  ",
+            approval
+                .detail
+                .last_update_time
+                .map(format_timestamp)
+                .unwrap_or_else(|| "-".to_owned())
+        )),
+        Span::styled(
+            format!("{:<10} ", status),
+            Style::default().fg(status_color(status)),
+        ),
+        Span::raw(format!(
+            "by {}",
+            approval
+                .updated_by
+                .as_deref()
+                .map(arn_resource)
+                .unwrap_or("unknown")
+        )),
+        Span::styled(
+            match comment {
+                Some(comment) => format!("  \"{}\"", comment),
+                None => String::new(),
+            },
+            Style::default().fg(colors().muted),
+        ),
+    ])
+}
+
+// task sets (or ECS deployments) with their task counts, then any lifecycle hook that didn't go to plan
+fn deployment_lines(deployment: &DeploymentProgress) -> Vec<Spans<'static>> {
+    let mut lines = vec![Spans::from(vec![
+        Span::styled(
+            format!("Deployment {} ", deployment.id),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            deployment.status.clone(),
+            Style::default().fg(status_color(&deployment.status)),
+        ),
+    ])];
+    if let Some(overview) = &deployment.overview {
+        lines.push(Spans::from(format!("Targets: {}", overview)));
+    }
+    if let Some(error) = &deployment.error {
+        lines.push(Spans::from(Span::styled(
+            error.clone(),
+            Style::default().fg(colors().failed),
+        )));
+    }
+    for set in &deployment.task_sets {
+        lines.push(Spans::from(format!(
+            "{:<8} {:<24} {}/{} running, {} pending{}",
+            set.label,
+            set.status,
+            set.running,
+            set.desired,
+            set.pending,
+            match set.traffic_weight {
+                Some(weight) => format!(", {}% of traffic", weight),
+                None => String::new(),
+            }
+        )));
+    }
+    // hooks that succeeded are noise, the ones still going or that failed are what you're looking for
+    lines.extend(
+        deployment
+            .lifecycle_events
+            .iter()
+            .filter(|hook| hook.status != "Succeeded" && hook.status != "Skipped")
+            .map(|hook| {
+                Spans::from(Span::styled(
+                    format!(
+                        "{} {}: {} {}",
+                        hook.target,
+                        hook.name,
+                        hook.status,
+                        hook.message.as_deref().unwrap_or_default()
+                    ),
+                    Style::default().fg(if hook.status == "Failed" {
+                        colors().failed
+                    } else {
+                        colors().info
+                    }),
+                ))
+            }),
+    );
+    lines
+}
+
+// finished phases say how long they took, running ones we work out ourselves
+fn phase_duration(phase: &BuildPhase) -> u64 {
+    match (phase.duration_in_seconds, phase.start_time) {
+        (Some(seconds), _) => seconds.max(0) as u64,
+        (None, Some(start)) => {
+            let end = phase
+                .end_time
+                .unwrap_or_else(|| Local::now().timestamp() as f64);
+            (end - start).max(0.0) as u64
+        }
+        (None, None) => 0,
+    }
+}
+
+// the last crumb is the view we're in, the ones before are what backspace walks back through
+fn draw_breadcrumbs<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let crumbs = app.breadcrumbs();
+    let last = crumbs.len().saturating_sub(1);
+    let mut spans = vec![];
+    for (index, crumb) in crumbs.into_iter().enumerate() {
+        if index > 0 {
+            spans.push(Span::styled(" ▸ ", Style::default().fg(colors().muted)));
+        }
+        let style = if index == last {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(colors().muted)
+        };
+        spans.push(Span::styled(crumb, style));
+    }
+    f.render_widget(Paragraph::new(Spans::from(spans)), area);
+}
+
+fn draw_footer<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let line = match &app.last_error {
+        // says what e does itself
+        Some(error) if app.credentials_expired => Spans::from(Span::styled(
+            error.clone(),
+            Style::default().fg(colors().warning),
+        )),
+        // a failed refresh is more important than the key hints, we keep showing the last good data underneath
+        Some(error) => Spans::from(Span::styled(
+            format!("Refresh failed (e: details): {}", error),
+            Style::default().fg(colors().failed),
+        )),
+        None => {
+            // read-only has no business advertising keys that won't do anything
+            let changes = if app.read_only {
+                ""
+            } else {
+                "S: start  X: stop  R: retry  A/x: approve/reject  b: rollback  T: transition  "
+            };
+            let runs = if app.execution_mode == ExecutionMode::Parallel {
+                "[/]: focus run  "
+            } else {
+                ""
+            };
+            Spans::from(Span::styled(
+                format!("q: quit  r: refresh  p: pipelines  F: fleet  s: stages  h: history  t: stats  {}a: artifacts  g: timeline  n: notifications  tab: pane  z: zoom  {}y/Y/u: copy id/revision/link  o: console  l: build log  L: app log  I: API calls  F12: debug  /: search  f: follow  !: problems only  c: theme  space: mark  N: note  */B: bookmark/bookmarks  1-5: sort  arrows: select  enter: open  esc/backspace: back", changes, runs),
+                Style::default().fg(colors().muted),
+            ))
+        }
+    };
+    f.render_widget(Paragraph::new(line), area);
+}
+
+fn status_color(status: &str) -> Color {
+    match status {
+        "InProgress" => colors().info,
+        "Failed" => colors().failed,
+        "Succeeded" => colors().succeeded,
+        // a newer execution overtook it, nothing actually went wrong
+        "Superseded" | "Abandoned" | "Cancelled" => colors().muted,
+        // somebody stopped it on purpose
+        "Stopped" | "Stopping" => colors().stopped,
+        _ => colors().pending,
+    }
+}
+
+// a symbol in front of the status, so a superseded or stopped run doesn't read like a failure even without colors
+fn status_label(status: &str) -> String {
+    format!("{} {}", status_symbol(status), status)
+}
+
+fn status_symbol(status: &str) -> &'static str {
+    // heavier versions that still take up a single cell, so everything lines up the same
+    if colors().bold {
+        return match status {
+            "InProgress" => "◉",
+            "Failed" => "✖",
+            "Succeeded" => "✔",
+            "Superseded" | "Abandoned" | "Cancelled" => "⤼",
+            "Stopped" | "Stopping" => "■",
+            _ => "◆",
+        };
+    }
+    match status {
+        "InProgress" => "●",
+        "Failed" => "✗",
+        "Succeeded" => "✓",
+        "Superseded" | "Abandoned" | "Cancelled" => "↷",
+        "Stopped" | "Stopping" => "■",
+        _ => "·",
+    }
+}
+
+// how the service's alarms are doing, on the stage they belong next to
+fn alarm_line(app: &App, stage: &StageStateV2) -> Option<Spans<'static>> {
+    let alarms = app.alarms.as_ref()?;
+    if stage.state.stage_name.as_deref() != app.alarm_stage_name() {
+        return None;
+    }
+    let alarming = alarms
+        .iter()
+        .filter(|alarm| alarm.state == "ALARM")
+        .map(|alarm| alarm.name.as_str())
+        .collect::<Vec<_>>();
+    let unknown = alarms
+        .iter()
+        .filter(|alarm| alarm.state == "INSUFFICIENT_DATA")
+        .count();
+    Some(if !alarming.is_empty() {
+        Spans::from(Span::styled(
+            format!("Alarms: {}", alarming.join(", ")),
+            Style::default().fg(colors().failed),
+        ))
+    } else if unknown > 0 {
+        Spans::from(Span::styled(
+            format!(
+                "Alarms: {} OK, {} without data",
+                alarms.len() - unknown,
+                unknown
+            ),
+            Style::default().fg(colors().pending),
+        ))
+    } else {
+        Spans::from(Span::styled(
+            format!("Alarms: all {} OK", alarms.len()),
+            Style::default().fg(colors().succeeded),
+        ))
+    })
+}
+
+fn alarming(app: &App, stage: &StageStateV2) -> bool {
+    stage.state.stage_name.as_deref() == app.alarm_stage_name()
+        && app
+            .alarms
+            .iter()
+            .flatten()
+            .any(|alarm| alarm.state == "ALARM")
+}
+
+// "Entry: 2 rules ✗ Failed" for each kind of condition the stage has, followed by whichever of its rules didn't pass,
+// "  alarm MyService-5xx ✗ Failed" with what the rule had to say under it
+fn condition_lines(app: &App, stage: &StageStateV2) -> Vec<Spans<'static>> {
+    let declared = stage
+        .state
+        .stage_name
+        .as_deref()
+        .and_then(|name| app.conditions(name));
+    let entry = declared.and_then(|declared| declared.before_entry.as_ref());
+    let success = declared.and_then(|declared| declared.on_success.as_ref());
+    let failure = declared.and_then(|declared| declared.on_failure.as_ref());
+    let kinds = [
+        (
+            "Entry",
+            None,
+            entry.map(|entry| &entry.conditions[..]),
+            stage.before_entry_condition_state.as_ref(),
+        ),
+        (
+            "On success",
+            None,
+            success.map(|success| &success.conditions[..]),
+            stage.on_success_condition_state.as_ref(),
+        ),
+        (
+            "On failure",
+            failure.and_then(|failure| failure.result.as_deref()),
+            failure.map(|failure| &failure.conditions[..]),
+            stage.on_failure_condition_state.as_ref(),
+        ),
+    ];
+
+    let mut lines = vec![];
+    for (kind, result, conditions, state) in kinds.iter() {
+        let rules = conditions.map_or(0, |conditions| {
+            conditions
+                .iter()
+                .map(|condition| condition.rules.len())
+                .sum::<usize>()
+        });
+        if result.is_none() && rules == 0 && state.is_none() {
+            continue;
+        }
+        let mut about = vec![];
+        if let Some(result) = result {
+            about.push(result.to_lowercase());
+        }
+        if rules > 0 {
+            about.push(format!(
+                "{} {}",
+                rules,
+                if rules == 1 { "rule" } else { "rules" }
+            ));
+        }
+        let mut spans = vec![Span::raw(format!("{}: {} ", kind, about.join(", ")))];
+        if let Some(execution) = state.and_then(|state| state.latest_execution.as_ref()) {
+            spans.push(Span::styled(
+                status_label(&execution.status),
+                Style::default().fg(status_color(&execution.status)),
+            ));
+        }
+        lines.push(Spans::from(spans));
+
+        // the ones that passed aren't what anyone's looking for
+        let rule_states = state
+            .iter()
+            .flat_map(|state| &state.condition_states)
+            .flat_map(|condition| &condition.rule_states);
+        for rule in rule_states {
+            let execution = match &rule.latest_execution {
+                Some(execution) if execution.status != "Succeeded" => execution,
+                _ => continue,
+            };
+            let name = rule.rule_name.as_deref().unwrap_or("?");
+            let label = match declared.and_then(|declared| declared.rule(name)) {
+                // an alarm gate is better known by its alarm
+                Some(rule) => match rule.configuration.get("AlarmName") {
+                    Some(alarm) if rule.rule_type_id.provider == "CloudWatchAlarm" => {
+                        format!("alarm {}", alarm)
+                    }
+                    _ => format!("{} ({})", name, rule.rule_type_id.provider),
+                },
+                None => name.to_owned(),
+            };
+            lines.push(Spans::from(vec![
+                Span::raw(format!("  {} ", label)),
+                Span::styled(
+                    status_label(&execution.status),
+                    Style::default().fg(status_color(&execution.status)),
+                ),
+            ]));
+            if let Some(summary) = &execution.summary {
+                lines.push(Spans::from(Span::styled(
+                    format!("    {}", summary),
+                    Style::default().fg(colors().muted),
+                )));
+            }
+        }
+    }
+    lines
+}
+
+// with several running at once in a parallel pipeline, the stage only knows which of them got there last, unless
+// the stages are focused on one of them
+fn latest_label(app: &App) -> String {
+    let running = app.pipeline.running_executions().len();
+    if app.execution_mode == ExecutionMode::Parallel
+        && running > 1
+        && app.pinned_execution.is_none()
+    {
+        format!("Latest of {}", running)
+    } else {
+        "Latest".to_owned()
+    }
+}
+
+// "Latest: 1a2b3c4d InProgress" followed by anything waiting to get into the stage and then the stage's actions
+// `selected` is the index of the highlighted action when this is the selected stage
+fn stage_execution_lines(
+    app: &App,
+    stage: &StageStateV2,
+    selected: Option<usize>,
+    overrun: Option<(u64, u64)>,
+    sla: Option<(u64, u64)>,
+) -> Vec<Spans<'static>> {
+    let mut lines = match &stage.state.latest_execution {
+        Some(StageExecution {
+            pipeline_execution_id,
+            status,
+        }) => vec![Spans::from(vec![
+            Span::raw(format!(
+                "{}: {} ",
+                latest_label(app),
+                short_id(pipeline_execution_id)
+            )),
+            Span::styled(
+                status_label(status),
+                Style::default().fg(status_color(status)),
+            ),
+        ])],
+        None => vec![Spans::from(format!("{}: never run", latest_label(app)))],
+    };
+    // a failed stage whose actions never ran is down to its gate, not a broken build
+    if let Some(blocked) = stage.blocked_by_conditions() {
+        let message = match blocked.status() {
+            Some("InProgress") => "Waiting on its entry conditions",
+            _ => "Held at its entry conditions",
+        };
+        lines.push(Spans::from(Span::styled(
+            message,
+            Style::default().fg(colors().warning),
+        )));
+    }
+    // a disabled transition on either side explains why nothing is moving
+    if let Some(line) = transition_line("In", stage) {
+        lines.push(line);
+    }
+    if let Some(line) = app
+        .next_stage(stage)
+        .and_then(|next| transition_line("Out", next))
+    {
+        lines.push(line);
+    }
+    if let Some(line) = alarm_line(app, stage) {
+        lines.push(line);
+    }
+    if let Some((elapsed, limit)) = sla {
+        lines.push(Spans::from(Span::styled(
+            format!(
+                "Running {}, over its {} SLA",
+                format_duration(elapsed),
+                format_duration(limit)
+            ),
+            Style::default().fg(colors().failed),
+        )));
+    } else if let Some((elapsed, median)) = overrun {
+        lines.push(Spans::from(Span::styled(
+            format!(
+                "Running {}, usually {}",
+                format_duration(elapsed),
+                format_duration(median)
+            ),
+            Style::default().fg(colors().warning),
+        )));
+    }
+    lines.extend(stage.inbound().into_iter().map(|inbound| {
+        Spans::from(Span::styled(
+            format!(
+                "Inbound: {} {}",
+                short_id(&inbound.pipeline_execution_id),
+                inbound.status
+            ),
+            Style::default().fg(colors().info),
+        ))
+    }));
+    lines.extend(condition_lines(app, stage));
+
+    lines.push(Spans::from(""));
+    lines.extend(
+        stage
+            .state
+            .action_states
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(index, action)| {
+                let status = action
+                    .latest_execution
+                    .as_ref()
+                    .and_then(|execution| execution.status.as_deref())
+                    .unwrap_or("NotRun");
+                let style = Style::default().fg(status_color(status));
+                let name = action.action_name.as_deref().unwrap_or("?");
+                let mut spans = vec![Span::styled(
+                    format!("{} {}", name, status_label(status)),
+                    if selected == Some(index) {
+                        style.add_modifier(Modifier::REVERSED)
+                    } else {
+                        style
+                    },
+                )];
+                // its details get fetched from a different region, so say which
+                let stage_name = stage.state.stage_name.as_deref().unwrap_or_default();
+                if let Some(region) = app.action_region(stage_name, name) {
+                    spans.push(Span::styled(
+                        format!(" ({})", region),
+                        Style::default().fg(colors().muted),
+                    ));
+                }
+                Spans::from(spans)
+            }),
+    );
+    lines
+}
+
+// transitions are enabled unless CodePipeline tells us otherwise
+fn transition_enabled(stage: &StageState) -> bool {
+    stage
+        .inbound_transition_state
+        .as_ref()
+        .and_then(|transition| transition.enabled)
+        .unwrap_or(true)
+}
+
+// why the transition into `stage` is disabled and who did it, if it is
+fn transition_line(direction: &str, stage: &StageStateV2) -> Option<Spans<'static>> {
+    if transition_enabled(&stage.state) {
+        return None;
+    }
+    let transition = stage.state.inbound_transition_state.as_ref()?;
+    let mut text = format!(
+        "{}bound disabled: {}",
+        direction,
+        transition
+            .disabled_reason
+            .as_deref()
+            .unwrap_or("no reason given")
+    );
+    if let Some(by) = &transition.last_changed_by {
+        text.push_str(&format!(" (by {})", arn_resource(by)));
+    }
+    Some(Spans::from(Span::styled(
+        text,
+        Style::default().fg(colors().failed),
+    )))
+}
+
+// the revision the stage's latest execution is carrying, plus any queued runs it superseded along the way
+// so it's obvious where a commit that never reached the end of the pipeline went
+// along with the commit itself when we've managed to look it up
+fn stage_revision_lines<'a>(
+    stage: &StageStateV2,
+    executions: &'a [PipelineExecutionSummary],
+    commits: &HashMap<String, CommitInfo>,
+) -> Vec<Spans<'a>> {
+    let latest_id = match &stage.state.latest_execution {
+        Some(execution) => &execution.pipeline_execution_id,
+        None => return vec![],
+    };
+    // executions come back newest first
+    let position = match executions
+        .iter()
+        .position(|e| e.pipeline_execution_id.as_ref() == Some(latest_id))
+    {
+        Some(position) => position,
+        None => {
+            return vec![Spans::from(format!(
+                "{} (too old to look up)",
+                short_id(latest_id)
+            ))]
+        }
+    };
+
+    let commit = executions[position]
+        .source_revisions
+        .as_ref()
+        .and_then(|revisions| revisions.first())
+        .and_then(|revision| revision.revision_id.as_ref())
+        .and_then(|id| commits.get(id));
+
+    let mut lines = vec![Spans::from(format!(
+        "{} {}{}",
+        short_id(latest_id),
+        revision_label(&executions[position]),
+        match commit.and_then(|commit| commit.branch.as_ref()) {
+            Some(branch) => format!(" ({})", branch),
+            None => String::new(),
+        }
+    ))];
+    if let Some(commit) = commit {
+        lines.push(Spans::from(Span::styled(
+            commit.message.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        if let Some(author) = &commit.author {
+            lines.push(Spans::from(format!("by {}", author)));
+        }
+    }
+    // in SUPERSEDED mode, a newer execution replaces the one waiting for a stage and the replaced one is marked Superseded
+    // so the runs this execution superseded are the Superseded ones directly before it
+    lines.extend(
+        executions[position + 1..]
+            .iter()
+            .take_while(|e| e.status.as_deref() == Some("Superseded"))
+            .map(|superseded| {
+                Spans::from(Span::styled(
+                    format!(
+                        "superseded {} {}",
+                        short_id(superseded.pipeline_execution_id.as_deref().unwrap_or("?")),
+                        revision_label(superseded)
+                    ),
+                    Style::default().fg(colors().muted),
+                ))
+            }),
+    );
+    lines
+}
+
+// the first source revision is the commit, which is what people recognize
+fn revision_label(execution: &PipelineExecutionSummary) -> String {
+    match execution
+        .source_revisions
+        .as_ref()
+        .and_then(|revisions| revisions.first())
+        .and_then(|revision| revision.revision_id.as_ref())
+    {
+        Some(revision_id) => format!("@ {}", revision_id.get(..7).unwrap_or(revision_id)),
+        None => "@ unknown revision".to_owned(),
+    }
+}
+
+// who or what kicked off an execution, e.g. "StartPipelineExecution by assumed-role/Admin/dlewisn"
+// the detail is usually an ARN, and the interesting part of an ARN is its resource at the end
+fn trigger_label(trigger: &ExecutionTrigger) -> String {
+    let trigger_type = trigger.trigger_type.as_deref().unwrap_or("Unknown");
+    let detail = trigger
+        .trigger_detail
+        .as_deref()
+        .map(|detail| arn_resource(detail).to_owned());
+    match (trigger_type, detail) {
+        ("StartPipelineExecution", Some(principal)) => {
+            format!("StartPipelineExecution by {}", principal)
+        }
+        ("Webhook", Some(webhook)) | ("WebhookV2", Some(webhook)) => format!("webhook {}", webhook),
+        ("CloudWatchEvent", Some(rule)) => format!("CloudWatch {}", rule),
+        (trigger_type, Some(detail)) => format!("{} {}", trigger_type, detail),
+        (trigger_type, None) => trigger_type.to_owned(),
+    }
+}
+
+// the resource on the end of an ARN, e.g. "assumed-role/Admin/dlewisn", or the whole thing if it isn't an ARN
+pub fn arn_resource(arn: &str) -> &str {
+    arn.splitn(6, ':').nth(5).unwrap_or(arn)
+}
+
+// timestamps come back from AWS as fractional seconds since the epoch
+pub fn format_timestamp(timestamp: f64) -> String {
+    match Local.timestamp_opt(timestamp as i64, 0).single() {
+        Some(time) => time.format("%Y-%m-%d %H:%M").to_string(),
+        None => "-".to_owned(),
+    }
+}
+
+// how long an execution took, or has taken so far if it's still going
+fn execution_duration(execution: &PipelineExecutionSummary) -> String {
+    let end = match execution.status.as_deref() {
+        Some("InProgress") => Local::now().timestamp() as f64,
+        _ => match execution.last_update_time {
+            Some(end) => end,
+            None => return "-".to_owned(),
+        },
+    };
+    match execution.start_time {
+        Some(start) => format_duration((end - start).max(0.0) as u64),
+        None => "-".to_owned(),
+    }
+}
+
+fn format_duration(seconds: u64) -> String {
+    if seconds >= 3600 {
+        format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60)
+    } else {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    }
+}
@@ -0,0 +1,139 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use std::env::{self, consts};
+use std::error::Error;
+use std::fs;
+
+const LATEST_RELEASE: &str =
+    "https://api.github.com/repos/fxaa/codepipeline-status/releases/latest";
+// every release carries one of these alongside its binaries, in sha256sum's format
+const CHECKSUMS: &str = "SHA256SUMS";
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+// swaps the running binary for the latest release's, most people having installed it as the one static binary
+// with nothing to update it for them. The download has to match the release's checksum before anything is
+// touched, which catches a broken download but not a bad release, the checksums aren't signed
+pub async fn self_update(check: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let http = reqwest::Client::new();
+    let release: Release = match get(&http, LATEST_RELEASE).await {
+        Ok(response) => response.json().await?,
+        Err(e) => return Err(format!("Couldn't look up the latest release: {}", e).into()),
+    };
+    let current = env!("CARGO_PKG_VERSION");
+    if !newer(&release.tag_name, current) {
+        println!("Already on the latest release ({})", current);
+        return Ok(());
+    }
+    if check {
+        println!("{} is out, this is {}", release.tag_name, current);
+        return Ok(());
+    }
+
+    let name = asset_name();
+    let asset = |wanted: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == wanted)
+            .ok_or_else(|| format!("Release {} has no {}", release.tag_name, wanted))
+    };
+    let checksums = get(&http, &asset(CHECKSUMS)?.browser_download_url)
+        .await?
+        .text()
+        .await?;
+    let expected = checksum_for(&checksums, &name).ok_or_else(|| {
+        format!(
+            "{} of {} has no checksum for {}",
+            CHECKSUMS, release.tag_name, name
+        )
+    })?;
+    println!("Downloading {} {}", name, release.tag_name);
+    let binary = get(&http, &asset(&name)?.browser_download_url)
+        .await?
+        .bytes()
+        .await?;
+    let actual = hex::encode(Sha256::digest(&binary));
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(format!(
+            "The download doesn't match its checksum ({} rather than {}), so nothing's been replaced",
+            actual, expected
+        )
+        .into());
+    }
+
+    replace(&binary)?;
+    println!("Updated from {} to {}", current, release.tag_name);
+    Ok(())
+}
+
+async fn get(
+    http: &reqwest::Client,
+    url: &str,
+) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+    Ok(http
+        .get(url)
+        .header("Accept", "application/vnd.github.v3+json")
+        .header("User-Agent", "codepipeline-status")
+        .send()
+        .await?
+        .error_for_status()?)
+}
+
+// the binary built for wherever we're running, e.g. codepipeline-status-x86_64-linux
+pub fn asset_name() -> String {
+    format!(
+        "codepipeline-status-{}-{}{}",
+        consts::ARCH,
+        consts::OS,
+        consts::EXE_SUFFIX
+    )
+}
+
+// a line of sha256sum's output: the hash, then two spaces (or a space and a * in binary mode) and the file name
+pub fn checksum_for<'a>(checksums: &'a str, name: &str) -> Option<&'a str> {
+    checksums.lines().find_map(|line| {
+        let (hash, file) = line.split_once(' ')?;
+        let file = file.trim_start().trim_start_matches('*');
+        Some(hash).filter(|_| file == name)
+    })
+}
+
+// v1.2.3 against 1.2.0, going by the numbers
+pub fn newer(tag: &str, current: &str) -> bool {
+    let version = |version: &str| {
+        version
+            .trim_start_matches('v')
+            .split(['.', '-'])
+            .map_while(|part| part.parse::<u64>().ok())
+            .collect::<Vec<_>>()
+    };
+    version(tag) > version(current)
+}
+
+// written next to the running binary and renamed over it, so it's never left half replaced, and wherever a
+// symlink to it (from a package manager, say) points
+fn replace(binary: &[u8]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let current = fs::canonicalize(env::current_exe()?)?;
+    let staged = current.with_file_name(".codepipeline-status.new");
+    fs::write(&staged, binary)?;
+    let permissions = fs::metadata(&current)?.permissions();
+    let result =
+        fs::set_permissions(&staged, permissions).and_then(|()| fs::rename(&staged, &current));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&staged);
+        return Err(format!("Couldn't replace {}: {}", current.display(), e).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use crate::aws::{self, Clients};
+use tokio::time::delay_for;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+// a script waiting on a deploy is in no hurry, and there might be a few of them running at once
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+// throttling or a network blip shouldn't fail a release, but something that keeps failing should stop it
+const MAX_FAILURES: u32 = 5;
+
+// how the wait ended, which is all a script gets to see of it, along with ERROR_EXIT_CODE for when it never found
+// out
+pub enum Outcome {
+    Succeeded,
+    // Failed, but also Stopped, Superseded, ...
+    Failed,
+    TimedOut,
+}
+
+// the credentials or the API kept failing, so a script can tell that apart from the execution failing
+pub const ERROR_EXIT_CODE: i32 = 3;
+
+impl Outcome {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Outcome::Succeeded => 0,
+            Outcome::Failed => 1,
+            Outcome::TimedOut => 2,
+        }
+    }
+}
+
+// the latest execution unless we're given one, printing a line whenever it or one of its stages moves on
+pub async fn wait(
+    clients: &Clients,
+    pipeline: &str,
+    execution_id: Option<String>,
+    timeout: Option<Duration>,
+) -> Result<Outcome, Box<dyn Error + Send + Sync>> {
+    let started = Instant::now();
+    let execution_id = match execution_id {
+        Some(id) => id,
+        None => aws::fetch_executions(clients, pipeline)
+            .await?
+            .into_iter()
+            .find_map(|execution| execution.pipeline_execution_id)
+            .ok_or_else(|| format!("{} has never run", pipeline))?,
+    };
+    let mut stdout = io::stdout();
+    writeln!(
+        stdout,
+        "Waiting for {} execution {}",
+        pipeline,
+        aws::short_id(&execution_id)
+    )?;
+
+    let mut stage_statuses = HashMap::new();
+    let mut last_status = String::new();
+    let mut failures = 0;
+    loop {
+        match progress(clients, pipeline, &execution_id).await {
+            Ok((status, stages)) => {
+                failures = 0;
+                for (stage, stage_status) in stages {
+                    if stage_statuses.get(&stage) != Some(&stage_status) {
+                        writeln!(stdout, "Stage {}: {}", stage, stage_status)?;
+                        stage_statuses.insert(stage, stage_status);
+                    }
+                }
+                if status != last_status {
+                    writeln!(stdout, "Execution: {}", status)?;
+                }
+                stdout.flush()?;
+                match status.as_str() {
+                    "Succeeded" => return Ok(Outcome::Succeeded),
+                    "InProgress" | "Stopping" => {}
+                    _ => return Ok(Outcome::Failed),
+                }
+                last_status = status;
+            }
+            Err(e) => {
+                failures += 1;
+                if failures >= MAX_FAILURES {
+                    return Err(e);
+                }
+                warn!("Couldn't check on {}: {}", pipeline, e);
+            }
+        }
+
+        let wait = match timeout {
+            Some(timeout) => match timeout.checked_sub(started.elapsed()) {
+                Some(left) if left > Duration::from_secs(0) => left.min(POLL_INTERVAL),
+                _ => {
+                    writeln!(stdout, "Timed out after {}s", timeout.as_secs())?;
+                    return Ok(Outcome::TimedOut);
+                }
+            },
+            None => POLL_INTERVAL,
+        };
+        delay_for(wait).await;
+    }
+}
+
+// the execution's status, and those of the stages it's got to so far
+async fn progress(
+    clients: &Clients,
+    pipeline: &str,
+    execution_id: &str,
+) -> Result<(String, Vec<(String, String)>), Box<dyn Error + Send + Sync>> {
+    let status = aws::fetch_execution_status(clients, pipeline, execution_id).await?;
+    let stages = aws::fetch_state(clients, pipeline)
+        .await?
+        .into_iter()
+        .filter_map(|stage| {
+            let execution = stage.state.latest_execution?;
+            if execution.pipeline_execution_id != execution_id {
+                return None;
+            }
+            Some((stage.state.stage_name?, execution.status))
+        })
+        .collect();
+    Ok((status, stages))
+}
@@ -0,0 +1,51 @@
+use regex::Regex;
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// the pipelines a --watchlist file puts on the dashboard, one name or regex a line, each one having to match a
+// pipeline's whole name so a plain name doesn't pull in every pipeline it's part of
+#[derive(Clone, Debug)]
+pub struct Watchlist {
+    patterns: Vec<Regex>,
+}
+
+impl Watchlist {
+    pub fn load(path: &Path) -> Result<Watchlist, Box<dyn Error + Send + Sync>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+        Ok(Watchlist::parse(&contents).map_err(|e| format!("{}, {}", path.display(), e))?)
+    }
+
+    // blank lines and # comments, on a line of their own or after a name, are skipped
+    pub fn parse(contents: &str) -> Result<Watchlist, String> {
+        let mut patterns = vec![];
+        for (number, line) in contents.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let pattern = Regex::new(&format!("^(?:{})$", line))
+                .map_err(|e| format!("line {}: \"{}\" isn't a regex: {}", number + 1, line, e))?;
+            patterns.push(pattern);
+        }
+        Ok(Watchlist { patterns })
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(name))
+    }
+}
+
+// a # only starts a comment at the start of the line or after a space, so a regex can still have one in it
+fn strip_comment(line: &str) -> &str {
+    let mut previous = None;
+    for (at, c) in line.char_indices() {
+        if c == '#' && previous.is_none_or(char::is_whitespace) {
+            return &line[..at];
+        }
+        previous = Some(c);
+    }
+    line
+}
@@ -0,0 +1,123 @@
+// the dashboard's own keys for changing a pipeline, each asking first and then handing the main loop a command
+use codepipeline_status::app::{App, Command, View};
+use codepipeline_status::aws::{Pipeline, PipelineState};
+use crossterm::event::KeyCode;
+use serde_json::Value;
+
+// the fixture's pipeline, with `change` made to its state first
+fn opened(change: impl FnOnce(&mut Value)) -> App {
+    let mut state: Value = serde_json::from_str(include_str!("fixtures/state.json")).unwrap();
+    change(&mut state);
+    let state: PipelineState = serde_json::from_value(state).unwrap();
+    let mut app = App::new("123456789012".to_owned(), "us-west-2".to_owned(), vec![]);
+    app.pipeline = Pipeline {
+        name: "payments-prod".to_owned(),
+        stage_states: state.stage_states,
+        executions: vec![],
+        concurrent: vec![],
+    };
+    app.view = View::Stages;
+    app
+}
+
+// Prod's action waiting on someone to approve it
+fn waiting(state: &mut Value) {
+    state["stageStates"][2]["actionStates"][0]["latestExecution"] =
+        serde_json::json!({ "status": "InProgress", "token": "approval-token-1234" });
+}
+
+fn press(app: &mut App, keys: &str) -> Option<Command> {
+    let mut command = None;
+    for key in keys.chars() {
+        command = app.on_key(KeyCode::Char(key));
+    }
+    command
+}
+
+#[test]
+fn approves_with_the_comment_typed() {
+    let mut app = opened(waiting);
+    app.selected_stage = 2;
+
+    assert_eq!(press(&mut app, "Aok"), None);
+    assert_eq!(
+        app.on_key(KeyCode::Enter),
+        Some(Command::Approve {
+            stage: "Prod".to_owned(),
+            action: "Deploy".to_owned(),
+            comment: "ok".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn rejects_with_a_comment_even_when_none_is_typed() {
+    let mut app = opened(waiting);
+    app.selected_stage = 2;
+
+    press(&mut app, "x");
+    assert_eq!(
+        app.on_key(KeyCode::Enter),
+        Some(Command::Reject {
+            stage: "Prod".to_owned(),
+            action: "Deploy".to_owned(),
+            comment: "Rejected from codepipeline-status".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn only_an_approval_still_waiting_can_be_answered() {
+    let mut app = opened(|_| {});
+    app.selected_stage = 2;
+
+    assert_eq!(press(&mut app, "A"), None);
+    assert!(app.modal.is_none());
+}
+
+#[test]
+fn retries_only_a_failed_stage() {
+    let mut app = opened(|_| {});
+    app.selected_stage = 1;
+    assert_eq!(
+        press(&mut app, "R"),
+        Some(Command::RetryStage("Build".to_owned()))
+    );
+
+    app.selected_stage = 0;
+    assert_eq!(press(&mut app, "R"), None);
+}
+
+#[test]
+fn stops_the_running_execution_with_the_reason_typed() {
+    let mut app = opened(|state| {
+        state["stageStates"][1]["latestExecution"]["status"] = "InProgress".into();
+    });
+
+    press(&mut app, "Xbad");
+    assert_eq!(
+        app.on_key(KeyCode::Enter),
+        Some(Command::StopExecution {
+            execution_id: "7d1f0a52-2c3b-4e5f-8a9b-0c1d2e3f4a5b".to_owned(),
+            reason: Some("bad".to_owned()),
+        })
+    );
+
+    // the fixture's latest run has already failed
+    let mut app = opened(|_| {});
+    assert_eq!(press(&mut app, "X"), None);
+    assert!(app.modal.is_none());
+}
+
+#[test]
+fn read_only_turns_them_all_off() {
+    let mut app = opened(waiting);
+    app.read_only = true;
+    app.selected_stage = 2;
+
+    for key in "AxRX".chars() {
+        assert_eq!(app.on_key(KeyCode::Char(key)), None);
+        assert!(app.modal.is_none());
+    }
+    assert!(app.toasts[0].message.starts_with("Read-only"));
+}
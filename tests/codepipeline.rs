@@ -0,0 +1,417 @@
+// the calls the dashboard and the subcommands make, against the mock server rather than a real account
+use codepipeline_status::aws;
+use codepipeline_status::mock::MockServer;
+use serde_json::json;
+
+#[tokio::test]
+async fn lists_pipelines_across_every_page() {
+    let server = MockServer::start().await;
+    server.respond(
+        "ListPipelines",
+        json!({ "pipelines": [{ "name": "api" }, { "name": "web" }], "nextToken": "page-2" }),
+    );
+    server.respond(
+        "ListPipelines",
+        json!({ "pipelines": [{ "name": "worker" }] }),
+    );
+
+    let names = aws::list_pipeline_names(&server.clients()).await.unwrap();
+
+    assert_eq!(names, vec!["api", "web", "worker"]);
+    let requests = server.requests("ListPipelines");
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].get("nextToken"), None);
+    assert_eq!(requests[1]["nextToken"], "page-2");
+}
+
+#[tokio::test]
+async fn stops_paging_action_executions_once_it_has_enough() {
+    let server = MockServer::start().await;
+    server.respond(
+        "ListActionExecutions",
+        json!({
+            "actionExecutionDetails": [
+                { "actionExecutionId": "a1", "stageName": "Build", "actionName": "Compile" },
+                { "actionExecutionId": "a2", "stageName": "Deploy", "actionName": "Release", "updatedBy": "alice" },
+            ],
+            "nextToken": "more",
+        }),
+    );
+
+    let details = aws::list_action_executions(&server.clients(), "api", 2)
+        .await
+        .unwrap();
+
+    assert_eq!(details.len(), 2);
+    assert_eq!(details[1].updated_by.as_deref(), Some("alice"));
+    assert_eq!(server.requests("ListActionExecutions").len(), 1);
+}
+
+#[tokio::test]
+async fn picks_out_one_actions_executions() {
+    let server = MockServer::start().await;
+    server.respond(
+        "ListActionExecutions",
+        json!({
+            "actionExecutionDetails": [
+                { "actionExecutionId": "a1", "stageName": "Build", "actionName": "Compile" },
+                { "actionExecutionId": "a2", "stageName": "Deploy", "actionName": "Release" },
+                { "actionExecutionId": "a3", "stageName": "Build", "actionName": "Compile" },
+            ],
+        }),
+    );
+
+    let details = aws::fetch_action_executions(&server.clients(), "api", "Build", "Compile", 10)
+        .await
+        .unwrap();
+
+    let ids = details
+        .runs
+        .iter()
+        .map(|execution| execution.detail.action_execution_id.as_deref().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["a1", "a3"]);
+    assert!(!details.cut_off);
+}
+
+#[tokio::test]
+async fn fetches_every_page_of_an_executions_actions() {
+    let server = MockServer::start().await;
+    server.respond(
+        "ListActionExecutions",
+        json!({
+            "actionExecutionDetails": [{ "actionExecutionId": "a1", "stageName": "Build", "actionName": "Compile" }],
+            "nextToken": "2",
+        }),
+    );
+    server.respond(
+        "ListActionExecutions",
+        json!({
+            "actionExecutionDetails": [{ "actionExecutionId": "a2", "stageName": "Prod", "actionName": "Deploy" }],
+        }),
+    );
+
+    let details = aws::fetch_execution_actions(&server.clients(), "api", "0f5b7c2e")
+        .await
+        .unwrap();
+
+    assert_eq!(details.len(), 2);
+    let requests = server.requests("ListActionExecutions");
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1]["nextToken"], "2");
+    assert_eq!(requests[1]["filter"]["pipelineExecutionId"], "0f5b7c2e");
+}
+
+// an approval that doesn't run often can be a long way back among a busy pipeline's other actions
+#[tokio::test]
+async fn pages_through_for_an_actions_executions_until_it_has_enough() {
+    let server = MockServer::start().await;
+    let page = |id: &str, action: &str, next_token: Option<&str>| {
+        json!({
+            "actionExecutionDetails": [
+                { "actionExecutionId": id, "stageName": "Prod", "actionName": action, "status": "Succeeded" },
+            ],
+            "nextToken": next_token,
+        })
+    };
+    server.respond("ListActionExecutions", page("a1", "Approve", Some("2")));
+    server.respond("ListActionExecutions", page("a2", "Deploy", Some("3")));
+    server.respond("ListActionExecutions", page("a3", "Approve", Some("4")));
+
+    let details = aws::fetch_action_executions(&server.clients(), "api", "Prod", "Approve", 2)
+        .await
+        .unwrap();
+
+    assert_eq!(details.runs.len(), 2);
+    assert!(!details.cut_off);
+    let requests = server.requests("ListActionExecutions");
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[2]["nextToken"], "3");
+}
+
+#[tokio::test]
+async fn says_when_it_gave_up_looking_for_an_actions_executions() {
+    let server = MockServer::start().await;
+    for token in 1..=5 {
+        server.respond(
+            "ListActionExecutions",
+            json!({
+                "actionExecutionDetails": [
+                    { "actionExecutionId": "b", "stageName": "Build", "actionName": "Compile" },
+                ],
+                "nextToken": token.to_string(),
+            }),
+        );
+    }
+
+    let details = aws::fetch_action_executions(&server.clients(), "api", "Prod", "Approve", 10)
+        .await
+        .unwrap();
+
+    assert!(details.runs.is_empty());
+    assert!(details.cut_off);
+    assert_eq!(server.requests("ListActionExecutions").len(), 5);
+}
+
+#[tokio::test]
+async fn starts_an_execution_with_its_variables() {
+    let server = MockServer::start().await;
+    server.respond(
+        "StartPipelineExecution",
+        json!({ "pipelineExecutionId": "0f5b7c2e-1111-2222-3333-444455556666" }),
+    );
+    let variables = vec![("version".to_owned(), "1.2.3".to_owned())];
+
+    let id = aws::start_execution(&server.clients(), "api", &variables)
+        .await
+        .unwrap();
+
+    assert_eq!(id, "0f5b7c2e-1111-2222-3333-444455556666");
+    assert_eq!(
+        server.requests("StartPipelineExecution"),
+        vec![json!({ "name": "api", "variables": [{ "name": "version", "value": "1.2.3" }] })]
+    );
+}
+
+#[tokio::test]
+async fn leaves_variables_out_when_there_are_none() {
+    let server = MockServer::start().await;
+    server.respond(
+        "StartPipelineExecution",
+        json!({ "pipelineExecutionId": "0f5b7c2e" }),
+    );
+
+    aws::start_execution(&server.clients(), "legacy", &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        server.requests("StartPipelineExecution"),
+        vec![json!({ "name": "legacy" })]
+    );
+}
+
+#[tokio::test]
+async fn reports_an_executions_status() {
+    let server = MockServer::start().await;
+    server.respond(
+        "GetPipelineExecution",
+        json!({ "pipelineExecution": { "status": "Succeeded", "variables": [] } }),
+    );
+
+    let status = aws::fetch_execution_status(&server.clients(), "api", "0f5b7c2e")
+        .await
+        .unwrap();
+
+    assert_eq!(status, "Succeeded");
+    assert_eq!(
+        server.requests("GetPipelineExecution"),
+        vec![json!({ "pipelineName": "api", "pipelineExecutionId": "0f5b7c2e" })]
+    );
+}
+
+#[tokio::test]
+async fn passes_on_errors_from_our_own_calls() {
+    let server = MockServer::start().await;
+    server.fail(
+        "GetPipelineState",
+        400,
+        "PipelineNotFoundException",
+        "The account with id '123456789012' does not include a pipeline with the name 'missing'",
+    );
+
+    let error = aws::fetch_state(&server.clients(), "missing")
+        .await
+        .unwrap_err()
+        .to_string();
+
+    assert!(
+        error.contains("GetPipelineState failed with 400"),
+        "{}",
+        error
+    );
+    assert!(error.contains("does not include a pipeline"), "{}", error);
+}
+
+#[tokio::test]
+async fn passes_on_errors_from_rusoto_calls() {
+    let server = MockServer::start().await;
+    server.fail(
+        "ListPipelineExecutions",
+        400,
+        "PipelineNotFoundException",
+        "No pipeline called missing",
+    );
+
+    let error = aws::fetch_executions(&server.clients(), "missing")
+        .await
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("No pipeline called missing"), "{}", error);
+}
+
+#[tokio::test]
+async fn recovers_once_throttling_stops() {
+    let server = MockServer::start().await;
+    server.fail("ListPipelines", 400, "ThrottlingException", "Rate exceeded");
+    server.respond("ListPipelines", json!({ "pipelines": [{ "name": "api" }] }));
+    let clients = server.clients();
+
+    let error = aws::list_pipeline_names(&clients).await.unwrap_err();
+    let names = aws::list_pipeline_names(&clients).await.unwrap();
+
+    assert!(error.to_string().contains("Rate exceeded"), "{}", error);
+    assert_eq!(names, vec!["api"]);
+}
+
+#[tokio::test]
+async fn says_what_it_was_asked_for_that_it_has_nothing_for() {
+    let server = MockServer::start().await;
+
+    let error = aws::fetch_state(&server.clients(), "api")
+        .await
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("nothing for GetPipelineState"), "{}", error);
+}
+
+#[tokio::test]
+async fn counts_the_calls_made_and_how_often_they_had_to_be_retried() {
+    let server = MockServer::start().await;
+    server.fail("ListPipelines", 400, "ThrottlingException", "Rate exceeded");
+    server.fail("ListPipelines", 400, "ThrottlingException", "Rate exceeded");
+    server.respond("ListPipelines", json!({ "pipelines": [] }));
+    server.respond("GetPipelineState", json!({ "stageStates": [] }));
+    let clients = server.clients();
+
+    for _ in 0..3 {
+        let _ = aws::list_pipeline_names(&clients).await;
+    }
+    aws::fetch_state(&clients, "api").await.unwrap();
+
+    let apis = clients.calls.summary();
+    let summary = apis
+        .iter()
+        .map(|api| (api.api.as_str(), api.calls, api.failed, api.retries))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        vec![
+            ("codepipeline GetPipelineState", 1, 0, 0),
+            ("codepipeline ListPipelines", 3, 2, 2),
+        ]
+    );
+    assert_eq!(clients.calls.per_minute(), 4);
+}
+
+#[tokio::test]
+async fn keeps_each_calls_request_and_response_with_the_secrets_taken_out() {
+    let server = MockServer::start().await;
+    server.respond(
+        "GetPipelineState",
+        json!({
+            "stageStates": [{
+                "stageName": "Prod",
+                "latestExecution": { "pipelineExecutionId": "0f5b7c2e", "status": "InProgress" },
+                "actionStates": [{
+                    "actionName": "Approve",
+                    "latestExecution": { "status": "InProgress", "token": "approval-token-1234" },
+                }],
+            }],
+        }),
+    );
+    server.respond("PutApprovalResult", json!({}));
+    let clients = server.clients();
+
+    aws::submit_approval(&clients, "api", "Prod", "Approve", true, "Looks good")
+        .await
+        .unwrap();
+
+    let (lines, dropped) = clients.calls.lines();
+    let text = lines.join("\n");
+    assert_eq!(dropped, 0);
+    assert!(
+        lines[0].contains("codepipeline GetPipelineState answered 200"),
+        "{}",
+        text
+    );
+    assert!(text.contains("\"stageName\": \"Prod\""), "{}", text);
+    assert!(
+        text.contains("codepipeline PutApprovalResult answered 200"),
+        "{}",
+        text
+    );
+    assert!(text.contains("\"summary\": \"Looks good\""), "{}", text);
+    assert!(!text.contains("approval-token-1234"), "{}", text);
+    assert!(text.contains("\"token\": \"[redacted]\""), "{}", text);
+}
+
+#[tokio::test]
+async fn reads_the_execution_mode_off_the_definition() {
+    let server = MockServer::start().await;
+    let pipeline = |mode: Option<&str>| {
+        let mut pipeline =
+            json!({ "name": "api", "roleArn": "arn:aws:iam::123456789012:role/api", "stages": [] });
+        if let Some(mode) = mode {
+            pipeline["executionMode"] = json!(mode);
+        }
+        json!({ "pipeline": pipeline })
+    };
+    server.respond("GetPipeline", pipeline(Some("PARALLEL")));
+    server.respond("GetPipeline", pipeline(None));
+
+    let parallel = aws::fetch_definition(&server.clients(), "api")
+        .await
+        .unwrap();
+    // older pipelines don't say, and supersede
+    let older = aws::fetch_definition(&server.clients(), "api")
+        .await
+        .unwrap();
+
+    assert_eq!(parallel.execution_mode, aws::ExecutionMode::Parallel);
+    assert_eq!(older.execution_mode, aws::ExecutionMode::Superseded);
+}
+
+#[tokio::test]
+async fn reads_each_stages_conditions() {
+    let server = MockServer::start().await;
+    server.respond(
+        "GetPipeline",
+        json!({ "pipeline": {
+            "name": "api",
+            "roleArn": "arn:aws:iam::123456789012:role/api",
+            "stages": [
+                { "name": "Build", "actions": [] },
+                {
+                    "name": "Prod",
+                    "actions": [],
+                    "beforeEntry": { "conditions": [{ "result": "FAIL", "rules": [{
+                        "name": "quiet",
+                        "ruleTypeId": { "category": "Rule", "owner": "AWS", "provider": "CloudWatchAlarm", "version": "1" },
+                        "configuration": { "AlarmName": "api-5xx", "WaitTime": "1" },
+                    }]}]},
+                    "onFailure": { "result": "ROLLBACK" },
+                },
+            ],
+        }}),
+    );
+
+    let definition = aws::fetch_definition(&server.clients(), "api")
+        .await
+        .unwrap();
+
+    // rusoto's side of the stages is all still there
+    assert_eq!(definition.declaration.stages.len(), 2);
+    let prod = &definition.conditions[1];
+    assert!(definition.conditions[0].is_empty());
+    assert_eq!(prod.name, "Prod");
+    let rule = prod.rule("quiet").unwrap();
+    assert_eq!(rule.rule_type_id.provider, "CloudWatchAlarm");
+    assert_eq!(rule.configuration["AlarmName"], "api-5xx");
+    assert_eq!(
+        prod.on_failure.as_ref().unwrap().result.as_deref(),
+        Some("ROLLBACK")
+    );
+}
@@ -0,0 +1,218 @@
+// a parallel pipeline's running executions each getting their own view of the stages, and ] and [ going round
+// them
+use codepipeline_status::app::{App, Command, View};
+use codepipeline_status::aws::{
+    self, ActionExecutionDetailV2, ConcurrentExecution, ExecutionMode, Pipeline, PipelineState,
+};
+use codepipeline_status::mock::MockServer;
+use crossterm::event::KeyCode;
+use rusoto_codepipeline::PipelineExecutionSummary;
+use serde_json::json;
+
+const NEWER: &str = "7d1f0a52-2c3b-4e5f-8a9b-0c1d2e3f4a5b";
+const OLDER: &str = "3a9c8b7d-6e5f-4a3b-9c8d-7e6f5a4b3c2d";
+
+fn state() -> PipelineState {
+    serde_json::from_str(include_str!("fixtures/state.json")).unwrap()
+}
+
+// the fixture's first two executions, both still going
+fn running() -> Vec<PipelineExecutionSummary> {
+    let mut executions: Vec<PipelineExecutionSummary> =
+        serde_json::from_str(include_str!("fixtures/executions.json")).unwrap();
+    for execution in executions.iter_mut().take(2) {
+        execution.status = Some("InProgress".to_owned());
+    }
+    executions
+}
+
+// answered by execution, the calls going out at once
+fn respond_for_newer(server: &MockServer) {
+    server.respond_to(
+        "ListActionExecutions",
+        json!({ "filter": { "pipelineExecutionId": NEWER } }),
+        json!({ "actionExecutionDetails": [
+            { "pipelineExecutionId": NEWER, "stageName": "Build", "actionName": "Compile", "status": "InProgress" },
+            { "pipelineExecutionId": NEWER, "stageName": "Source", "actionName": "GitHub", "status": "Succeeded" },
+        ]}),
+    );
+}
+
+fn respond_for_older(server: &MockServer) {
+    server.respond_to(
+        "ListActionExecutions",
+        json!({ "filter": { "pipelineExecutionId": OLDER } }),
+        json!({ "actionExecutionDetails": [
+            { "pipelineExecutionId": OLDER, "stageName": "Build", "actionName": "UnitTests", "status": "Succeeded" },
+            { "pipelineExecutionId": OLDER, "stageName": "Build", "actionName": "Compile", "status": "Succeeded" },
+            { "pipelineExecutionId": OLDER, "stageName": "Source", "actionName": "GitHub", "status": "Succeeded" },
+        ]}),
+    );
+}
+
+fn stage_status<'a>(concurrent: &'a ConcurrentExecution, stage: &str) -> Option<&'a str> {
+    concurrent
+        .stage_states
+        .iter()
+        .find(|state| state.state.stage_name.as_deref() == Some(stage))?
+        .state
+        .latest_execution
+        .as_ref()
+        .map(|execution| execution.status.as_str())
+}
+
+#[tokio::test]
+async fn follows_each_running_execution() {
+    let server = MockServer::start().await;
+    respond_for_newer(&server);
+    respond_for_older(&server);
+
+    let concurrent = aws::fetch_concurrent(
+        &server.clients(),
+        "payments-prod",
+        &state().stage_states,
+        &running(),
+        &[],
+        None,
+    )
+    .await;
+
+    // the finished third one isn't asked about
+    let mut asked = server
+        .requests("ListActionExecutions")
+        .iter()
+        .map(|request| request["filter"]["pipelineExecutionId"].clone())
+        .collect::<Vec<_>>();
+    asked.sort_by_key(|id| id.to_string());
+    assert_eq!(asked, vec![json!(OLDER), json!(NEWER)]);
+    // in the order they're listed, however the calls came back
+    assert_eq!(concurrent.len(), 2);
+    assert_eq!(concurrent[0].execution_id, NEWER);
+    // the newer one hasn't got as far as Prod, which the older one went through last
+    assert_eq!(stage_status(&concurrent[0], "Prod"), None);
+    assert_eq!(stage_status(&concurrent[1], "Prod"), Some("Succeeded"));
+    // where the newer one failed the build, the older one passed it
+    assert_eq!(stage_status(&concurrent[0], "Build"), Some("Failed"));
+    assert_eq!(stage_status(&concurrent[1], "Build"), Some("Succeeded"));
+}
+
+// the pinned execution's details already came with the poll, so only the other one's fetched
+#[tokio::test]
+async fn reuses_the_pinned_executions_details() {
+    let server = MockServer::start().await;
+    respond_for_older(&server);
+    let pinned: Vec<ActionExecutionDetailV2> = serde_json::from_value(json!([
+        { "pipelineExecutionId": NEWER, "stageName": "Build", "actionName": "Compile", "status": "InProgress" },
+        { "pipelineExecutionId": NEWER, "stageName": "Source", "actionName": "GitHub", "status": "Succeeded" },
+    ]))
+    .unwrap();
+
+    let concurrent = aws::fetch_concurrent(
+        &server.clients(),
+        "payments-prod",
+        &state().stage_states,
+        &running(),
+        &[],
+        Some((NEWER, &pinned)),
+    )
+    .await;
+
+    let requests = server.requests("ListActionExecutions");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["filter"]["pipelineExecutionId"], OLDER);
+    assert_eq!(concurrent.len(), 2);
+    assert_eq!(stage_status(&concurrent[0], "Prod"), None);
+    assert_eq!(stage_status(&concurrent[1], "Prod"), Some("Succeeded"));
+}
+
+// one that can't be fetched this time keeps what it had, rather than the whole poll going
+#[tokio::test]
+async fn keeps_the_last_of_one_that_fails() {
+    let server = MockServer::start().await;
+    respond_for_older(&server);
+    server.fail(
+        "ListActionExecutions",
+        400,
+        "ThrottlingException",
+        "Rate exceeded",
+    );
+    let previous = vec![ConcurrentExecution {
+        execution_id: NEWER.to_owned(),
+        stage_states: state().stage_states,
+    }];
+
+    let concurrent = aws::fetch_concurrent(
+        &server.clients(),
+        "payments-prod",
+        &state().stage_states,
+        &running(),
+        &previous,
+        None,
+    )
+    .await;
+
+    assert_eq!(concurrent.len(), 2);
+    assert_eq!(concurrent[0], previous[0]);
+    assert_eq!(concurrent[1].execution_id, OLDER);
+    assert_eq!(stage_status(&concurrent[1], "Build"), Some("Succeeded"));
+}
+
+fn parallel() -> App {
+    let stage_states = state().stage_states;
+    let mut app = App::new("123456789012".to_owned(), "us-west-2".to_owned(), vec![]);
+    app.pipeline = Pipeline {
+        name: "payments-prod".to_owned(),
+        stage_states: stage_states.clone(),
+        executions: running(),
+        concurrent: [NEWER, OLDER]
+            .iter()
+            .map(|id| ConcurrentExecution {
+                execution_id: id.to_string(),
+                stage_states: stage_states.clone(),
+            })
+            .collect(),
+    };
+    app.execution_mode = ExecutionMode::Parallel;
+    app.view = View::Stages;
+    app
+}
+
+#[test]
+fn goes_round_the_running_executions() {
+    let mut app = parallel();
+    let focus = |app: &mut App, key| {
+        let command = app.on_key(KeyCode::Char(key));
+        // as the main loop would
+        if let Some(Command::Focus(id)) = &command {
+            app.pinned_execution = id.clone();
+        }
+        command
+    };
+
+    assert_eq!(
+        focus(&mut app, ']'),
+        Some(Command::Focus(Some(NEWER.to_owned())))
+    );
+    assert_eq!(
+        focus(&mut app, ']'),
+        Some(Command::Focus(Some(OLDER.to_owned())))
+    );
+    // back to the latest state between the last and the first
+    assert_eq!(focus(&mut app, ']'), Some(Command::Focus(None)));
+    assert_eq!(
+        focus(&mut app, '['),
+        Some(Command::Focus(Some(OLDER.to_owned())))
+    );
+}
+
+#[test]
+fn only_for_parallel_pipelines_with_something_running() {
+    let mut app = parallel();
+    app.execution_mode = ExecutionMode::Queued;
+    assert_eq!(app.on_key(KeyCode::Char(']')), None);
+
+    let mut app = parallel();
+    app.pipeline.concurrent.clear();
+    assert_eq!(app.on_key(KeyCode::Char(']')), None);
+    assert!(app.toasts[0].message.contains("Nothing's running"));
+}
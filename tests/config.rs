@@ -0,0 +1,89 @@
+// `config export` and `config import`: what counts as someone's own and stays out of a shared config, and
+// what's kept from the config an import replaces
+use codepipeline_status::config::{with_secrets, without_secrets, Config};
+
+fn toml(contents: &str) -> toml::Value {
+    toml::from_str(contents).unwrap()
+}
+
+#[test]
+fn exports_everything_but_the_secrets() {
+    let mut config = toml(
+        r#"
+        read_only = true
+
+        [github]
+        token = "ghp_mine"
+
+        [bitbucket]
+        username = "me"
+        app_password = "hunter2"
+
+        [notifications]
+        slack_webhook = "https://hooks.slack.com/services/T0/B0/x"
+        failure_minutes = 30
+
+        [[notifications.rules]]
+        on = ["Failed"]
+        channels = ["slack"]
+        "#,
+    );
+
+    let removed = without_secrets(&mut config);
+
+    assert_eq!(
+        removed,
+        vec![
+            "github.token",
+            "bitbucket.username",
+            "bitbucket.app_password",
+            "notifications.slack_webhook",
+        ]
+    );
+    // nothing's left of these, so they go altogether
+    assert!(config.get("github").is_none());
+    assert!(config.get("bitbucket").is_none());
+    let exported = toml::to_string(&config).unwrap();
+    assert!(!exported.contains("hooks.slack.com"), "{}", exported);
+    // and what's left is still a config
+    let config: Config = toml::from_str(&exported).unwrap();
+    assert!(config.read_only);
+    assert_eq!(config.notifications.failure_minutes, 30);
+    assert_eq!(config.notifications.rules.len(), 1);
+}
+
+#[test]
+fn an_import_keeps_your_own_secrets() {
+    let mut shared = toml(
+        r#"
+        [notifications]
+        webhook = "https://example.com/team-deploys"
+
+        [[notifications.rules]]
+        on = ["Failed"]
+        channels = ["slack", "webhook"]
+        "#,
+    );
+    let current = toml(
+        r#"
+        [github]
+        token = "ghp_mine"
+
+        [notifications]
+        slack_webhook = "https://hooks.slack.com/services/T0/B0/mine"
+        webhook = "https://example.com/my-deploys"
+        "#,
+    );
+
+    let kept = with_secrets(&mut shared, &current);
+
+    // a webhook the shared config sets on purpose stays as it is
+    assert_eq!(kept, vec!["github.token", "notifications.slack_webhook"]);
+    let config: Config = toml::from_str(&toml::to_string(&shared).unwrap()).unwrap();
+    assert_eq!(config.github.token.as_deref(), Some("ghp_mine"));
+    assert_eq!(
+        config.notifications.webhook.as_deref(),
+        Some("https://example.com/team-deploys")
+    );
+    assert_eq!(config.notifications.rules.len(), 1);
+}
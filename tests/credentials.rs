@@ -0,0 +1,97 @@
+// expired credentials: telling them apart from any other failure, and the one popup offering to sign in again
+// rather than an error every refresh, and credentials that couldn't be found being looked for again
+use async_trait::async_trait;
+use codepipeline_status::app::{App, Command, Modal};
+use codepipeline_status::credentials::{self, CachedCredentials};
+use crossterm::event::KeyCode;
+use rusoto_core::credential::{AwsCredentials, CredentialsError, ProvideAwsCredentials};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const EXPIRED: &str = r#"Request ID: Some("4c3f...") Body: {"__type":"ExpiredTokenException","message":"The security token included in the request is expired"}"#;
+
+fn expired_app() -> App {
+    let mut app = App::new("123456789012".to_owned(), "us-west-2".to_owned(), vec![]);
+    app.reauth = "aws sso login --profile cdk".to_owned();
+    app.last_error = Some(EXPIRED.to_owned());
+    app
+}
+
+#[test]
+fn knows_an_expired_token_when_it_sees_one() {
+    assert!(credentials::expired(EXPIRED));
+    assert!(credentials::expired(
+        "<Error><Code>ExpiredToken</Code><Message>The security token included in the request is expired</Message></Error>"
+    ));
+    assert!(!credentials::expired(
+        "The security token included in the request is invalid"
+    ));
+    assert!(!credentials::expired("Pipeline not found"));
+}
+
+#[test]
+fn pops_up_once_and_signs_in_again_on_yes() {
+    let mut app = expired_app();
+
+    assert!(app.check_credentials());
+    assert!(matches!(app.modal, Some(Modal::Reauth)));
+    assert!(!app.last_error.as_deref().unwrap().contains("ExpiredToken"));
+    assert_eq!(
+        app.on_key(KeyCode::Char('y')),
+        Some(Command::Reauthenticate)
+    );
+}
+
+#[test]
+fn not_now_leaves_a_way_back() {
+    let mut app = expired_app();
+    app.check_credentials();
+    assert_eq!(app.on_key(KeyCode::Esc), None);
+    assert!(app.modal.is_none());
+
+    // the next refresh failing the same way doesn't bring it straight back
+    app.last_error = Some(EXPIRED.to_owned());
+    assert!(!app.check_credentials());
+    assert!(app.modal.is_none());
+    assert!(app
+        .last_error
+        .as_deref()
+        .unwrap()
+        .contains("e signs in again"));
+
+    app.on_key(KeyCode::Char('e'));
+    assert!(matches!(app.modal, Some(Modal::Reauth)));
+}
+
+// fails the first time it's asked, like the metadata service timing out or asking before `aws sso login`
+struct FailsOnce {
+    asked: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl ProvideAwsCredentials for FailsOnce {
+    async fn credentials(&self) -> Result<AwsCredentials, CredentialsError> {
+        if self.asked.fetch_add(1, Ordering::SeqCst) == 0 {
+            return Err(CredentialsError::new("Couldn't find any credentials"));
+        }
+        Ok(AwsCredentials::new("AKIA", "secret", None, None))
+    }
+}
+
+#[tokio::test]
+async fn a_failure_isnt_kept() {
+    let asked = Arc::new(AtomicUsize::new(0));
+    let cached = CachedCredentials::new(FailsOnce {
+        asked: asked.clone(),
+    });
+
+    assert!(cached.credentials().await.is_err());
+    assert_eq!(
+        cached.credentials().await.unwrap().aws_access_key_id(),
+        "AKIA"
+    );
+    // and what it found is
+    assert!(cached.credentials().await.is_ok());
+    assert_eq!(asked.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,38 @@
+// the demo's made-up pipelines, which a bug report can only point at by their seed if the seed always makes them
+// the same way
+use codepipeline_status::aws::{self, Clients};
+use codepipeline_status::demo;
+
+// every pipeline's executions, newest first, by ID and status
+async fn world(seed: u64) -> Vec<(String, Vec<(String, String)>)> {
+    let clients = Clients::local(demo::serve(seed, "us-west-2").await.unwrap()).unwrap();
+    let mut world = vec![];
+    for name in aws::list_pipeline_names(&clients).await.unwrap() {
+        let executions = aws::fetch_executions(&clients, &name)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|execution| {
+                (
+                    execution.pipeline_execution_id.unwrap_or_default(),
+                    execution.status.unwrap_or_default(),
+                )
+            })
+            .collect();
+        world.push((name, executions));
+    }
+    world
+}
+
+#[tokio::test]
+async fn the_same_seed_makes_the_same_pipelines() {
+    let first = world(42).await;
+
+    assert!(!first.is_empty());
+    assert_eq!(first, world(42).await);
+}
+
+#[tokio::test]
+async fn another_seed_makes_others() {
+    assert_ne!(world(42).await, world(43).await);
+}
@@ -0,0 +1,411 @@
+// the dashboard drawn from fixed state into an in-memory terminal and compared against the frames in
+// tests/snapshots, so a layout that's gone wrong (stages squashed together, panes overlapping, ...) shows up
+// as a diff. UPDATE_SNAPSHOTS=1 cargo test writes out whatever's drawn now instead, for a change that's meant
+use chrono::Local;
+use codepipeline_status::app::{App, Command, Form, Modal, TypedConfirmation, View};
+use codepipeline_status::aws::{
+    ActionExecutionDetailV2, ConcurrentExecution, ExecutionMode, Pipeline, PipelineListing,
+    PipelineState,
+};
+use codepipeline_status::git::{CommitInfo, SourceChange};
+use codepipeline_status::history::Bookmark;
+use codepipeline_status::stats;
+use codepipeline_status::ui;
+use rusoto_codepipeline::PipelineExecutionSummary;
+use tui::backend::TestBackend;
+use tui::Terminal;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn listings() -> Vec<PipelineListing> {
+    let listing = |name: &str, status: Option<&str>, tags: &[(&str, &str)]| PipelineListing {
+        name: name.to_owned(),
+        tags: tags
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        durations: vec![400, 380, 420, 900, 410],
+        latest_status: status.map(str::to_owned),
+        latest_execution_id: status.map(|_| "0f5b7c2e-1111-2222-3333-444455556666".to_owned()),
+        last_run: status.map(|_| 1_700_000_420.0),
+        running_since: None,
+    };
+    vec![
+        listing("payments-prod", Some("Failed"), &[("team", "payments")]),
+        listing(
+            "payments-staging",
+            Some("Succeeded"),
+            &[("team", "payments")],
+        ),
+        listing("search-prod", Some("Superseded"), &[("team", "search")]),
+        listing("search-staging", Some("Stopped"), &[]),
+        listing("sandbox", None, &[]),
+    ]
+}
+
+fn opened() -> App {
+    let state: PipelineState = serde_json::from_str(include_str!("fixtures/state.json")).unwrap();
+    let executions: Vec<PipelineExecutionSummary> =
+        serde_json::from_str(include_str!("fixtures/executions.json")).unwrap();
+    let mut app = App::new(
+        "123456789012".to_owned(),
+        "us-west-2".to_owned(),
+        listings(),
+    );
+    app.pipeline = Pipeline {
+        name: "payments-prod".to_owned(),
+        stage_states: state.stage_states,
+        executions,
+        concurrent: vec![],
+    };
+    app.view = View::Stages;
+    app
+}
+
+fn render(app: &App, width: u16, height: u16) -> String {
+    // timestamps are drawn in local time, which has to be the same wherever the tests run
+    env::set_var("TZ", "UTC");
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|f| ui::draw(f, app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let area = buffer.area();
+    let mut frame = String::new();
+    for y in 0..area.height {
+        let line = (0..area.width)
+            .map(|x| buffer.get(x, y).symbol.as_str())
+            .collect::<String>();
+        frame.push_str(line.trim_end());
+        frame.push('\n');
+    }
+    frame
+}
+
+fn assert_snapshot(name: &str, frame: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name));
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() || !path.exists() {
+        fs::write(&path, frame).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap();
+    if expected != frame {
+        panic!(
+            "{} doesn't match {} (UPDATE_SNAPSHOTS=1 if the change is meant)\n\nexpected:\n{}\ndrawn:\n{}",
+            name,
+            path.display(),
+            expected,
+            frame
+        );
+    }
+}
+
+#[test]
+fn pipelines() {
+    let app = App::new(
+        "123456789012".to_owned(),
+        "us-west-2".to_owned(),
+        listings(),
+    );
+    assert_snapshot("pipelines", &render(&app, 100, 16));
+}
+
+#[test]
+fn fleet() {
+    let mut app = App::new(
+        "123456789012".to_owned(),
+        "us-west-2".to_owned(),
+        listings(),
+    );
+    app.view = View::Fleet;
+    // two hours in, long enough not to tick over to another minute while it's drawn
+    app.pipelines[2].latest_status = Some("InProgress".to_owned());
+    app.pipelines[2].running_since = Some(Local::now().timestamp() as f64 - 2.0 * 3600.0);
+    assert_snapshot("fleet", &render(&app, 100, 14));
+}
+
+// narrower than a single cell, which gets cut off at the border
+#[test]
+fn fleet_narrow() {
+    let mut app = App::new(
+        "123456789012".to_owned(),
+        "us-west-2".to_owned(),
+        listings(),
+    );
+    app.view = View::Fleet;
+    assert_snapshot("fleet_narrow", &render(&app, 20, 14));
+}
+
+#[test]
+fn stages() {
+    assert_snapshot("stages", &render(&opened(), 120, 36));
+}
+
+// the narrowest a laptop split is likely to go, where the stage boxes have least to work with
+#[test]
+fn stages_narrow() {
+    assert_snapshot("stages_narrow", &render(&opened(), 60, 36));
+}
+
+#[test]
+fn stages_problems_only() {
+    let mut app = opened();
+    app.problems_only = true;
+    assert_snapshot("stages_problems_only", &render(&app, 100, 30));
+}
+
+#[test]
+fn stages_filtered() {
+    let mut app = opened();
+    app.stage_filter.hide = vec!["Source".to_owned()];
+    assert_snapshot("stages_filtered", &render(&app, 100, 30));
+}
+
+// two of them at once, each with a row of its own over the stages, and the stages focused on the older one
+#[test]
+fn stages_parallel() {
+    let mut app = opened();
+    app.execution_mode = ExecutionMode::Parallel;
+    let now = Local::now().timestamp() as f64;
+    for (execution, hours) in app.pipeline.executions.iter_mut().zip(&[2.0, 3.0]) {
+        execution.status = Some("InProgress".to_owned());
+        execution.start_time = Some(now - hours * 3600.0);
+    }
+    let newer = "7d1f0a52-2c3b-4e5f-8a9b-0c1d2e3f4a5b";
+    let older = "3a9c8b7d-6e5f-4a3b-9c8d-7e6f5a4b3c2d";
+    let mut pinned = app.pipeline.clone();
+    let details: Vec<ActionExecutionDetailV2> = serde_json::from_value(serde_json::json!([
+        { "pipelineExecutionId": older, "stageName": "Source", "actionName": "GitHub", "status": "Succeeded" },
+        { "pipelineExecutionId": older, "stageName": "Build", "actionName": "Compile", "status": "Succeeded" },
+        { "pipelineExecutionId": older, "stageName": "Build", "actionName": "UnitTests", "status": "Succeeded" },
+    ]))
+    .unwrap();
+    pinned.pin_to(older, &details);
+    app.pipeline.concurrent = vec![
+        ConcurrentExecution {
+            execution_id: newer.to_owned(),
+            stage_states: app.pipeline.stage_states.clone(),
+        },
+        ConcurrentExecution {
+            execution_id: older.to_owned(),
+            stage_states: pinned.stage_states.clone(),
+        },
+    ];
+    app.pipeline.stage_states = pinned.stage_states;
+    app.pinned_execution = Some(older.to_owned());
+    assert_snapshot("stages_parallel", &render(&app, 120, 36));
+}
+
+// Prod's alarm gate keeping the execution out, which isn't the same as the deploy failing
+#[test]
+fn stages_gated() {
+    let mut app = opened();
+    app.stage_conditions = serde_json::from_value(serde_json::json!([
+        { "name": "Source" },
+        { "name": "Build" },
+        {
+            "name": "Prod",
+            "beforeEntry": { "conditions": [{ "result": "FAIL", "rules": [
+                { "name": "quiet", "ruleTypeId": { "provider": "CloudWatchAlarm" }, "configuration": { "AlarmName": "payments-5xx" } },
+                { "name": "window", "ruleTypeId": { "provider": "DeploymentWindow" } },
+            ]}]},
+            "onFailure": { "result": "ROLLBACK" },
+        },
+    ]))
+    .unwrap();
+    let prod = &mut app.pipeline.stage_states[2];
+    prod.state.latest_execution.as_mut().unwrap().status = "Failed".to_owned();
+    prod.before_entry_condition_state = serde_json::from_value(serde_json::json!({
+        "latestExecution": { "status": "Failed" },
+        "conditionStates": [{ "ruleStates": [
+            { "ruleName": "quiet", "latestExecution": { "status": "Failed", "summary": "In ALARM" } },
+            { "ruleName": "window", "latestExecution": { "status": "Succeeded" } },
+        ]}],
+    }))
+    .unwrap();
+    assert_snapshot("stages_gated", &render(&app, 120, 36));
+}
+
+#[test]
+fn history() {
+    let mut app = opened();
+    app.view = View::History;
+    app.selected_execution = 1;
+    assert_snapshot("history", &render(&app, 120, 14));
+}
+
+#[test]
+fn history_with_notes() {
+    let mut app = opened();
+    app.view = View::History;
+    app.notes.insert(
+        "3a9c8b7d-6e5f-4a3b-9c8d-7e6f5a4b3c2d".to_owned(),
+        "hotfix for incident-42".to_owned(),
+    );
+    assert_snapshot("history_with_notes", &render(&app, 140, 14));
+}
+
+#[test]
+fn bookmarks() {
+    let mut app = opened();
+    app.view = View::Bookmarks;
+    app.bookmarks = vec![
+        Bookmark {
+            pipeline: "search-prod".to_owned(),
+            execution_id: "0f5b7c2e-1111-2222-3333-444455556666".to_owned(),
+            status: None,
+            started: None,
+            seconds: None,
+            note: None,
+        },
+        Bookmark {
+            pipeline: "payments-prod".to_owned(),
+            execution_id: "3a9c8b7d-6e5f-4a3b-9c8d-7e6f5a4b3c2d".to_owned(),
+            status: Some("Succeeded".to_owned()),
+            started: Some(1_699_989_000.0),
+            seconds: Some(1000),
+            note: Some("hotfix for incident-42".to_owned()),
+        },
+    ];
+    app.selected_bookmark = 1;
+    assert_snapshot("bookmarks", &render(&app, 100, 10));
+}
+
+#[test]
+fn typed_confirmation() {
+    let mut app = opened();
+    app.modal = Some(Modal::Typed(TypedConfirmation {
+        action: "disable-transition",
+        command: Command::DisableTransition {
+            stage: "Prod".to_owned(),
+            reason: "Change freeze".to_owned(),
+        },
+        expected: "Prod".to_owned(),
+        typed: "Pr".to_owned(),
+    }));
+    assert_snapshot("typed_confirmation", &render(&app, 100, 30));
+}
+
+#[test]
+fn approval_form() {
+    let mut app = opened();
+    let mut form = Form::approve("Prod".to_owned(), "Deploy".to_owned());
+    form.fields[0].value = "Checked the canary".to_owned();
+    app.modal = Some(Modal::Form(form));
+    assert_snapshot("approval_form", &render(&app, 100, 30));
+}
+
+#[test]
+fn credentials_expired() {
+    let mut app = opened();
+    app.reauth = "aws sso login --profile cdk".to_owned();
+    app.last_error = Some(
+        "ExpiredTokenException: the security token included in the request is expired".to_owned(),
+    );
+    app.check_credentials();
+    assert_snapshot("credentials_expired", &render(&app, 100, 30));
+}
+
+// with nothing called yet, as the latencies of real calls would never draw the same twice
+#[test]
+fn debug_overlay() {
+    let mut app = opened();
+    app.debug = true;
+    assert_snapshot("debug_overlay", &render(&app, 100, 30));
+}
+
+#[test]
+fn comparison() {
+    let mut app = opened();
+    app.definition = serde_json::from_value(serde_json::json!({
+        "name": "payments-prod",
+        "roleArn": "arn:aws:iam::123456789012:role/pipeline",
+        "stages": [
+            { "name": "Source", "actions": [] },
+            { "name": "Build", "actions": [] },
+            { "name": "Prod", "actions": [] },
+        ],
+    }))
+    .unwrap();
+    let details = |id: &str, runs: &[(&str, &str, f64, f64)]| -> Vec<ActionExecutionDetailV2> {
+        runs.iter()
+            .map(|(stage, status, start, end)| {
+                serde_json::from_value(serde_json::json!({
+                    "pipelineExecutionId": id,
+                    "stageName": stage,
+                    "actionName": "Action",
+                    "status": status,
+                    "startTime": start,
+                    "lastUpdateTime": end,
+                }))
+                .unwrap()
+            })
+            .collect()
+    };
+    let base = "3a9c8b7d-6e5f-4a3b-9c8d-7e6f5a4b3c2d";
+    let head = "7d1f0a52-2c3b-4e5f-8a9b-0c1d2e3f4a5b";
+    app.view = View::Compare;
+    app.compared = Some((base.to_owned(), head.to_owned()));
+    app.stage_comparison = Some(Ok(stats::compare_stages(
+        &app.definition,
+        &details(
+            base,
+            &[
+                ("Source", "Succeeded", 0.0, 20.0),
+                ("Build", "Succeeded", 20.0, 320.0),
+                ("Prod", "Succeeded", 320.0, 1000.0),
+            ],
+        ),
+        &details(
+            head,
+            &[
+                ("Source", "Succeeded", 0.0, 25.0),
+                ("Build", "Failed", 25.0, 420.0),
+            ],
+        ),
+    )));
+    app.comparison = Some(Ok(vec![
+        SourceChange {
+            action_name: "App".to_owned(),
+            base: Some("4f1c2a9e7b".to_owned()),
+            head: Some("9e8d7c6b5a".to_owned()),
+            // as though CodeCommit's history had a lot more in between than we'd follow back
+            commits: Some(vec![(
+                "9e8d7c6b5a".to_owned(),
+                CommitInfo {
+                    message: "Add retries to the payment client".to_owned(),
+                    author: Some("Dana".to_owned()),
+                    branch: Some("main".to_owned()),
+                },
+            )]),
+            truncated: true,
+        },
+        SourceChange {
+            action_name: "Config".to_owned(),
+            base: Some("a1b2c3d4e5".to_owned()),
+            head: Some("a1b2c3d4e5".to_owned()),
+            commits: None,
+            truncated: false,
+        },
+    ]));
+    assert_snapshot("comparison", &render(&app, 100, 20));
+}
+
+// what went wrong said in each pane rather than loading forever
+#[test]
+fn comparison_unavailable() {
+    let mut app = opened();
+    app.view = View::Compare;
+    app.compared = Some((
+        "3a9c8b7d-6e5f-4a3b-9c8d-7e6f5a4b3c2d".to_owned(),
+        "7d1f0a52-2c3b-4e5f-8a9b-0c1d2e3f4a5b".to_owned(),
+    ));
+    app.stage_comparison = Some(Err(
+        "Couldn't fetch the executions' stages: Rate exceeded".to_owned()
+    ));
+    app.comparison = Some(Err("Execution not found in the recent history".to_owned()));
+    assert_snapshot("comparison_unavailable", &render(&app, 100, 20));
+}
@@ -0,0 +1,78 @@
+// [guardrails]: which ARNs can change which pipelines, and a pipeline with one never being changed until the
+// credentials have been checked against it
+use codepipeline_status::aws;
+use codepipeline_status::config::GuardrailsConfig;
+use codepipeline_status::guardrails::guarded;
+use codepipeline_status::mock::MockServer;
+use serde_json::json;
+
+const DEPLOYER: &str = "arn:aws:sts::123456789012:assumed-role/ProdDeployer/alice";
+const DEVELOPER: &str = "arn:aws:sts::123456789012:assumed-role/Developer/alice";
+
+fn guardrails() -> GuardrailsConfig {
+    toml::from_str(
+        r#"
+        [[pipelines]]
+        pipeline = "*-prod"
+        roles = ["arn:aws:sts::*:assumed-role/ProdDeployer/*"]
+
+        [[pipelines]]
+        pipeline = "frozen"
+        "#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn only_the_allowed_roles_change_production() {
+    let guardrails = guardrails();
+
+    assert!(guardrails.allows("payments-prod", DEPLOYER));
+    assert!(!guardrails.allows("payments-prod", DEVELOPER));
+    // no guardrail, anyone's
+    assert!(guardrails.allows("payments-staging", DEVELOPER));
+    // one without any roles is nobody's
+    assert!(!guardrails.allows("frozen", DEPLOYER));
+}
+
+#[tokio::test]
+async fn goes_ahead_without_a_guardrail() {
+    let server = MockServer::start().await;
+    server.respond(
+        "StartPipelineExecution",
+        json!({ "pipelineExecutionId": "e1" }),
+    );
+    let clients = server.clients();
+
+    let start = aws::start_execution(&clients, "payments-staging", &[]);
+    let id = guarded(&clients, &guardrails(), "payments-staging", "start", start)
+        .await
+        .unwrap();
+
+    assert_eq!(id, "e1");
+}
+
+#[tokio::test]
+async fn nothing_changes_until_the_identity_checks_out() {
+    let server = MockServer::start().await;
+    server.respond(
+        "StartPipelineExecution",
+        json!({ "pipelineExecutionId": "e1" }),
+    );
+    let clients = server.clients();
+
+    // the mock only speaks CodePipeline, so who we are can't be found out
+    let start = aws::start_execution(&clients, "payments-prod", &[]);
+    let error = guarded(&clients, &guardrails(), "payments-prod", "start", start)
+        .await
+        .unwrap_err();
+
+    assert!(
+        error.to_string().starts_with(
+            "Couldn't check who we're signed in as, so not going to start payments-prod"
+        ),
+        "{}",
+        error
+    );
+    assert!(server.requests("StartPipelineExecution").is_empty());
+}
@@ -0,0 +1,69 @@
+// the history database's bookmarks and notes, which have to outlast the runs it forgets as they get old
+use codepipeline_status::history::History;
+use rusoto_codepipeline::PipelineExecutionSummary;
+
+use std::env;
+use std::fs;
+use std::process;
+
+fn execution(id: &str, start: f64) -> PipelineExecutionSummary {
+    PipelineExecutionSummary {
+        pipeline_execution_id: Some(id.to_owned()),
+        status: Some("Succeeded".to_owned()),
+        start_time: Some(start),
+        last_update_time: Some(start + 600.0),
+        ..PipelineExecutionSummary::default()
+    }
+}
+
+// one test for the lot, the database's whereabouts come from the environment every test shares
+#[test]
+fn bookmarked_executions_are_kept_past_the_history_window() {
+    let dir = env::temp_dir().join(format!("codepipeline-status-history-{}", process::id()));
+    env::set_var("XDG_DATA_HOME", &dir);
+    // long before any window
+    let long_ago = 1_500_000_000.0;
+
+    let history = History::open(90).unwrap();
+    history
+        .record_executions(
+            "api",
+            &[
+                execution("kept", long_ago),
+                execution("forgotten", long_ago),
+            ],
+        )
+        .unwrap();
+    history.set_bookmark("api", "kept", true).unwrap();
+    history
+        .set_note("api", "kept", "  rollback of #123 ")
+        .unwrap();
+    drop(history);
+
+    // opening it again is when the old runs go
+    let history = History::open(90).unwrap();
+    history.set_bookmark("api", "forgotten", true).unwrap();
+    let bookmarks = history.bookmarks().unwrap();
+    assert_eq!(bookmarks.len(), 2);
+    let kept = bookmarks
+        .iter()
+        .find(|bookmark| bookmark.execution_id == "kept")
+        .unwrap();
+    assert_eq!(kept.status.as_deref(), Some("Succeeded"));
+    assert_eq!(kept.seconds, Some(600));
+    assert_eq!(kept.note.as_deref(), Some("rollback of #123"));
+    let forgotten = bookmarks
+        .iter()
+        .find(|bookmark| bookmark.execution_id == "forgotten")
+        .unwrap();
+    assert_eq!(forgotten.status, None);
+
+    // taking the bookmark and the note off
+    history.set_bookmark("api", "kept", false).unwrap();
+    history.set_note("api", "kept", "").unwrap();
+    assert!(history.notes("api").unwrap().is_empty());
+    assert_eq!(history.bookmarks().unwrap().len(), 1);
+
+    drop(history);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,115 @@
+// macros from the config: what their keys and steps can be, and a macro's steps going through the app one at a
+// time the way the main loop runs them
+use codepipeline_status::app::{App, Command, View};
+use codepipeline_status::aws::{Pipeline, PipelineState};
+use codepipeline_status::config::MacroConfig;
+use codepipeline_status::macros::{self, Step};
+use crossterm::event::KeyCode;
+
+fn config(key: &str, steps: &[&str]) -> MacroConfig {
+    MacroConfig {
+        key: key.to_owned(),
+        name: None,
+        steps: steps.iter().map(|step| step.to_string()).collect(),
+    }
+}
+
+fn opened() -> App {
+    let state: PipelineState = serde_json::from_str(include_str!("fixtures/state.json")).unwrap();
+    let mut app = App::new("123456789012".to_owned(), "us-west-2".to_owned(), vec![]);
+    app.pipeline = Pipeline {
+        name: "payments-prod".to_owned(),
+        stage_states: state.stage_states,
+        executions: vec![],
+        concurrent: vec![],
+    };
+    app.view = View::Stages;
+    app
+}
+
+#[test]
+fn understands_keys_pipelines_and_failed_actions() {
+    let (parsed, problems) = macros::parse(&[config(
+        "M",
+        &[
+            "pipeline payments-prod",
+            "failed",
+            "enter",
+            "l",
+            "F12",
+            "space",
+        ],
+    )]);
+
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(parsed[0].key, KeyCode::Char('M'));
+    assert_eq!(parsed[0].name, "M");
+    assert_eq!(
+        parsed[0].steps,
+        vec![
+            Step::Pipeline("payments-prod".to_owned()),
+            Step::Failed,
+            Step::Key(KeyCode::Enter),
+            Step::Key(KeyCode::Char('l')),
+            Step::Key(KeyCode::F(12)),
+            Step::Key(KeyCode::Char(' ')),
+        ]
+    );
+}
+
+#[test]
+fn leaves_out_macros_it_cant_run() {
+    let (parsed, problems) = macros::parse(&[
+        config("ctrl-x", &["h"]),
+        config("M", &["h", "open the logs"]),
+        config("F5", &["h"]),
+    ]);
+
+    assert_eq!(parsed.len(), 1);
+    assert_eq!(parsed[0].key, KeyCode::F(5));
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+    assert!(problems[1].contains("open the logs"), "{:?}", problems);
+}
+
+#[test]
+fn runs_its_steps_one_at_a_time() {
+    let mut app = opened();
+    let (parsed, _) = macros::parse(&[config(
+        "M",
+        &["pipeline payments-prod", "h", "s", "failed", "enter"],
+    )]);
+    app.macros = parsed;
+
+    // the key itself only queues the steps up, in place of anything it did before
+    assert_eq!(app.on_key(KeyCode::Char('M')), None);
+    assert_eq!(app.running_macro.as_deref(), Some("M"));
+    assert_eq!(
+        app.next_macro_step(),
+        Some(Command::OpenPipeline("payments-prod".to_owned()))
+    );
+    assert_eq!(app.next_macro_step(), None);
+    assert_eq!(app.view, View::History);
+    app.next_macro_step();
+    assert_eq!(app.view, View::Stages);
+    // Build's second action is the one that failed
+    app.next_macro_step();
+    assert_eq!((app.selected_stage, app.selected_action), (1, 1));
+    app.next_macro_step();
+    assert_eq!(app.view, View::Action);
+    assert!(app.macro_steps.is_empty());
+    assert_eq!(app.running_macro, None);
+}
+
+#[test]
+fn stops_when_nothing_has_failed() {
+    let mut app = App::new("123456789012".to_owned(), "us-west-2".to_owned(), vec![]);
+    let (parsed, _) = macros::parse(&[config("M", &["failed", "enter", "l"])]);
+    app.macros = parsed;
+
+    app.on_key(KeyCode::Char('M'));
+    app.next_macro_step();
+
+    assert!(app.macro_steps.is_empty());
+    assert_eq!(app.running_macro, None);
+    assert!(app.toasts[0].message.contains("nothing's failed"));
+}
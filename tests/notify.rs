@@ -0,0 +1,233 @@
+// which of the [notifications] rules a pipeline's execution changing status ends up going through, and when
+// quiet hours and a flapping pipeline hold them back
+use chrono::NaiveTime;
+use codepipeline_status::config::{Channel, NotificationsConfig, QuietHours};
+use codepipeline_status::notify::rules::{self, Change};
+use codepipeline_status::notify::throttle::{self, FailureThrottle};
+
+use std::time::{Duration, Instant};
+
+fn config(rules: &str) -> NotificationsConfig {
+    toml::from_str(rules).unwrap()
+}
+
+fn change<'a>(pipeline: &'a str, status: &'a str, previous: Option<&'a str>) -> Change<'a> {
+    Change {
+        pipeline,
+        status,
+        previous,
+    }
+}
+
+#[test]
+fn only_failures_and_recoveries() {
+    let config = config(
+        r#"
+        [[rules]]
+        on = ["Failed", "Recovered"]
+        channels = ["desktop"]
+        "#,
+    );
+
+    let sent = |status, previous| rules::channels(&config, &change("api", status, previous));
+
+    assert_eq!(sent("Failed", Some("Succeeded")), vec![Channel::Desktop]);
+    assert_eq!(sent("Succeeded", Some("Failed")), vec![Channel::Desktop]);
+    assert_eq!(sent("Succeeded", Some("Succeeded")), vec![]);
+    assert_eq!(sent("Succeeded", None), vec![]);
+    assert_eq!(sent("InProgress", Some("Failed")), vec![]);
+}
+
+#[test]
+fn the_first_rule_for_the_pipeline_decides() {
+    let config = config(
+        r#"
+        slack_webhook = "https://hooks.slack.com/services/T0/B0/x"
+        webhook = "https://example.com/deploys"
+
+        [[rules]]
+        pipeline = "*-prod"
+        on = ["InProgress", "Succeeded", "Failed"]
+        channels = ["slack", "webhook"]
+
+        [[rules]]
+        pipeline = "sandbox"
+
+        [[rules]]
+        on = ["Failed"]
+        channels = ["desktop"]
+        "#,
+    );
+
+    let sent = |pipeline, status| rules::channels(&config, &change(pipeline, status, None));
+
+    assert_eq!(
+        sent("payments-prod", "InProgress"),
+        vec![Channel::Slack, Channel::Webhook]
+    );
+    assert_eq!(sent("payments-prod", "Stopped"), vec![]);
+    assert_eq!(sent("payments-staging", "Failed"), vec![Channel::Desktop]);
+    assert_eq!(sent("payments-staging", "Succeeded"), vec![]);
+    // a rule without any statuses keeps the pipeline quiet rather than falling through to the next
+    assert_eq!(sent("sandbox", "Failed"), vec![]);
+    assert!(rules::problems(&config).is_empty());
+}
+
+#[test]
+fn nothing_without_rules() {
+    let config = NotificationsConfig::default();
+
+    assert_eq!(
+        rules::channels(&config, &change("api", "Failed", None)),
+        vec![]
+    );
+}
+
+#[test]
+fn points_out_rules_that_can_never_send_anything() {
+    let config = config(
+        r#"
+        [[rules]]
+        pipeline = "api"
+        on = ["Failure"]
+        channels = ["slack"]
+        "#,
+    );
+
+    let problems = rules::problems(&config);
+
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+    assert!(problems[0].contains("Failure"), "{:?}", problems);
+    assert!(problems[1].contains("slack_webhook"), "{:?}", problems);
+}
+
+#[test]
+fn quiet_hours_run_past_midnight() {
+    let quiet = QuietHours {
+        from: "22:00".to_owned(),
+        to: "07:00".to_owned(),
+        batch: false,
+    };
+    let at = |time| throttle::is_quiet(&quiet, NaiveTime::parse_from_str(time, "%H:%M").unwrap());
+
+    assert!(at("22:00"));
+    assert!(at("03:30"));
+    assert!(!at("07:00"));
+    assert!(!at("12:00"));
+}
+
+#[test]
+fn quiet_hours_within_a_day() {
+    let quiet = QuietHours {
+        from: "12:00".to_owned(),
+        to: "13:00".to_owned(),
+        batch: true,
+    };
+    let at = |time| throttle::is_quiet(&quiet, NaiveTime::parse_from_str(time, "%H:%M").unwrap());
+
+    assert!(at("12:30"));
+    assert!(!at("11:59"));
+    assert!(!at("13:00"));
+}
+
+#[test]
+fn points_out_quiet_hours_that_dont_parse() {
+    let config = config(
+        r#"
+        quiet_hours = { from = "10pm", to = "07:00" }
+        "#,
+    );
+
+    let problems = rules::problems(&config);
+
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert!(problems[0].contains("10pm"), "{:?}", problems);
+}
+
+#[test]
+fn a_flapping_pipeline_is_heard_from_once_per_window() {
+    let window = Duration::from_secs(30 * 60);
+    let start = Instant::now();
+    let minutes = |minutes: u64| start + Duration::from_secs(minutes * 60);
+    let mut throttle = FailureThrottle::default();
+
+    assert!(throttle.allow("api", "e1", window, minutes(0)));
+    assert!(!throttle.allow("api", "e2", window, minutes(5)));
+    assert!(!throttle.allow("api", "e3", window, minutes(10)));
+    // every pipeline has a window of its own
+    assert!(throttle.allow("web", "w1", window, minutes(10)));
+    assert!(throttle.expired(window, minutes(20)).is_empty());
+
+    let still = throttle.expired(window, minutes(30));
+    assert_eq!(still.len(), 1);
+    assert_eq!(still[0].pipeline, "api");
+    assert_eq!(still[0].held, 2);
+    assert_eq!(still[0].execution_id, "e3");
+
+    // the summary opened another window, and web's has closed with nothing in it
+    assert!(!throttle.allow("api", "e4", window, minutes(35)));
+    assert!(throttle.expired(window, minutes(41)).is_empty());
+    assert!(throttle.allow("web", "w2", window, minutes(42)));
+    let still = throttle.expired(window, minutes(60));
+    assert_eq!(still.len(), 1);
+    assert_eq!(still[0].held, 1);
+}
+
+// no "still failing" once it's passed again
+#[test]
+fn a_recovery_closes_the_window() {
+    let window = Duration::from_secs(30 * 60);
+    let start = Instant::now();
+    let minutes = |minutes: u64| start + Duration::from_secs(minutes * 60);
+    let mut throttle = FailureThrottle::default();
+
+    assert!(throttle.allow("api", "e1", window, minutes(0)));
+    assert!(!throttle.allow("api", "e2", window, minutes(5)));
+    throttle.recovered("api");
+
+    assert!(throttle.expired(window, minutes(30)).is_empty());
+    // and failing again afterwards is news
+    assert!(throttle.allow("api", "e4", window, minutes(31)));
+}
+
+#[test]
+fn no_window_sends_every_failure() {
+    let mut throttle = FailureThrottle::default();
+    let now = Instant::now();
+
+    assert!(throttle.allow("api", "e1", Duration::default(), now));
+    assert!(throttle.allow("api", "e2", Duration::default(), now));
+}
+
+#[test]
+fn a_sound_on_failure() {
+    let config = config(
+        r#"
+        sound = "/nowhere/alarm.wav"
+
+        [[rules]]
+        pipeline = "*-prod"
+        on = ["Failed"]
+        channels = ["sound", "desktop"]
+        "#,
+    );
+
+    assert_eq!(
+        rules::channels(&config, &change("api-prod", "Failed", None)),
+        vec![Channel::Sound, Channel::Desktop]
+    );
+    assert_eq!(
+        rules::channels(&config, &change("api-staging", "Failed", None)),
+        vec![]
+    );
+    assert_eq!(
+        rules::problems(&config),
+        vec!["The notification rule for *-prod plays /nowhere/alarm.wav, which isn't there"]
+    );
+    // the terminal's bell needs nothing set up
+    let bell = NotificationsConfig {
+        sound: None,
+        ..config
+    };
+    assert!(rules::problems(&bell).is_empty());
+}
@@ -0,0 +1,235 @@
+// how often [polling] has each pipeline polled, what a pipeline it doesn't mention gets, and the fleet's
+// pipelines being polled and listed again by the supervisor too
+use codepipeline_status::aws::{ExecutionMode, Pipeline, PipelineListing, PipelineState};
+use codepipeline_status::config::PollingConfig;
+use codepipeline_status::mock::MockServer;
+use codepipeline_status::poller::{intervals, Executions, Intervals, Supervisor};
+use serde_json::json;
+use tokio::time::{delay_for, timeout};
+
+use std::time::Duration;
+
+fn every(active: u64, idle: u64) -> Intervals {
+    Intervals {
+        active: Duration::from_secs(active),
+        idle: Duration::from_secs(idle),
+    }
+}
+
+#[test]
+fn the_first_matching_pipeline_decides() {
+    let config: PollingConfig = toml::from_str(
+        r#"
+        [[pipelines]]
+        pipeline = "*-prod"
+        seconds = 10
+
+        [[pipelines]]
+        pipeline = "sandbox-*"
+        seconds = 60
+        idle_seconds = 300
+
+        [[pipelines]]
+        pipeline = "broken"
+        seconds = 0
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(intervals(&config, "payments-prod"), every(10, 10));
+    assert_eq!(intervals(&config, "sandbox-ledger"), every(60, 300));
+    assert_eq!(intervals(&config, "payments-staging"), every(5, 90));
+    // not flat out
+    assert_eq!(intervals(&config, "broken"), every(1, 1));
+}
+
+// a fleet pipeline gets a task of its own like the open one, and what it finds goes into its listing
+#[tokio::test]
+async fn watches_a_fleet_pipeline() {
+    let server = MockServer::start().await;
+    server.respond("GetPipelineState", json!({ "stageStates": [] }));
+    server.respond(
+        "ListPipelineExecutions",
+        json!({ "pipelineExecutionSummaries": [
+            { "pipelineExecutionId": "e1", "status": "Failed", "startTime": 1700000000, "lastUpdateTime": 1700000420 },
+        ]}),
+    );
+    let polling: PollingConfig = toml::from_str(
+        r#"
+        [[pipelines]]
+        pipeline = "api"
+        seconds = 1
+        "#,
+    )
+    .unwrap();
+    let mut supervisor = Supervisor::new(server.clients(), polling);
+    let mut listing = PipelineListing::new("api".to_owned());
+
+    supervisor.watch_listing(&listing);
+    // already watched, so not another task polling it
+    supervisor.watch_listing(&listing);
+    let update = timeout(Duration::from_secs(5), supervisor.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    supervisor.unwatch_all();
+
+    assert_eq!(update.pipeline, "api");
+    listing.follow(&update.result.unwrap().executions);
+    assert_eq!(listing.latest_status.as_deref(), Some("Failed"));
+    assert_eq!(listing.latest_execution_id.as_deref(), Some("e1"));
+    assert_eq!(server.requests("GetPipelineState").len(), 1);
+}
+
+// listing the fleet again only fetches listings for the pipelines that are new since
+#[tokio::test]
+async fn relists_in_the_background() {
+    let server = MockServer::start().await;
+    server.respond(
+        "ListPipelines",
+        json!({ "pipelines": [{ "name": "api" }, { "name": "web" }] }),
+    );
+    server.respond("ListTagsForResource", json!({ "tags": [] }));
+    server.respond(
+        "ListPipelineExecutions",
+        json!({ "pipelineExecutionSummaries": [
+            { "pipelineExecutionId": "e1", "status": "Succeeded", "startTime": 1700000000, "lastUpdateTime": 1700000420 },
+        ]}),
+    );
+    let mut supervisor = Supervisor::new(server.clients(), PollingConfig::default());
+
+    supervisor.relist(
+        "123456789012",
+        vec!["api".to_owned(), "gone".to_owned()],
+        None,
+    );
+    let listed = timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(listed) = supervisor.try_listed() {
+                return listed;
+            }
+            delay_for(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(listed.names, vec!["api", "web"]);
+    assert_eq!(listed.new.len(), 1);
+    assert_eq!(listed.new[0].name, "web");
+    assert_eq!(listed.new[0].latest_status.as_deref(), Some("Succeeded"));
+    assert_eq!(server.requests("ListTagsForResource").len(), 1);
+}
+
+async fn next(supervisor: &mut Supervisor) {
+    timeout(Duration::from_secs(5), supervisor.recv())
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+// the stages view needs nothing but the state, so that's all a poll asks for until a view wants the executions
+#[tokio::test]
+async fn a_quiet_watch_is_one_call_a_poll() {
+    let server = MockServer::start().await;
+    let state: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/state.json")).unwrap();
+    server.respond("GetPipelineState", state);
+    server.respond(
+        "ListPipelineExecutions",
+        json!({ "pipelineExecutionSummaries": [] }),
+    );
+    let polling: PollingConfig = toml::from_str(
+        r#"
+        [[pipelines]]
+        pipeline = "payments-prod"
+        seconds = 1
+        "#,
+    )
+    .unwrap();
+    let mut supervisor = Supervisor::new(server.clients(), polling);
+    let pipeline = Pipeline {
+        name: "payments-prod".to_owned(),
+        ..Pipeline::default()
+    };
+
+    supervisor.watch(pipeline, None, ExecutionMode::default());
+    // the state's changed from nothing at all, which still isn't reason enough
+    next(&mut supervisor).await;
+    assert_eq!(server.requests("GetPipelineState").len(), 1);
+    assert!(server.requests("ListPipelineExecutions").is_empty());
+
+    // the history view opening has it polled straight away, executions and all
+    supervisor.set_executions("payments-prod", Executions::Always);
+    next(&mut supervisor).await;
+    supervisor.unwatch_all();
+    assert_eq!(server.requests("ListPipelineExecutions").len(), 1);
+}
+
+fn stages(statuses: &[(&str, &str)]) -> Pipeline {
+    let mut state: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/state.json")).unwrap();
+    for (stage, (id, status)) in state["stageStates"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .zip(statuses)
+    {
+        stage["latestExecution"] = json!({ "pipelineExecutionId": id, "status": status });
+    }
+    let state: PipelineState = serde_json::from_value(state).unwrap();
+    Pipeline {
+        name: "payments-prod".to_owned(),
+        stage_states: state.stage_states,
+        ..Pipeline::default()
+    }
+}
+
+#[test]
+fn the_stages_say_how_the_latest_run_went() {
+    let run = |statuses: &[(&str, &str)]| stages(statuses).latest_run();
+    let latest = |status: &str| Some(("new".to_owned(), status.to_owned()));
+
+    assert_eq!(
+        run(&[
+            ("new", "Succeeded"),
+            ("new", "Failed"),
+            ("old", "Succeeded")
+        ]),
+        latest("Failed")
+    );
+    assert_eq!(
+        run(&[
+            ("new", "Succeeded"),
+            ("new", "InProgress"),
+            ("old", "Succeeded")
+        ]),
+        latest("InProgress")
+    );
+    // between stages, waiting for a transition say
+    assert_eq!(
+        run(&[
+            ("new", "Succeeded"),
+            ("new", "Succeeded"),
+            ("old", "Succeeded")
+        ]),
+        latest("InProgress")
+    );
+    assert_eq!(
+        run(&[
+            ("new", "Succeeded"),
+            ("new", "Succeeded"),
+            ("new", "Succeeded")
+        ]),
+        latest("Succeeded")
+    );
+    assert_eq!(
+        run(&[
+            ("new", "Succeeded"),
+            ("new", "Stopped"),
+            ("old", "Succeeded")
+        ]),
+        latest("Stopped")
+    );
+}
@@ -0,0 +1,124 @@
+// stages running past what the [sla] allows them: which limit applies, when a stage counts as over, and that
+// each stage run's only alerted on once
+use codepipeline_status::app::App;
+use codepipeline_status::aws::{Pipeline, StageStateV2};
+use codepipeline_status::config::{Channel, NotificationsConfig, SlaConfig};
+use codepipeline_status::notify::rules::{self, Change};
+
+const STARTED: f64 = 1_700_000_000.0;
+
+fn sla() -> SlaConfig {
+    toml::from_str(
+        r#"
+        [[stages]]
+        pipeline = "payments-prod"
+        stage = "Deploy"
+        minutes = 15
+
+        [[stages]]
+        stage = "Deploy*"
+        minutes = 30
+        "#,
+    )
+    .unwrap()
+}
+
+fn stage(name: &str, execution_id: &str, status: &str) -> StageStateV2 {
+    serde_json::from_value(serde_json::json!({
+        "stageName": name,
+        "latestExecution": { "pipelineExecutionId": execution_id, "status": status },
+    }))
+    .unwrap()
+}
+
+fn deploying(status: &str) -> App {
+    let mut app = App::new("123456789012".to_owned(), "us-west-2".to_owned(), vec![]);
+    app.sla = sla();
+    app.pipeline = Pipeline {
+        name: "payments-prod".to_owned(),
+        stage_states: vec![
+            stage("Build", "e1", "Succeeded"),
+            stage("Deploy", "e1", status),
+        ],
+        executions: vec![],
+        concurrent: vec![],
+    };
+    app.stage_starts
+        .insert(("e1".to_owned(), "Deploy".to_owned()), STARTED);
+    app
+}
+
+#[test]
+fn the_first_matching_limit_applies() {
+    let sla = sla();
+
+    assert_eq!(sla.limit("payments-prod", "Deploy"), Some(15 * 60));
+    assert_eq!(sla.limit("payments-staging", "Deploy"), Some(30 * 60));
+    assert_eq!(sla.limit("payments-prod", "DeployCanary"), Some(30 * 60));
+    assert_eq!(sla.limit("payments-prod", "Build"), None);
+}
+
+#[test]
+fn a_running_stage_is_over_once_its_limit_has_passed() {
+    let app = deploying("InProgress");
+    let deploy = &app.pipeline.stage_states[1];
+
+    assert_eq!(app.over_sla(deploy, STARTED + 15.0 * 60.0), None);
+    assert_eq!(
+        app.over_sla(deploy, STARTED + 16.0 * 60.0),
+        Some((16 * 60, 15 * 60))
+    );
+    // nothing's asked of a stage without a limit, or one that's finished
+    assert_eq!(
+        app.over_sla(&app.pipeline.stage_states[0], STARTED + 3600.0),
+        None
+    );
+    let finished = deploying("Succeeded");
+    assert_eq!(
+        finished.over_sla(&finished.pipeline.stage_states[1], STARTED + 3600.0),
+        None
+    );
+}
+
+#[test]
+fn each_stage_run_is_only_alerted_on_once() {
+    let mut app = deploying("InProgress");
+
+    assert!(app.newly_over_sla(STARTED + 60.0).is_empty());
+    assert_eq!(
+        app.newly_over_sla(STARTED + 20.0 * 60.0),
+        vec![("Deploy".to_owned(), "e1".to_owned(), 20 * 60, 15 * 60)]
+    );
+    assert!(app.newly_over_sla(STARTED + 25.0 * 60.0).is_empty());
+}
+
+#[test]
+fn overdue_goes_wherever_the_rules_on_it_say() {
+    let config: NotificationsConfig = toml::from_str(
+        r#"
+        [[rules]]
+        pipeline = "*-prod"
+        on = ["Failed", "Overdue"]
+        channels = ["desktop"]
+
+        [[rules]]
+        on = ["Failed"]
+        channels = ["desktop"]
+        "#,
+    )
+    .unwrap();
+    let overdue = |pipeline| {
+        rules::channels(
+            &config,
+            &Change {
+                pipeline,
+                status: "Overdue",
+                previous: None,
+            },
+        )
+    };
+
+    assert_eq!(overdue("payments-prod"), vec![Channel::Desktop]);
+    assert_eq!(overdue("payments-staging"), vec![]);
+    assert!(rules::problems(&config).is_empty());
+}
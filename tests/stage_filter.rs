@@ -0,0 +1,19 @@
+// the [stages] filter: hide taking a stage off even when only would have let it through
+use codepipeline_status::config::StagesConfig;
+
+#[test]
+fn hide_wins_over_only() {
+    let filter: StagesConfig = toml::from_str(
+        r#"
+        hide = ["Source", "*Canary"]
+        only = ["Deploy*", "Source"]
+        "#,
+    )
+    .unwrap();
+
+    assert!(filter.shows("DeployEu"));
+    assert!(!filter.shows("DeployCanary"));
+    assert!(!filter.shows("Source"));
+    assert!(!filter.shows("Build"));
+    assert!(StagesConfig::default().shows("Build"));
+}
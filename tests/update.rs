@@ -0,0 +1,65 @@
+// what self-update goes by: which asset is ours, its line in SHA256SUMS and whether a release is newer
+use codepipeline_status::update::{asset_name, checksum_for, newer};
+
+const CHECKSUMS: &str = "\
+0f343b0931126a20f133d67c2b018a3b1e3e9b1c7e3c4c8e2e8f6f4d7c2b1a09  codepipeline-status-x86_64-linux
+9a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f9 *codepipeline-status-aarch64-macos
+";
+
+#[test]
+fn finds_the_checksum_in_text_mode() {
+    assert_eq!(
+        checksum_for(CHECKSUMS, "codepipeline-status-x86_64-linux"),
+        Some("0f343b0931126a20f133d67c2b018a3b1e3e9b1c7e3c4c8e2e8f6f4d7c2b1a09")
+    );
+}
+
+#[test]
+fn finds_the_checksum_in_binary_mode() {
+    assert_eq!(
+        checksum_for(CHECKSUMS, "codepipeline-status-aarch64-macos"),
+        Some("9a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f9")
+    );
+}
+
+#[test]
+fn no_checksum_for_a_missing_file() {
+    assert_eq!(
+        checksum_for(CHECKSUMS, "codepipeline-status-x86_64-windows.exe"),
+        None
+    );
+    // nor for one that's only the start of a listed name
+    assert_eq!(checksum_for(CHECKSUMS, "codepipeline-status-x86_64"), None);
+    assert_eq!(checksum_for("", "codepipeline-status-x86_64-linux"), None);
+}
+
+#[test]
+fn compares_versions_by_their_numbers() {
+    assert!(newer("v1.3.0", "1.2.9"));
+    assert!(newer("1.10.0", "1.9.0"));
+    assert!(newer("v2.0.0", "1.99.99"));
+    assert!(!newer("v1.2.0", "1.3.0"));
+}
+
+#[test]
+fn the_same_version_isnt_newer() {
+    assert!(!newer("v1.2.3", "1.2.3"));
+    assert!(!newer("1.2.3", "1.2.3"));
+}
+
+#[test]
+fn a_pre_release_goes_by_its_numbers() {
+    assert!(newer("v1.3.0-rc.1", "1.2.0"));
+    // not newer than the release it comes before, nor is that release newer than it
+    assert!(!newer("v1.2.0-rc.1", "1.2.0"));
+    assert!(!newer("v1.2.0", "1.2.0-rc.1"));
+}
+
+#[test]
+fn the_asset_is_named_for_this_platform() {
+    let name = asset_name();
+    assert!(name.starts_with("codepipeline-status-"));
+    assert!(name.contains(std::env::consts::ARCH));
+    assert!(name.contains(std::env::consts::OS));
+    assert!(name.ends_with(std::env::consts::EXE_SUFFIX));
+}
@@ -0,0 +1,40 @@
+// --watchlist files: what counts as a comment, and a line having to match a pipeline's whole name
+use codepipeline_status::app::App;
+use codepipeline_status::watchlist::Watchlist;
+
+#[test]
+fn names_and_regexes_with_comments() {
+    let watchlist = Watchlist::parse(
+        "# the payments team's pipelines\n\
+         \n\
+         payments-prod\n\
+         payments-(staging|dev)  # not the sandboxes\n\
+         ledger-[0-9]+\n",
+    )
+    .unwrap();
+
+    assert!(watchlist.matches("payments-prod"));
+    assert!(watchlist.matches("payments-dev"));
+    assert!(watchlist.matches("ledger-2"));
+    // the whole name, not just a part of it
+    assert!(!watchlist.matches("payments-prod-old"));
+    assert!(!watchlist.matches("old-ledger-2"));
+    assert!(!watchlist.matches("payments-sandbox"));
+}
+
+#[test]
+fn a_bad_regex_says_which_line() {
+    let error = Watchlist::parse("payments-prod\nledger-(\n").unwrap_err();
+
+    assert!(error.starts_with("line 2: \"ledger-(\""), "{}", error);
+}
+
+#[test]
+fn everything_is_watched_without_one() {
+    let mut app = App::new("123456789012".to_owned(), "us-west-2".to_owned(), vec![]);
+    assert!(app.watching("anything"));
+
+    app.watchlist = Some(Watchlist::parse("api-.*").unwrap());
+    assert!(app.watching("api-prod"));
+    assert!(!app.watching("web-prod"));
+}